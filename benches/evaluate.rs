@@ -0,0 +1,76 @@
+// Bulk evaluation as batch input and worksheets use it. Compare a change
+// against the last release with `cargo bench -- --save-baseline main` there
+// and `cargo bench -- --baseline main` on the change; criterion flags any
+// benchmark that got slower.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::collections::HashMap;
+
+// The crate is a binary, so the benchmark compiles the domain module, which
+// has no dependencies on the rest of the app, on its own; its unit tests
+// come along unused.
+#[allow(dead_code, unused_imports)]
+#[path = "../src/domain/mod.rs"]
+mod domain;
+
+use domain::engine::EvalSettings;
+use domain::types::AngleMode;
+use domain::{eval, worksheet};
+
+const LINES: usize = 1_000;
+
+fn batch_lines() -> Vec<String> {
+    let templates = [
+        "2 + 3 \u{00d7} 4",
+        "(1.5 + 2.25) \u{00f7} 3 \u{2212} 0.125",
+        "sqrt(2) * sin(45) + cos(30)",
+        "50% of 200",
+        "2^10 - 5!",
+        "ln(10) / log(10)",
+        "99999999999999999 + 1",
+        "12.3 +/- 0.2",
+    ];
+    (0..LINES).map(|i| templates[i % templates.len()].to_string()).collect()
+}
+
+// A sheet where every line depends on the one above, so an edit to the
+// first line re-evaluates all of them.
+fn worksheet_lines() -> Vec<String> {
+    let mut lines = vec!["rate = 1.25".to_string()];
+    for i in 1..LINES {
+        lines.push(match i % 3 {
+            0 => format!("x{} = ans * rate", i),
+            1 => format!("#{} + 1", i),
+            _ => "ans / 2".to_string(),
+        });
+    }
+    lines
+}
+
+fn bench_batch(c: &mut Criterion) {
+    let plugins = HashMap::new();
+    let lines = batch_lines();
+    let inputs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    c.bench_function("evaluate_many 1000 lines", |b| {
+        b.iter(|| eval::evaluate_many(black_box(&inputs), &plugins, EvalSettings::default()))
+    });
+    c.bench_function("evaluate_str 1000 lines", |b| {
+        b.iter(|| {
+            black_box(&inputs)
+                .iter()
+                .map(|input| eval::evaluate_str(input, &plugins, AngleMode::Degrees, true))
+                .collect::<Vec<_>>()
+        })
+    });
+}
+
+fn bench_worksheet(c: &mut Criterion) {
+    let plugins = HashMap::new();
+    let lines = worksheet_lines();
+    c.bench_function("worksheet 1000 dependent lines", |b| {
+        b.iter(|| worksheet::evaluate(black_box(&lines), &plugins, EvalSettings::default()))
+    });
+}
+
+criterion_group!(benches, bench_batch, bench_worksheet);
+criterion_main!(benches);
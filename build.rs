@@ -0,0 +1,40 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+const SOURCE_DIR: &str = "data";
+const MANIFEST: &str = "data/fredulator.gresource.xml";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    // Only the GTK frontend reads the bundle; terminal-only builds should not
+    // need the glib toolchain.
+    if env::var_os("CARGO_FEATURE_GUI").is_none() {
+        return;
+    }
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let target = Path::new(&out_dir).join("fredulator.gresource");
+
+    let status = Command::new("glib-compile-resources")
+        .arg("--sourcedir")
+        .arg(SOURCE_DIR)
+        .arg("--target")
+        .arg(&target)
+        .arg(MANIFEST)
+        .status()
+        .unwrap_or_else(|e| panic!("cannot run glib-compile-resources (install glib2 / libglib2.0-dev-bin): {}", e));
+    assert!(status.success(), "glib-compile-resources failed with {}", status);
+
+    println!("cargo:rerun-if-changed={}", MANIFEST);
+    let deps = Command::new("glib-compile-resources")
+        .arg("--sourcedir")
+        .arg(SOURCE_DIR)
+        .arg("--generate-dependencies")
+        .arg(MANIFEST)
+        .output()
+        .expect("glib-compile-resources --generate-dependencies");
+    for dep in String::from_utf8_lossy(&deps.stdout).split_whitespace() {
+        println!("cargo:rerun-if-changed={}", dep);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::app::message::Message;
+use crate::app::state::AppState;
+use crate::app::update::{update, SideEffect};
+use crate::domain::money;
+use crate::domain::types::{self, AngleMode};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResultSize {
+    Normal,
+    Medium,
+    Small,
+    /// Small, and broken over several lines.
+    Wrapped,
+}
+
+impl ResultSize {
+    fn for_text(text: &str) -> Self {
+        match text.chars().count() {
+            n if n > 24 => Self::Wrapped,
+            n if n > 12 => Self::Small,
+            n if n > 7 => Self::Medium,
+            _ => Self::Normal,
+        }
+    }
+
+    pub fn css_class(self) -> Option<&'static str> {
+        match self {
+            Self::Normal => None,
+            Self::Medium => Some("result-medium"),
+            Self::Small | Self::Wrapped => Some("result-small"),
+        }
+    }
+}
+
+/// The unrounded form of a result whose display text is rounded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExactValue {
+    /// Every digit the float holds.
+    pub digits: String,
+    pub fraction: Option<String>,
+    /// In money mode, the part of a cent the shown amount leaves out.
+    pub lost: Option<String>,
+}
+
+impl ExactValue {
+    fn of(val: f64, money: bool) -> Self {
+        Self {
+            digits: val.to_string(),
+            fraction: types::fraction(val, 1_000_000).map(|(p, q)| format!("{}/{}", p, q)),
+            lost: money.then(|| money::describe_loss(val)).flatten(),
+        }
+    }
+}
+
+/// Everything a frontend needs to repaint the display after a message,
+/// plus the side effects it still has to carry out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayUpdate {
+    pub main: String,
+    pub size: ResultSize,
+    pub secondary: Option<String>,
+    pub preview: Option<String>,
+    /// Set when `main` is a rounded result.
+    pub exact: Option<ExactValue>,
+    pub angle: &'static str,
+    pub effects: Vec<SideEffect>,
+}
+
+impl DisplayUpdate {
+    pub fn from_state(state: &AppState) -> Self {
+        let engine = state.engine();
+        let main = types::group_digits(&engine.main_display_text());
+        Self {
+            size: ResultSize::for_text(&main),
+            main,
+            secondary: engine.show_secondary().then(|| engine.secondary_display_text()),
+            preview: engine.auto_eval().map(|p| format!("\u{2248} {}", p)),
+            exact: engine.rounded_result().map(|val| ExactValue::of(val, engine.money())),
+            angle: match engine.angle_mode() {
+                AngleMode::Degrees => "Deg",
+                AngleMode::Radians => "Rad",
+            },
+            effects: Vec::new(),
+        }
+    }
+}
+
+pub fn reduce(state: &mut AppState, msg: Message) -> DisplayUpdate {
+    let effects = update(state, msg);
+    DisplayUpdate { effects, ..DisplayUpdate::from_state(state) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::types::{BinaryOp, PostfixOp};
+    use crate::services::config::Config;
+
+    fn key(c: char) -> Message {
+        match c {
+            '0'..='9' => Message::Digit(c),
+            '.' => Message::Decimal,
+            '+' => Message::BinaryOp(BinaryOp::Add),
+            '-' => Message::BinaryOp(BinaryOp::Subtract),
+            '*' => Message::BinaryOp(BinaryOp::Multiply),
+            '/' => Message::BinaryOp(BinaryOp::Divide),
+            '^' => Message::BinaryOp(BinaryOp::Power),
+            '%' => Message::PostfixOp(PostfixOp::Percent),
+            '(' => Message::LeftParen,
+            ')' => Message::RightParen,
+            '=' => Message::Equals,
+            'C' => Message::Clear,
+            '<' => Message::Backspace,
+            'n' => Message::ToggleSign,
+            'u' => Message::Undo,
+            other => panic!("no test key for {:?}", other),
+        }
+    }
+
+    fn run(keys: &str) -> DisplayUpdate {
+        let mut state = AppState::new(Config::default(), 0);
+        let mut last = DisplayUpdate::from_state(&state);
+        for c in keys.chars() {
+            last = reduce(&mut state, key(c));
+        }
+        last
+    }
+
+    #[test]
+    fn key_sequences() {
+        let cases = [
+            ("", "0"),
+            ("12", "12"),
+            ("2+3=", "5"),
+            ("2+3*4=", "14"),
+            ("(2+3)*4=", "20"),
+            ("10/4=", "2.5"),
+            ("5/0=", "Division by zero"),
+            ("2^10=", "1024"),
+            ("123<", "12"),
+            ("12C", "0"),
+            ("1.5+1.5=", "3"),
+            ("2+3=*2=", "10"),
+            ("45u", "4"),
+            ("2+3=4", "4"),
+            ("2+3=4+1=", "5"),
+            ("2+3=-1=", "4"),
+            ("2+3=.5", "0.5"),
+            ("2+3==", "8"),
+            ("2*3===", "54"),
+            ("2+3*4==", "56"),
+            ("(2+3)==", "5"),
+            ("2+3==u", "5"),
+            ("=", "0"),
+            ("-=", "-"),
+        ];
+        for (keys, expected) in cases {
+            assert_eq!(run(keys).main, expected, "key sequence {:?}", keys);
+        }
+    }
+
+    #[test]
+    fn repeated_equals_records_each_step() {
+        let mut state = AppState::new(Config::default(), 0);
+        state.config.history.auto_save = false;
+        for c in "=2+3===".chars() {
+            reduce(&mut state, key(c));
+        }
+        let history: Vec<&str> = state.engine().history.iter().map(|e| e.expression.as_str()).collect();
+        assert_eq!(history, ["2+3", "5+3", "8+3"]);
+        assert_eq!(DisplayUpdate::from_state(&state).secondary.as_deref(), Some("8+3="));
+    }
+
+    #[test]
+    fn secondary_and_preview() {
+        let d = run("2+3");
+        assert_eq!(d.secondary, None);
+        assert_eq!(d.preview.as_deref(), Some("\u{2248} 5"));
+
+        let d = run("2+3=");
+        assert_eq!(d.secondary.as_deref(), Some("2+3="));
+        assert_eq!(d.preview, None);
+    }
+
+    #[test]
+    fn rounded_results_carry_the_exact_value() {
+        let d = run("1/3=");
+        assert_eq!(d.main, "0.3333333333");
+        assert_eq!(
+            d.exact,
+            Some(ExactValue { digits: "0.3333333333333333".into(), fraction: Some("1/3".into()), lost: None })
+        );
+        assert_eq!(run("10/4=").exact, None);
+        assert_eq!(run("1/3").exact, None);
+    }
+
+    #[test]
+    fn size_follows_length() {
+        assert_eq!(run("1234567").size, ResultSize::Normal);
+        assert_eq!(run("12345678").size, ResultSize::Medium);
+        assert_eq!(run("1234567890123").size, ResultSize::Small);
+    }
+
+    #[test]
+    fn long_whole_numbers_are_exact_and_grouped() {
+        let d = run("99999999999999999+1=");
+        assert_eq!(d.main, "100\u{2009}000\u{2009}000\u{2009}000\u{2009}000\u{2009}000");
+        assert_eq!(d.size, ResultSize::Small);
+        assert_eq!(d.exact, None);
+        let d = run("123456789123456789*987654321987654321=");
+        assert_eq!(d.main.replace('\u{2009}', ""), "121932631356500531347203169112635269");
+        assert_eq!(d.size, ResultSize::Wrapped);
+    }
+
+    #[test]
+    fn effects_are_forwarded() {
+        let mut state = AppState::new(Config::default(), 0);
+        let d = reduce(&mut state, Message::Digit('1'));
+        assert!(d.effects.contains(&SideEffect::UpdateDisplay));
+        let d = reduce(&mut state, Message::NewTab);
+        assert!(d.effects.contains(&SideEffect::UpdateTabs));
+        assert_eq!(d.main, "0");
+    }
+}
@@ -15,6 +15,15 @@ pub enum Message {
     LeftParen,
     RightParen,
     EE,
+    PercentQuery(crate::domain::types::PercentQuery, f64, f64),
+    LoadExpression(String),
+    /// Loads snippet `n`, its placeholders filled with the current value.
+    InsertSnippet(usize),
+    /// Replaces the snippets once the editor has saved them.
+    SetSnippets(Vec<crate::domain::snippet::Snippet>),
+    /// Asks the frontend for the clipboard text, which comes back as `Paste`.
+    RequestPaste,
+    Paste(String),
 
     MemoryClear,
     MemoryRecall,
@@ -23,6 +32,11 @@ pub enum Message {
     MemoryStore,
 
     ToggleAngleMode,
+    ToggleIntervalArithmetic,
+    ToggleMoneyMode,
+    /// A character for `Engine::input_char`, for keys with no action of their
+    /// own such as the brackets of `[lo, hi]`.
+    Type(char),
     Undo,
 
     NewTab,
@@ -35,6 +49,9 @@ pub enum Message {
 
     ToggleScientific,
     ToggleTheme,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
 
     ToggleHistory,
     ToggleMemory,
@@ -42,12 +59,25 @@ pub enum Message {
     PinResult,
     SearchHistory(String),
     ClearHistory,
+    ToggleStar(usize),
+    LabelHistory(usize, String),
+    /// Loads entry `n`'s expression back for editing; "=" then adds a new entry.
+    EditHistory(usize),
     ExportHistoryJson,
     ExportHistoryCsv,
+    ExportHistoryTo(std::path::PathBuf),
+    CopyAs(crate::domain::ast::CopyStyle),
+    ShowSteps,
+    OpenPalette,
+    ConvertResult(crate::domain::convert::UnitPair),
 
     OpenConverter,
     OpenTools,
     OpenNotes,
+    OpenWorksheet,
+    SetWorksheetLine(usize, String),
+    InsertWorksheetLine(usize),
+    RemoveWorksheetLine(usize),
     CloseMode,
     ShowHelp,
     Quit,
@@ -1,3 +1,9 @@
+// Parts of these are only reachable from the GTK frontend; the allows keep
+// headless builds quiet about them without hiding dead code elsewhere.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub mod display;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 pub mod message;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 pub mod state;
 pub mod update;
@@ -1,7 +1,18 @@
+use crate::domain::convert::{UnitPair, COMMON_PAIRS};
 use crate::domain::engine::{Engine, EvalSettings};
+use crate::domain::{eval, highlight};
+use crate::domain::snippet::Snippet;
+use crate::domain::symbols::SymbolTable;
 use crate::domain::types::*;
+use crate::domain::worksheet::{self, LineResult};
 use crate::services::config::Config;
 
+const MAX_RECENT_CONVERSIONS: usize = 4;
+const MAX_CONVERSION_CHOICES: usize = 8;
+pub const ZOOM_MIN: f64 = 0.5;
+pub const ZOOM_MAX: f64 = 2.5;
+const ZOOM_STEP: f64 = 0.1;
+
 pub struct Tab {
     pub engine: Engine,
     pub name: String,
@@ -19,6 +30,7 @@ pub enum ModePanel {
     Converter,
     Tools,
     Notes,
+    Worksheet,
 }
 
 pub struct AppState {
@@ -30,7 +42,19 @@ pub struct AppState {
     pub mode_panel_visible: bool,
     pub active_mode: Option<ModePanel>,
     pub history_search: String,
+    pub recent_conversions: Vec<UnitPair>,
+    pub worksheet: Vec<String>,
     pub session_id: u64,
+    /// Scale for keypad and display text.
+    pub zoom: f64,
+    /// Display and scientific keys beside the basic keypad instead of above it.
+    pub landscape: bool,
+    /// Starts from the config and can be switched for this session only.
+    pub interval_arithmetic: bool,
+    /// Like `interval_arithmetic`: from the config, switchable per session.
+    pub money_mode: bool,
+    /// Saved expressions for the snippets menu; the frontend loads them.
+    pub snippets: Vec<Snippet>,
     pub config: Config,
 }
 
@@ -46,7 +70,14 @@ impl AppState {
             mode_panel_visible: false,
             active_mode: None,
             history_search: String::new(),
+            recent_conversions: Vec::new(),
+            worksheet: vec![String::new()],
             session_id,
+            zoom: config.layout.zoom.clamp(ZOOM_MIN, ZOOM_MAX),
+            landscape: false,
+            interval_arithmetic: config.behavior.interval_arithmetic,
+            money_mode: config.behavior.money_mode,
+            snippets: Vec::new(),
             config,
         };
         state.tabs.push(Tab {
@@ -64,8 +95,100 @@ impl AppState {
         &mut self.tabs[self.active_tab].engine
     }
 
+    /// Zooms in (positive) or out by whole steps; 0 goes back to the
+    /// configured zoom.
+    pub fn zoom_by(&mut self, steps: i32) {
+        let zoom = match steps {
+            0 => self.config.layout.zoom,
+            _ => self.zoom + steps as f64 * ZOOM_STEP,
+        };
+        // Rounded so repeated steps land on tidy values.
+        self.zoom = ((zoom * 10.0).round() / 10.0).clamp(ZOOM_MIN, ZOOM_MAX);
+    }
+
+    /// Picks the layout for a window of `width` × `height`: landscape once it
+    /// is clearly wider than tall, portrait again once it no longer is, so a
+    /// window near square doesn't flip back and forth. Returns whether the
+    /// layout changed.
+    pub fn fit_window(&mut self, width: i32, height: i32) -> bool {
+        let landscape = if self.landscape { width > height } else { width * 4 > height * 5 };
+        std::mem::replace(&mut self.landscape, landscape) != landscape
+    }
+
+    /// The value of copied text that reads as a calculation, worked out with
+    /// the current tab's angle mode and the configured plugins.
+    pub fn clipboard_offer(&self, text: &str) -> Option<f64> {
+        if !highlight::looks_like_expression(text) {
+            return None;
+        }
+        let expr = text.trim().trim_end_matches('=');
+        eval::evaluate_str(
+            expr,
+            &self.config.plugins.functions,
+            self.engine().angle_mode(),
+            self.config.behavior.operator_precedence,
+        )
+        .ok()
+    }
+
+    /// Pairs for the "Convert to…" popover: recently used first, then the
+    /// common ones, without duplicates.
+    pub fn conversion_choices(&self) -> Vec<UnitPair> {
+        let mut choices = self.recent_conversions.clone();
+        for pair in COMMON_PAIRS {
+            if !choices.contains(pair) {
+                choices.push(*pair);
+            }
+        }
+        choices.truncate(MAX_CONVERSION_CHOICES);
+        choices
+    }
+
+    pub fn remember_conversion(&mut self, pair: UnitPair) {
+        self.recent_conversions.retain(|p| *p != pair);
+        self.recent_conversions.insert(0, pair);
+        self.recent_conversions.truncate(MAX_RECENT_CONVERSIONS);
+    }
+
+    pub fn worksheet_results(&self) -> Vec<LineResult> {
+        worksheet::evaluate(&self.worksheet, &self.config.plugins.functions, self.eval_settings())
+    }
+
+    /// Names available on worksheet line `line`: built-ins, plugin
+    /// functions and the variables assigned above it.
+    pub fn worksheet_symbols(&self, line: usize) -> SymbolTable {
+        let mut table = SymbolTable::builtin().with_plugins(&self.config.plugins.functions);
+        for result in self.worksheet_results().iter().take(line) {
+            if let Some(value) = result.number() {
+                table.define("ans", value);
+                if let Some(name) = &result.name {
+                    table.define(name, value);
+                }
+            }
+        }
+        table
+    }
+
     pub fn eval_settings(&self) -> EvalSettings {
-        eval_settings(&self.config)
+        EvalSettings {
+            interval_arithmetic: self.interval_arithmetic,
+            money: self.money_mode,
+            ..eval_settings(&self.config)
+        }
+    }
+
+    pub fn toggle_interval_arithmetic(&mut self) {
+        self.interval_arithmetic = !self.interval_arithmetic;
+        for tab in &mut self.tabs {
+            tab.engine.set_interval_arithmetic(self.interval_arithmetic);
+        }
+    }
+
+    pub fn toggle_money_mode(&mut self) {
+        self.money_mode = !self.money_mode;
+        for tab in &mut self.tabs {
+            tab.engine.set_money(self.money_mode);
+        }
     }
 
     pub fn timestamp(&self) -> u64 {
@@ -85,12 +208,16 @@ pub fn eval_settings(config: &Config) -> EvalSettings {
         standard_precedence: config.behavior.operator_precedence,
         auto_evaluate: config.behavior.auto_evaluate,
         max_history: config.history.max_entries,
+        interval_arithmetic: config.behavior.interval_arithmetic,
+        money: config.behavior.money_mode,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app::message::Message;
+    use crate::app::update;
 
     #[test]
     fn new_state_has_one_tab() {
@@ -99,6 +226,74 @@ mod tests {
         assert_eq!(state.tabs[0].name, "Calc 1");
     }
 
+    #[test]
+    fn zoom_steps_and_limits() {
+        let mut state = AppState::new(Config::default(), 0);
+        assert_eq!(state.zoom, 1.0);
+        state.zoom_by(1);
+        state.zoom_by(1);
+        assert_eq!(state.zoom, 1.2);
+        for _ in 0..30 {
+            state.zoom_by(-1);
+        }
+        assert_eq!(state.zoom, ZOOM_MIN);
+        state.zoom_by(0);
+        assert_eq!(state.zoom, 1.0);
+    }
+
+    #[test]
+    fn landscape_switch_has_hysteresis() {
+        let mut state = AppState::new(Config::default(), 0);
+        assert!(!state.fit_window(400, 580));
+        assert!(!state.fit_window(580, 580));
+        assert!(!state.fit_window(700, 580));
+        assert!(state.fit_window(800, 600));
+        assert!(state.landscape);
+        assert!(!state.fit_window(700, 600));
+        assert!(state.fit_window(600, 600));
+        assert!(!state.landscape);
+    }
+
+    #[test]
+    fn interval_arithmetic_switches_every_tab() {
+        let mut state = AppState::new(Config::default(), 0);
+        update::update(&mut state, Message::NewTab);
+        state.toggle_interval_arithmetic();
+        assert!(state.eval_settings().interval_arithmetic);
+        for tab in 0..2 {
+            state.active_tab = tab;
+            state.engine_mut().input_str("0.1+0.2").unwrap();
+            state.engine_mut().calculate(0, 0);
+            assert_eq!(state.engine().main_display_text(), "[0.2999999999, 0.3000000001]");
+        }
+    }
+
+    #[test]
+    fn money_mode_switches_every_tab() {
+        let mut state = AppState::new(Config::default(), 0);
+        update::update(&mut state, Message::NewTab);
+        update::update(&mut state, Message::ToggleMoneyMode);
+        for tab in 0..2 {
+            state.active_tab = tab;
+            state.engine_mut().input_str("19.99*3").unwrap();
+            state.engine_mut().calculate(0, 0);
+            assert_eq!(state.engine().main_display_text(), "59.97");
+        }
+        state.toggle_money_mode();
+        assert_eq!(state.engine().main_display_text(), "59.97");
+        assert!(!state.eval_settings().money);
+    }
+
+    #[test]
+    fn clipboard_offers_only_calculations() {
+        let state = AppState::new(Config::default(), 0);
+        assert_eq!(state.clipboard_offer("12*(3+4)"), Some(84.0));
+        assert_eq!(state.clipboard_offer(" 2 + 3 * 4 =\n"), Some(14.0));
+        assert_eq!(state.clipboard_offer("84"), None);
+        assert_eq!(state.clipboard_offer("1/0"), None);
+        assert_eq!(state.clipboard_offer("see you at 5"), None);
+    }
+
     #[test]
     fn new_state_defaults() {
         let state = AppState::new(Config::default(), 100);
@@ -161,6 +356,18 @@ mod tests {
         assert_eq!(settings.angle_mode, AngleMode::Degrees);
     }
 
+    #[test]
+    fn worksheet_symbols_only_see_lines_above() {
+        let mut state = AppState::new(Config::default(), 0);
+        state.worksheet = vec!["rate = 1.25".into(), "rate * 2".into(), "radius = 3".into()];
+        let names = |line| -> Vec<String> {
+            state.worksheet_symbols(line).complete("ra").iter().map(|s| s.name.clone()).collect()
+        };
+        assert_eq!(names(2), ["rate"]);
+        assert_eq!(names(3), ["rate", "radius"]);
+        assert_eq!(state.worksheet_symbols(2).complete("an")[0].detail, "= 2.5");
+    }
+
     #[test]
     fn engine_accessor() {
         let state = AppState::new(Config::default(), 0);
@@ -15,24 +15,32 @@ pub enum SideEffect {
     RefreshHistory,
     RefreshMemory,
     RefreshPinned,
+    RefreshWorksheet,
     ExportedFile(std::path::PathBuf),
+    CopyToClipboard(String),
+    /// Text to read aloud, see `services::speech`.
+    Speak(String),
+    PasteFromClipboard,
+    ShowSteps(Result<Vec<crate::domain::ast::Step>, String>),
+    OpenPalette,
     ShowHelp,
     Navigate(crate::ui::keyboard::Direction),
     ActivateButton,
     OpenMenu,
     Quit,
     ResizeWindow,
+    ApplyZoom,
     Noop,
 }
 
 pub fn update(state: &mut AppState, msg: Message) -> Vec<SideEffect> {
     match msg {
         Message::Digit(d) => {
-            state.engine_mut().input_digit(d);
+            state.engine_mut().input_char(d);
             vec![SideEffect::UpdateDisplay]
         }
         Message::Decimal => {
-            state.engine_mut().input_decimal();
+            state.engine_mut().input_char('.');
             vec![SideEffect::UpdateDisplay]
         }
         Message::BinaryOp(op) => {
@@ -56,8 +64,44 @@ pub fn update(state: &mut AppState, msg: Message) -> Vec<SideEffect> {
             let session = state.session_id;
             state.engine_mut().calculate(ts, session);
             history::save_history(&state.engine().history, state.config.history.auto_save);
-            vec![SideEffect::UpdateDisplay]
+            calculated(state)
+        }
+        Message::LoadExpression(text) => {
+            let plugins = state.config.plugins.functions.clone();
+            match state.engine_mut().load_expression(&text, &plugins) {
+                Ok(()) => vec![SideEffect::UpdateDisplay],
+                Err(_) => vec![SideEffect::Noop],
+            }
+        }
+        Message::InsertSnippet(index) => {
+            let Some(snippet) = state.snippets.get(index) else { return vec![SideEffect::Noop] };
+            let text = snippet.fill(state.engine().current_value());
+            let plugins = state.config.plugins.functions.clone();
+            match state.engine_mut().load_expression(&text, &plugins) {
+                Ok(()) => vec![SideEffect::UpdateDisplay],
+                Err(_) => vec![SideEffect::Noop],
+            }
+        }
+        Message::SetSnippets(snippets) => {
+            state.snippets = snippets;
+            vec![SideEffect::Noop]
+        }
+        Message::PercentQuery(query, x, y) => {
+            let plugins = state.config.plugins.functions.clone();
+            if state.engine_mut().load_expression(&query.expression(x, y), &plugins).is_err() {
+                return vec![SideEffect::Noop];
+            }
+            let ts = state.timestamp();
+            let session = state.session_id;
+            state.engine_mut().calculate(ts, session);
+            history::save_history(&state.engine().history, state.config.history.auto_save);
+            calculated(state)
         }
+        Message::RequestPaste => vec![SideEffect::PasteFromClipboard],
+        Message::Paste(text) => match state.engine_mut().input_str(text.trim()) {
+            Ok(()) => vec![SideEffect::UpdateDisplay],
+            Err(_) => vec![SideEffect::Noop],
+        },
         Message::Clear => {
             state.engine_mut().clear();
             vec![SideEffect::UpdateDisplay]
@@ -107,6 +151,21 @@ pub fn update(state: &mut AppState, msg: Message) -> Vec<SideEffect> {
             state.engine_mut().toggle_angle_mode();
             vec![SideEffect::UpdateDisplay]
         }
+        Message::ToggleIntervalArithmetic => {
+            state.toggle_interval_arithmetic();
+            vec![SideEffect::UpdateDisplay]
+        }
+        Message::ToggleMoneyMode => {
+            state.toggle_money_mode();
+            vec![SideEffect::UpdateDisplay]
+        }
+        Message::Type(c) => {
+            if state.engine_mut().input_char(c) {
+                vec![SideEffect::UpdateDisplay]
+            } else {
+                vec![SideEffect::Noop]
+            }
+        }
         Message::Undo => {
             state.engine_mut().undo();
             vec![SideEffect::UpdateDisplay]
@@ -179,7 +238,12 @@ pub fn update(state: &mut AppState, msg: Message) -> Vec<SideEffect> {
         }
         Message::ToggleScientific => {
             state.scientific_mode = !state.scientific_mode;
-            vec![SideEffect::ToggleScientific(state.scientific_mode), SideEffect::ResizeWindow]
+            // A landscape window has room for both keypads as it is.
+            if state.landscape {
+                vec![SideEffect::ToggleScientific(state.scientific_mode)]
+            } else {
+                vec![SideEffect::ToggleScientific(state.scientific_mode), SideEffect::ResizeWindow]
+            }
         }
         Message::ToggleTheme => {
             vec![SideEffect::ToggleTheme]
@@ -225,6 +289,26 @@ pub fn update(state: &mut AppState, msg: Message) -> Vec<SideEffect> {
             history::save_history(&state.engine().history, state.config.history.auto_save);
             vec![SideEffect::RefreshHistory]
         }
+        Message::ToggleStar(index) => {
+            state.engine_mut().toggle_star(index);
+            history::save_history(&state.engine().history, state.config.history.auto_save);
+            vec![SideEffect::RefreshHistory]
+        }
+        Message::LabelHistory(index, label) => {
+            state.engine_mut().set_history_label(index, &label);
+            history::save_history(&state.engine().history, state.config.history.auto_save);
+            vec![SideEffect::RefreshHistory]
+        }
+        Message::EditHistory(index) => {
+            let Some(text) = state.engine().history.get(index).map(|e| e.expression.clone()) else {
+                return vec![SideEffect::Noop];
+            };
+            let plugins = state.config.plugins.functions.clone();
+            match state.engine_mut().load_expression(&text, &plugins) {
+                Ok(()) => vec![SideEffect::UpdateDisplay],
+                Err(_) => vec![SideEffect::Noop],
+            }
+        }
         Message::ExportHistoryJson => {
             let p = history::export_history_json(&state.engine().history);
             vec![SideEffect::ExportedFile(p)]
@@ -233,6 +317,31 @@ pub fn update(state: &mut AppState, msg: Message) -> Vec<SideEffect> {
             let p = history::export_history_csv(&state.engine().history);
             vec![SideEffect::ExportedFile(p)]
         }
+        Message::ExportHistoryTo(path) => {
+            let format = history::ExportFormat::from_path(&path).unwrap_or(history::ExportFormat::Csv);
+            match history::export_history_to(&state.engine().history, &path, format) {
+                Ok(()) => vec![SideEffect::ExportedFile(path)],
+                Err(e) => {
+                    eprintln!("Export failed: {}", e);
+                    vec![SideEffect::Noop]
+                }
+            }
+        }
+        Message::CopyAs(style) => match state.engine().copy_text(style) {
+            Some(text) => vec![SideEffect::CopyToClipboard(text)],
+            None => vec![SideEffect::Noop],
+        },
+        Message::ShowSteps => vec![SideEffect::ShowSteps(state.engine().steps())],
+        Message::ConvertResult(pair) => {
+            let ts = state.timestamp();
+            let session = state.session_id;
+            if !state.engine_mut().convert_result(pair, ts, session) {
+                return vec![SideEffect::Noop];
+            }
+            state.remember_conversion(pair);
+            history::save_history(&state.engine().history, state.config.history.auto_save);
+            vec![SideEffect::UpdateDisplay]
+        }
         Message::OpenConverter => {
             toggle_mode(state, ModePanel::Converter);
             vec![SideEffect::ToggleModePanel]
@@ -245,6 +354,30 @@ pub fn update(state: &mut AppState, msg: Message) -> Vec<SideEffect> {
             toggle_mode(state, ModePanel::Notes);
             vec![SideEffect::ToggleModePanel]
         }
+        Message::OpenWorksheet => {
+            toggle_mode(state, ModePanel::Worksheet);
+            vec![SideEffect::ToggleModePanel]
+        }
+        Message::SetWorksheetLine(index, text) => {
+            if let Some(line) = state.worksheet.get_mut(index) {
+                *line = text;
+            }
+            vec![SideEffect::Noop]
+        }
+        Message::InsertWorksheetLine(index) => {
+            let index = index.min(state.worksheet.len());
+            state.worksheet.insert(index, String::new());
+            vec![SideEffect::RefreshWorksheet]
+        }
+        Message::RemoveWorksheetLine(index) => {
+            if index < state.worksheet.len() {
+                state.worksheet.remove(index);
+            }
+            if state.worksheet.is_empty() {
+                state.worksheet.push(String::new());
+            }
+            vec![SideEffect::RefreshWorksheet]
+        }
         Message::CloseMode => {
             if state.mode_panel_visible {
                 state.mode_panel_visible = false;
@@ -261,6 +394,19 @@ pub fn update(state: &mut AppState, msg: Message) -> Vec<SideEffect> {
         Message::ShowHelp => {
             vec![SideEffect::ShowHelp]
         }
+        Message::OpenPalette => vec![SideEffect::OpenPalette],
+        Message::ZoomIn => {
+            state.zoom_by(1);
+            vec![SideEffect::ApplyZoom]
+        }
+        Message::ZoomOut => {
+            state.zoom_by(-1);
+            vec![SideEffect::ApplyZoom]
+        }
+        Message::ZoomReset => {
+            state.zoom_by(0);
+            vec![SideEffect::ApplyZoom]
+        }
         Message::Quit => {
             save_on_exit(state);
             vec![SideEffect::Quit]
@@ -280,6 +426,20 @@ pub fn update(state: &mut AppState, msg: Message) -> Vec<SideEffect> {
     }
 }
 
+// After "=": a redraw, and the result read out when `speak_results` is on.
+fn calculated(state: &AppState) -> Vec<SideEffect> {
+    if !state.config.feedback.speak_results {
+        return vec![SideEffect::UpdateDisplay];
+    }
+    let shown = state.engine().main_display_text();
+    let spoken = if state.engine().has_error() {
+        format!("Error: {}", shown)
+    } else {
+        format!("equals {}", crate::ui::a11y::spoken_result(&shown))
+    };
+    vec![SideEffect::UpdateDisplay, SideEffect::Speak(spoken)]
+}
+
 fn toggle_mode(state: &mut AppState, mode: ModePanel) {
     if state.active_mode == Some(mode) && state.mode_panel_visible {
         state.mode_panel_visible = false;
@@ -291,6 +451,7 @@ fn toggle_mode(state: &mut AppState, mode: ModePanel) {
 }
 
 pub fn save_on_exit(state: &AppState) {
+    history::save_history(&state.engine().history, state.config.history.auto_save);
     if state.config.session.restore_session {
         let tab_states: Vec<session::TabState> = state
             .tabs
@@ -305,6 +466,7 @@ pub fn save_on_exit(state: &AppState) {
             tabs: tab_states,
             active_tab: state.active_tab,
             scientific_mode: state.scientific_mode,
+            worksheet: state.worksheet.clone(),
         };
         session::save_session(&ss);
     }
@@ -333,6 +495,9 @@ pub fn restore_session(state: &mut AppState) {
         }
         state.active_tab = ss.active_tab.min(state.tabs.len() - 1);
         state.scientific_mode = ss.scientific_mode;
+        if !ss.worksheet.is_empty() {
+            state.worksheet = ss.worksheet;
+        }
     } else {
         let loaded = history::load_history(state.config.history.auto_save);
         for entry in loaded {
@@ -350,6 +515,26 @@ mod tests {
         AppState::new(Config::default(), 12345)
     }
 
+    #[test]
+    fn results_are_spoken_when_enabled() {
+        let mut s = test_state();
+        s.config.history.auto_save = false;
+        update(&mut s, Message::Paste("2-5".into()));
+        assert_eq!(update(&mut s, Message::Equals), vec![SideEffect::UpdateDisplay]);
+
+        s.config.feedback.speak_results = true;
+        update(&mut s, Message::Paste("2-5".into()));
+        assert_eq!(
+            update(&mut s, Message::Equals),
+            vec![SideEffect::UpdateDisplay, SideEffect::Speak("equals minus 3".into())]
+        );
+        update(&mut s, Message::Paste("1/0".into()));
+        assert_eq!(
+            update(&mut s, Message::Equals),
+            vec![SideEffect::UpdateDisplay, SideEffect::Speak("Error: Division by zero".into())]
+        );
+    }
+
     #[test]
     fn digit_updates_display() {
         let mut s = test_state();
@@ -369,6 +554,110 @@ mod tests {
         assert_eq!(s.engine().history.len(), 1);
     }
 
+    #[test]
+    fn percent_queries_calculate_into_the_display() {
+        use crate::domain::types::PercentQuery;
+        let mut s = test_state();
+        for (query, x, y, result) in [
+            (PercentQuery::Of, 15.0, 200.0, "30"),
+            (PercentQuery::Change, 80.0, 100.0, "25"),
+            (PercentQuery::Change, 100.0, -20.0, "-120"),
+            (PercentQuery::Share, 30.0, 120.0, "25"),
+        ] {
+            assert_eq!(update(&mut s, Message::PercentQuery(query, x, y)), vec![SideEffect::UpdateDisplay]);
+            assert_eq!(s.engine().main_display_text(), result);
+        }
+        assert_eq!(s.engine().history.len(), 4);
+    }
+
+    #[test]
+    fn snippets_fill_in_the_current_value() {
+        let mut s = test_state();
+        s.snippets = crate::domain::snippet::defaults();
+        update(&mut s, Message::Digit('8'));
+        update(&mut s, Message::Digit('0'));
+        assert_eq!(update(&mut s, Message::InsertSnippet(0)), vec![SideEffect::UpdateDisplay]);
+        assert_eq!(s.engine().main_display_text(), "80\u{00d7}1.25");
+        update(&mut s, Message::Equals);
+        assert_eq!(s.engine().main_display_text(), "100");
+        assert_eq!(update(&mut s, Message::InsertSnippet(9)), vec![SideEffect::Noop]);
+    }
+
+    #[test]
+    fn paste_goes_through_input_validation() {
+        let mut s = test_state();
+        assert_eq!(update(&mut s, Message::RequestPaste), vec![SideEffect::PasteFromClipboard]);
+        assert_eq!(update(&mut s, Message::Paste("1.2.3".into())), vec![SideEffect::Noop]);
+        assert_eq!(s.engine().main_display_text(), "0");
+        update(&mut s, Message::Paste(" 12 \u{00d7} 1.5\n".into()));
+        update(&mut s, Message::Decimal);
+        update(&mut s, Message::Digit('5'));
+        update(&mut s, Message::Decimal);
+        assert_eq!(s.engine().expression_text(), "12\u{00d7}1.55");
+    }
+
+    #[test]
+    fn copy_as_latex_after_equals() {
+        let mut s = test_state();
+        update(&mut s, Message::Digit('9'));
+        update(&mut s, Message::BinaryOp(crate::domain::types::BinaryOp::Divide));
+        update(&mut s, Message::Digit('2'));
+        update(&mut s, Message::Equals);
+        let effects = update(&mut s, Message::CopyAs(crate::domain::ast::CopyStyle::Latex));
+        assert_eq!(effects, vec![SideEffect::CopyToClipboard("\\frac{9}{2} = 4.5".into())]);
+    }
+
+    #[test]
+    fn convert_result_moves_pair_to_front() {
+        use crate::domain::convert::COMMON_PAIRS;
+        let mut s = test_state();
+        assert_eq!(s.conversion_choices(), COMMON_PAIRS);
+        update(&mut s, Message::Digit('5'));
+        let pair = COMMON_PAIRS[3];
+        let effects = update(&mut s, Message::ConvertResult(pair));
+        assert_eq!(effects, vec![SideEffect::UpdateDisplay]);
+        assert_eq!(s.engine().main_display_text(), "41");
+        let choices = s.conversion_choices();
+        assert_eq!(choices[0], pair);
+        assert_eq!(choices.iter().filter(|p| **p == pair).count(), 1);
+    }
+
+    #[test]
+    fn starred_entries_survive_clear() {
+        let mut s = test_state();
+        s.config.history.auto_save = false;
+        for d in ['1', '2'] {
+            update(&mut s, Message::Digit(d));
+            update(&mut s, Message::Equals);
+        }
+        assert_eq!(update(&mut s, Message::ToggleStar(1)), vec![SideEffect::RefreshHistory]);
+        update(&mut s, Message::LabelHistory(1, "rent".into()));
+        update(&mut s, Message::ClearHistory);
+        assert_eq!(s.engine().history.len(), 1);
+        assert_eq!(s.engine().history[0].label.as_deref(), Some("rent"));
+    }
+
+    #[test]
+    fn edited_history_is_saved_as_a_new_entry() {
+        let mut s = test_state();
+        s.config.history.auto_save = false;
+        update(&mut s, Message::Paste("12\u{00d7}(3+4)".into()));
+        update(&mut s, Message::Equals);
+        update(&mut s, Message::Clear);
+        assert_eq!(update(&mut s, Message::EditHistory(0)), vec![SideEffect::UpdateDisplay]);
+        assert_eq!(s.engine().expression_text(), "12\u{00d7}(3+4)");
+        update(&mut s, Message::Backspace);
+        update(&mut s, Message::Backspace);
+        update(&mut s, Message::Digit('5'));
+        update(&mut s, Message::RightParen);
+        update(&mut s, Message::Equals);
+        assert_eq!(s.engine().main_display_text(), "96");
+        let history = &s.engine().history;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].result, 84.0);
+        assert_eq!(update(&mut s, Message::EditHistory(5)), vec![SideEffect::Noop]);
+    }
+
     #[test]
     fn tab_management() {
         let mut s = test_state();
@@ -429,6 +718,11 @@ mod tests {
         let effects = update(&mut s, Message::ToggleScientific);
         assert!(s.scientific_mode);
         assert!(effects.contains(&SideEffect::ToggleScientific(true)));
+        assert!(effects.contains(&SideEffect::ResizeWindow));
+
+        s.landscape = true;
+        let effects = update(&mut s, Message::ToggleScientific);
+        assert_eq!(effects, vec![SideEffect::ToggleScientific(false)]);
     }
 
     #[test]
@@ -448,6 +742,23 @@ mod tests {
         assert!(!s.panel_visible);
     }
 
+    #[test]
+    fn worksheet_lines() {
+        let mut s = test_state();
+        assert_eq!(s.worksheet, [""]);
+        update(&mut s, Message::SetWorksheetLine(0, "x = 4".into()));
+        assert_eq!(update(&mut s, Message::InsertWorksheetLine(1)), vec![SideEffect::RefreshWorksheet]);
+        update(&mut s, Message::SetWorksheetLine(1, "x * 2".into()));
+        assert_eq!(s.worksheet_results()[1].number(), Some(8.0));
+
+        update(&mut s, Message::SetWorksheetLine(0, "x = 5".into()));
+        assert_eq!(s.worksheet_results()[1].number(), Some(10.0));
+
+        update(&mut s, Message::RemoveWorksheetLine(0));
+        update(&mut s, Message::RemoveWorksheetLine(0));
+        assert_eq!(s.worksheet, [""]);
+    }
+
     #[test]
     fn mode_panel_toggle() {
         let mut s = test_state();
@@ -0,0 +1,353 @@
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::os::unix::fs::FileTypeExt;
+use std::path::PathBuf;
+
+use crate::app::state::eval_settings;
+use crate::domain::eval;
+use crate::services::config::Config;
+use crate::services::history::csv_field;
+
+pub const USAGE: &str = "\
+Usage: fredulator [OPTIONS]
+
+Options:
+  --tui                    Run the terminal interface instead of the GTK window
+  --style <FILE>           Extra stylesheet layered over the theme, reloaded on save
+  --batch <FILE>           Evaluate one expression per line and print the results
+                           (`-` reads standard input)
+  --format <plain|json|csv>  Output format for --batch (default: plain)
+  -h, --help               Show this help
+
+When standard input is a pipe or a file, e.g. `echo 2+2 | fredulator`, its
+lines are evaluated as with `--batch -`.
+";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Plain,
+    Json,
+    Csv,
+}
+
+impl OutputFormat {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "plain" => Some(Self::Plain),
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Gui { style: Option<PathBuf> },
+    Tui,
+    Help,
+    Batch { input: PathBuf, format: OutputFormat },
+}
+
+/// `stdin_piped` turns a plain launch into `--batch -`; see [`stdin_piped`].
+pub fn parse_args(args: &[String], stdin_piped: bool) -> Result<Command, String> {
+    let mut batch: Option<PathBuf> = None;
+    let mut format: Option<OutputFormat> = None;
+    let mut tui = false;
+    let mut style: Option<PathBuf> = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--tui" => tui = true,
+            "--style" => {
+                let path = iter.next().ok_or("--style requires a file argument")?;
+                style = Some(PathBuf::from(path));
+            }
+            "--batch" => {
+                let path = iter.next().ok_or("--batch requires a file argument")?;
+                batch = Some(PathBuf::from(path));
+            }
+            "--format" => {
+                let name = iter.next().ok_or("--format requires an argument")?;
+                format = Some(
+                    OutputFormat::parse(name)
+                        .ok_or_else(|| format!("unknown output format '{}'", name))?,
+                );
+            }
+            other => return Err(format!("unrecognized argument '{}'", other)),
+        }
+    }
+
+    if style.is_some() && (tui || batch.is_some()) {
+        return Err("--style only applies to the GTK window".into());
+    }
+    if stdin_piped && batch.is_none() && !tui && style.is_none() {
+        batch = Some(PathBuf::from(STDIN));
+    }
+
+    match (batch, format) {
+        (Some(_), _) if tui => Err("--tui cannot be combined with --batch".into()),
+        (Some(input), format) => Ok(Command::Batch {
+            input,
+            format: format.unwrap_or(OutputFormat::Plain),
+        }),
+        (None, Some(_)) => Err("--format is only valid together with --batch".into()),
+        (None, None) if tui => Ok(Command::Tui),
+        (None, None) => Ok(Command::Gui { style }),
+    }
+}
+
+const STDIN: &str = "-";
+
+/// Whether stdin is a pipe or a redirected file. A terminal is not, and
+/// neither is `/dev/null`, which is what desktop launchers hand the GUI.
+pub fn stdin_piped() -> bool {
+    fs::metadata("/dev/stdin").is_ok_and(|m| m.file_type().is_fifo() || m.is_file())
+}
+
+/// Runs a non-GUI command and returns the process exit code.
+pub fn run(cmd: Command, config: &Config) -> i32 {
+    match cmd {
+        Command::Gui { .. } | Command::Tui => 0,
+        Command::Help => {
+            print!("{}", USAGE);
+            0
+        }
+        Command::Batch { input, format } => {
+            let read = if input.as_os_str() == STDIN {
+                let mut text = String::new();
+                std::io::stdin().read_to_string(&mut text).map(|_| text)
+            } else {
+                fs::read_to_string(&input)
+            };
+            let text = match read {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("fredulator: cannot read {}: {}", input.display(), e);
+                    return 1;
+                }
+            };
+            let results = evaluate_lines(&text, config);
+            print!("{}", render(&results, format));
+            0
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LineResult {
+    pub expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub fn evaluate_lines(text: &str, config: &Config) -> Vec<LineResult> {
+    let settings = eval_settings(config);
+    let plugins = &config.plugins.functions;
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("//"))
+        .collect();
+    let values = eval::evaluate_many(&lines, plugins, settings);
+    lines
+        .into_iter()
+        .zip(values)
+        .map(|(line, evaluated)| match evaluated {
+            Ok(evaluated) => LineResult {
+                expression: line.to_string(),
+                result: Some(evaluated.text),
+                error: None,
+            },
+            Err(e) => LineResult {
+                expression: line.to_string(),
+                result: None,
+                error: Some(e),
+            },
+        })
+        .collect()
+}
+
+pub fn render(results: &[LineResult], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Plain => {
+            let mut s = String::new();
+            for r in results {
+                match (&r.result, &r.error) {
+                    (Some(val), _) => s.push_str(&format!("{} = {}\n", r.expression, val)),
+                    (None, Some(err)) => s.push_str(&format!("{} = Error: {}\n", r.expression, err)),
+                    (None, None) => s.push_str(&format!("{}\n", r.expression)),
+                }
+            }
+            s
+        }
+        OutputFormat::Json => {
+            let mut s = serde_json::to_string_pretty(results).unwrap_or_else(|_| "[]".into());
+            s.push('\n');
+            s
+        }
+        OutputFormat::Csv => {
+            let mut s = String::from("expression,result,error\n");
+            for r in results {
+                s.push_str(&format!(
+                    "{},{},{}\n",
+                    csv_field(&r.expression),
+                    csv_field(r.result.as_deref().unwrap_or("")),
+                    csv_field(r.error.as_deref().unwrap_or("")),
+                ));
+            }
+            s
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn no_args_starts_gui() {
+        assert_eq!(parse_args(&[], false).unwrap(), Command::Gui { style: None });
+    }
+
+    #[test]
+    fn style_flag() {
+        assert_eq!(
+            parse_args(&args(&["--style", "dev.css"]), false).unwrap(),
+            Command::Gui { style: Some(PathBuf::from("dev.css")) }
+        );
+        assert!(parse_args(&args(&["--style"]), false).is_err());
+        assert!(parse_args(&args(&["--style", "a.css", "--tui"]), false).is_err());
+    }
+
+    #[test]
+    fn tui_flag() {
+        assert_eq!(parse_args(&args(&["--tui"]), false).unwrap(), Command::Tui);
+        assert!(parse_args(&args(&["--tui", "--batch", "x"]), false).is_err());
+    }
+
+    #[test]
+    fn batch_defaults_to_plain() {
+        let cmd = parse_args(&args(&["--batch", "in.txt"]), false).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Batch { input: PathBuf::from("in.txt"), format: OutputFormat::Plain }
+        );
+    }
+
+    #[test]
+    fn batch_with_format() {
+        let cmd = parse_args(&args(&["--format", "csv", "--batch", "in.txt"]), false).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Batch { input: PathBuf::from("in.txt"), format: OutputFormat::Csv }
+        );
+    }
+
+    #[test]
+    fn invalid_args_rejected() {
+        assert!(parse_args(&args(&["--batch"]), false).is_err());
+        assert!(parse_args(&args(&["--batch", "x", "--format", "xml"]), false).is_err());
+        assert!(parse_args(&args(&["--format", "json"]), false).is_err());
+        assert!(parse_args(&args(&["--bogus"]), false).is_err());
+    }
+
+    #[test]
+    fn piped_stdin_is_batch_input() {
+        let stdin = |format| Command::Batch { input: PathBuf::from("-"), format };
+        assert_eq!(parse_args(&[], true).unwrap(), stdin(OutputFormat::Plain));
+        assert_eq!(parse_args(&args(&["--format", "json"]), true).unwrap(), stdin(OutputFormat::Json));
+        assert_eq!(parse_args(&args(&["--batch", "-"]), false).unwrap(), stdin(OutputFormat::Plain));
+        assert_eq!(parse_args(&args(&["--tui"]), true).unwrap(), Command::Tui);
+        assert_eq!(
+            parse_args(&args(&["--batch", "in.txt"]), true).unwrap(),
+            Command::Batch { input: PathBuf::from("in.txt"), format: OutputFormat::Plain }
+        );
+    }
+
+    #[test]
+    fn evaluate_lines_skips_blanks_and_comments() {
+        let results = evaluate_lines("2+3\n\n# note\n// other\n10/4\n", &Config::default());
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].result.as_deref(), Some("5"));
+        assert_eq!(results[1].result.as_deref(), Some("2.5"));
+    }
+
+    #[test]
+    fn evaluate_lines_keeps_uncertainties() {
+        let results = evaluate_lines("2 * 12.3 +/- 0.2", &Config::default());
+        assert_eq!(results[0].result.as_deref(), Some("24.6 \u{00b1} 0.4"));
+    }
+
+    #[test]
+    fn evaluate_lines_keeps_interval_bounds() {
+        let results = evaluate_lines("[1,2]+[-1,1]", &Config::default());
+        assert_eq!(results[0].result.as_deref(), Some("[0, 3]"));
+    }
+
+    #[test]
+    fn evaluate_lines_keep_every_digit_of_whole_numbers() {
+        let results = evaluate_lines("99999999999999999 + 1\n9007199254740993-9007199254740992", &Config::default());
+        assert_eq!(results[0].result.as_deref(), Some("100000000000000000"));
+        assert_eq!(results[1].result.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn evaluate_lines_follow_the_configured_modes() {
+        let mut config = Config::default();
+        config.behavior.money_mode = true;
+        assert_eq!(evaluate_lines("10/3", &config)[0].result.as_deref(), Some("3.33"));
+        config.behavior.money_mode = false;
+        config.behavior.interval_arithmetic = true;
+        assert_eq!(evaluate_lines("2*3", &config)[0].result.as_deref(), Some("6"));
+    }
+
+    #[test]
+    fn evaluate_lines_reports_errors() {
+        let results = evaluate_lines("1/0\n1.2.3 + 1", &Config::default());
+        assert_eq!(results[0].error.as_deref(), Some("Division by zero"));
+        assert!(results[0].result.is_none());
+        assert_eq!(results[1].error.as_deref(), Some("Malformed number \u{2018}1.2.3\u{2019}"));
+    }
+
+    #[test]
+    fn render_plain() {
+        let results = evaluate_lines("2*3\n1/0", &Config::default());
+        assert_eq!(
+            render(&results, OutputFormat::Plain),
+            "2*3 = 6\n1/0 = Error: Division by zero\n"
+        );
+    }
+
+    #[test]
+    fn render_json() {
+        let results = evaluate_lines("2*3", &Config::default());
+        let out = render(&results, OutputFormat::Json);
+        let parsed: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(parsed[0]["expression"], "2*3");
+        assert_eq!(parsed[0]["result"], "6");
+        assert!(parsed[0].get("error").is_none());
+    }
+
+    #[test]
+    fn render_csv_escapes_fields() {
+        let results = vec![LineResult {
+            expression: "say \"hi\", 1".into(),
+            result: Some("1".into()),
+            error: None,
+        }];
+        assert_eq!(
+            render(&results, OutputFormat::Csv),
+            "expression,result,error\n\"say \"\"hi\"\", 1\",1,\n"
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use super::eval;
+use super::interval::Interval;
+use super::types::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Interval(f64, f64),
+    Constant(&'static str, f64),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Func(UnaryFunc, Box<Expr>),
+    Postfix(PostfixOp, Box<Expr>),
+    Group(Box<Expr>),
+}
+
+/// One operation in the evaluation of an expression, e.g. `3 × 4 = 12`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub operation: String,
+    pub result: f64,
+}
+
+impl Step {
+    pub fn render(&self) -> String {
+        format!("{} = {}", self.operation, format_number_default(self.result))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CopyStyle {
+    Plain,
+    Latex,
+}
+
+/// The deepest tree `build` makes. Trees are walked recursively, so this
+/// bounds the stack that any input can take.
+pub const MAX_DEPTH: usize = 500;
+
+enum StackOp {
+    Binary(BinaryOp),
+    Func(UnaryFunc),
+    LeftParen,
+}
+
+/// Builds an expression tree from the token stream, mirroring the
+/// precedence rules used by `eval::evaluate`. Trees deeper than `MAX_DEPTH`
+/// are refused.
+pub fn build(tokens: &[Token], standard_precedence: bool) -> Result<Expr, String> {
+    // Each node with the depth of the tree under it.
+    let mut output: Vec<(Expr, usize)> = Vec::new();
+    let mut ops: Vec<StackOp> = Vec::new();
+
+    for token in tokens {
+        match token {
+            Token::Number(n) => output.push((Expr::Number(*n), 1)),
+            Token::Integer(n) => output.push((Expr::Number(n.to_f64()), 1)),
+            Token::Interval(lo, hi) => output.push((Expr::Interval(*lo, *hi), 1)),
+            Token::Constant(name, value) => output.push((Expr::Constant(name, *value), 1)),
+            Token::BinaryOp(op) => {
+                while let Some(top) = ops.last() {
+                    let pop = match top {
+                        StackOp::LeftParen => false,
+                        StackOp::Func(_) => true,
+                        StackOp::Binary(top_op) => {
+                            if op.is_right_assoc() {
+                                top_op.precedence(standard_precedence) > op.precedence(standard_precedence)
+                            } else {
+                                top_op.precedence(standard_precedence) >= op.precedence(standard_precedence)
+                            }
+                        }
+                    };
+                    if !pop {
+                        break;
+                    }
+                    let popped = ops.pop().unwrap();
+                    reduce(&mut output, popped)?;
+                }
+                ops.push(StackOp::Binary(*op));
+            }
+            Token::UnaryFunc(f) => ops.push(StackOp::Func(*f)),
+            Token::LeftParen => ops.push(StackOp::LeftParen),
+            Token::RightParen => {
+                while let Some(top) = ops.last() {
+                    if matches!(top, StackOp::LeftParen) {
+                        break;
+                    }
+                    let popped = ops.pop().unwrap();
+                    reduce(&mut output, popped)?;
+                }
+                if matches!(ops.last(), Some(StackOp::LeftParen)) {
+                    ops.pop();
+                }
+                if let Some(StackOp::Func(_)) = ops.last() {
+                    let popped = ops.pop().unwrap();
+                    reduce(&mut output, popped)?;
+                } else {
+                    let (inner, depth) = output.pop().ok_or("Missing operand")?;
+                    output.push((Expr::Group(Box::new(inner)), nested(depth)?));
+                }
+            }
+            Token::PostfixOp(p) => {
+                let (inner, depth) = output.pop().ok_or("Missing operand")?;
+                output.push((Expr::Postfix(*p, Box::new(inner)), nested(depth)?));
+            }
+        }
+    }
+
+    while let Some(op) = ops.pop() {
+        reduce(&mut output, op)?;
+    }
+
+    let (expr, _) = output.pop().ok_or("Empty expression")?;
+    if !output.is_empty() {
+        return Err("Missing operator".into());
+    }
+    Ok(expr)
+}
+
+// The depth of a node over a subtree `depth` deep.
+fn nested(depth: usize) -> Result<usize, String> {
+    if depth >= MAX_DEPTH {
+        return Err("Expression is nested too deeply".into());
+    }
+    Ok(depth + 1)
+}
+
+fn reduce(output: &mut Vec<(Expr, usize)>, op: StackOp) -> Result<(), String> {
+    match op {
+        StackOp::Binary(bin_op) => {
+            let (b, db) = output.pop().ok_or("Missing operand")?;
+            let (a, da) = output.pop().ok_or("Missing operand")?;
+            output.push((Expr::Binary(bin_op, Box::new(a), Box::new(b)), nested(da.max(db))?));
+        }
+        StackOp::Func(func) => {
+            let (a, depth) = output.pop().ok_or("Missing operand")?;
+            output.push((Expr::Func(func, Box::new(a)), nested(depth)?));
+        }
+        StackOp::LeftParen => {}
+    }
+    Ok(())
+}
+
+/// Lists the operations that evaluating `expr` performs, innermost first, in
+/// the order the evaluator carries them out. Operands that were computed by
+/// an earlier step appear as their value.
+pub fn explain(expr: &Expr, angle_mode: AngleMode) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+    explain_into(expr, angle_mode, &mut steps)?;
+    Ok(steps)
+}
+
+// Returns the node's value and the leaf that stands for it in later steps.
+fn explain_into(expr: &Expr, angle_mode: AngleMode, steps: &mut Vec<Step>) -> Result<(f64, Expr), String> {
+    let (operation, result) = match expr {
+        Expr::Number(n) => return Ok((*n, expr.clone())),
+        Expr::Constant(_, value) => return Ok((*value, expr.clone())),
+        Expr::Interval(lo, hi) => return Ok((Interval { lo: *lo, hi: *hi }.midpoint(), expr.clone())),
+        Expr::Group(inner) => return explain_into(inner, angle_mode, steps),
+        Expr::Binary(op, a, b) => {
+            let (a, a_leaf) = explain_into(a, angle_mode, steps)?;
+            let (b, b_leaf) = explain_into(b, angle_mode, steps)?;
+            let node = Expr::Binary(*op, Box::new(a_leaf), Box::new(b_leaf));
+            (node, eval::apply_binary(*op, a, b)?)
+        }
+        Expr::Func(f, arg) => {
+            let (a, a_leaf) = explain_into(arg, angle_mode, steps)?;
+            (Expr::Func(*f, Box::new(a_leaf)), eval::apply_unary(*f, a, angle_mode)?)
+        }
+        Expr::Postfix(p, inner) => {
+            let (a, a_leaf) = explain_into(inner, angle_mode, steps)?;
+            (Expr::Postfix(*p, Box::new(a_leaf)), eval::apply_postfix(*p, a)?)
+        }
+    };
+    steps.push(Step { operation: to_plain(&operation), result });
+    Ok((result, Expr::Number(result)))
+}
+
+/// Renders `expr = result` in the requested style.
+pub fn render_equation(expr: &Expr, result: f64, style: CopyStyle) -> String {
+    match style {
+        CopyStyle::Plain => format!("{} = {}", to_plain(expr), format_number_default(result)),
+        CopyStyle::Latex => format!("{} = {}", to_latex(expr), latex_number(result)),
+    }
+}
+
+// Binding strength used to decide where explicit parentheses are needed
+// when the tree is printed back out.
+fn strength(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Binary(op, ..) => op.precedence(true),
+        Expr::Number(n) if *n < 0.0 => 1,
+        _ => 4,
+    }
+}
+
+fn needs_parens(parent: BinaryOp, child: &Expr, right: bool) -> bool {
+    let (p, c) = (parent.precedence(true), strength(child));
+    if parent == BinaryOp::Power {
+        return if right { c < p } else { c <= p };
+    }
+    c < p || (right && c == p && !matches!(parent, BinaryOp::Add | BinaryOp::Multiply))
+}
+
+pub fn to_plain(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(n) => format_number_default(*n),
+        Expr::Constant(name, _) => name.to_string(),
+        Expr::Interval(lo, hi) => format!("[{}, {}]", format_number_default(*lo), format_number_default(*hi)),
+        Expr::Group(inner) => format!("({})", to_plain(inner)),
+        Expr::Func(f, arg) => format!("{}({})", f.name(), to_plain(unwrap_group(arg))),
+        Expr::Postfix(p, inner) => {
+            let s = to_plain(inner);
+            if strength(inner) < 4 {
+                format!("({}){}", s, p.symbol())
+            } else {
+                format!("{}{}", s, p.symbol())
+            }
+        }
+        Expr::Binary(op, a, b) => {
+            let wrap = |e: &Expr, right: bool| {
+                let s = to_plain(e);
+                if needs_parens(*op, e, right) { format!("({})", s) } else { s }
+            };
+            let sym = match op {
+                BinaryOp::Power => "^",
+                BinaryOp::Modulo => "mod",
+                other => other.symbol(),
+            };
+            if *op == BinaryOp::Power {
+                format!("{}{}{}", wrap(a, false), sym, wrap(b, true))
+            } else {
+                format!("{} {} {}", wrap(a, false), sym, wrap(b, true))
+            }
+        }
+    }
+}
+
+pub fn to_latex(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(n) => latex_number(*n),
+        Expr::Constant(name, _) => match *name {
+            "\u{03c0}" => "\\pi".to_string(),
+            other => other.to_string(),
+        },
+        Expr::Interval(lo, hi) => format!("\\left[{}, {}\\right]", latex_number(*lo), latex_number(*hi)),
+        Expr::Group(inner) => format!("\\left({}\\right)", to_latex(inner)),
+        Expr::Func(f, arg) => latex_func(*f, unwrap_group(arg)),
+        Expr::Postfix(p, inner) => {
+            let base = latex_operand(inner);
+            match p {
+                PostfixOp::Square => format!("{}^{{2}}", base),
+                PostfixOp::Cube => format!("{}^{{3}}", base),
+                PostfixOp::Reciprocal => format!("{}^{{-1}}", base),
+                PostfixOp::Factorial => format!("{}!", base),
+                PostfixOp::Percent => format!("{}\\%", base),
+            }
+        }
+        Expr::Binary(BinaryOp::Divide, a, b) => {
+            format!("\\frac{{{}}}{{{}}}", to_latex(unwrap_group(a)), to_latex(unwrap_group(b)))
+        }
+        Expr::Binary(BinaryOp::Power, a, b) => {
+            let base = if needs_parens(BinaryOp::Power, a, false) {
+                format!("\\left({}\\right)", to_latex(a))
+            } else {
+                to_latex(a)
+            };
+            format!("{}^{{{}}}", base, to_latex(unwrap_group(b)))
+        }
+        Expr::Binary(op, a, b) => {
+            let wrap = |e: &Expr, right: bool| {
+                let s = to_latex(e);
+                if needs_parens(*op, e, right) { format!("\\left({}\\right)", s) } else { s }
+            };
+            let sym = match op {
+                BinaryOp::Add => "+",
+                BinaryOp::Subtract => "-",
+                BinaryOp::Multiply => "\\times",
+                BinaryOp::Modulo => "\\bmod",
+                BinaryOp::PlusMinus => "\\pm",
+                BinaryOp::Divide | BinaryOp::Power => unreachable!(),
+            };
+            format!("{} {} {}", wrap(a, false), sym, wrap(b, true))
+        }
+    }
+}
+
+fn unwrap_group(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Group(inner) => unwrap_group(inner),
+        other => other,
+    }
+}
+
+fn latex_operand(expr: &Expr) -> String {
+    if strength(expr) < 4 || matches!(expr, Expr::Binary(BinaryOp::Divide, ..)) {
+        format!("\\left({}\\right)", to_latex(expr))
+    } else {
+        to_latex(expr)
+    }
+}
+
+fn latex_func(func: UnaryFunc, arg: &Expr) -> String {
+    let inner = to_latex(arg);
+    let named = |cmd: &str| format!("{}\\left({}\\right)", cmd, inner);
+    match func {
+        UnaryFunc::Sin => named("\\sin"),
+        UnaryFunc::Cos => named("\\cos"),
+        UnaryFunc::Tan => named("\\tan"),
+        UnaryFunc::Asin => named("\\arcsin"),
+        UnaryFunc::Acos => named("\\arccos"),
+        UnaryFunc::Atan => named("\\arctan"),
+        UnaryFunc::Sinh => named("\\sinh"),
+        UnaryFunc::Cosh => named("\\cosh"),
+        UnaryFunc::Tanh => named("\\tanh"),
+        UnaryFunc::Ln => named("\\ln"),
+        UnaryFunc::Log10 => named("\\log_{10}"),
+        UnaryFunc::Sqrt => format!("\\sqrt{{{}}}", inner),
+        UnaryFunc::Cbrt => format!("\\sqrt[3]{{{}}}", inner),
+        UnaryFunc::Abs => format!("\\left|{}\\right|", inner),
+        UnaryFunc::Exp => format!("e^{{{}}}", inner),
+    }
+}
+
+fn latex_number(val: f64) -> String {
+    let s = format_number_default(val);
+    match s.split_once('e') {
+        Some((mantissa, exp)) => format!("{} \\times 10^{{{}}}", mantissa, exp),
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::eval::parse_expression;
+    use std::collections::HashMap;
+
+    fn tree(input: &str) -> Expr {
+        build(&parse_expression(input, &HashMap::new()).unwrap(), true).unwrap()
+    }
+
+    #[test]
+    fn build_respects_precedence() {
+        let e = tree("2+3*4");
+        assert!(matches!(e, Expr::Binary(BinaryOp::Add, _, ref b) if matches!(**b, Expr::Binary(BinaryOp::Multiply, ..))));
+    }
+
+    #[test]
+    fn latex_fraction_and_root() {
+        assert_eq!(to_latex(&tree("(1+2)/sqrt(9)")), "\\frac{1 + 2}{\\sqrt{9}}");
+    }
+
+    #[test]
+    fn latex_power_superscript() {
+        assert_eq!(to_latex(&tree("2^(3+1)")), "2^{3 + 1}");
+        assert_eq!(to_latex(&tree("(1+1)^2")), "\\left(1 + 1\\right)^{2}");
+    }
+
+    #[test]
+    fn latex_constants_and_functions() {
+        assert_eq!(to_latex(&tree("2pi")), "2 \\times \\pi");
+        assert_eq!(to_latex(&tree("sin(30)")), "\\sin\\left(30\\right)");
+    }
+
+    #[test]
+    fn plain_keeps_needed_parentheses() {
+        let tokens = vec![
+            Token::Number(2.0),
+            Token::BinaryOp(BinaryOp::Add),
+            Token::Number(3.0),
+            Token::BinaryOp(BinaryOp::Multiply),
+            Token::Number(4.0),
+        ];
+        // Left-to-right mode groups (2+3)*4, which must be spelled out.
+        let e = build(&tokens, false).unwrap();
+        assert_eq!(render_equation(&e, 20.0, CopyStyle::Plain), "(2 + 3) \u{00d7} 4 = 20");
+    }
+
+    #[test]
+    fn latex_scientific_result() {
+        assert_eq!(render_equation(&Expr::Number(1.0), 2e20, CopyStyle::Latex), "1 = 2 \\times 10^{20}");
+    }
+
+    fn steps(input: &str, standard_precedence: bool) -> Vec<String> {
+        let expr = build(&parse_expression(input, &HashMap::new()).unwrap(), standard_precedence).unwrap();
+        explain(&expr, AngleMode::Degrees).unwrap().iter().map(Step::render).collect()
+    }
+
+    #[test]
+    fn explain_follows_precedence() {
+        assert_eq!(steps("2+3*4", true), ["3 \u{00d7} 4 = 12", "2 + 12 = 14"]);
+        assert_eq!(steps("2+3*4", false), ["2 + 3 = 5", "5 \u{00d7} 4 = 20"]);
+        assert_eq!(steps("2^3^2", true), ["3^2 = 9", "2^9 = 512"]);
+        assert!(steps("42", true).is_empty());
+    }
+
+    #[test]
+    fn explain_functions_postfix_and_constants() {
+        assert_eq!(
+            steps("sqrt(9)+3!*2pi", true),
+            [
+                "\u{221a}(9) = 3",
+                "3! = 6",
+                "6 \u{00d7} 2 = 12",
+                "12 \u{00d7} \u{03c0} = 37.6991118431",
+                "3 + 37.6991118431 = 40.6991118431",
+            ]
+        );
+        assert_eq!(steps("(1-4)*2", true), ["1 \u{2212} 4 = -3", "(-3) \u{00d7} 2 = -6"]);
+    }
+
+    #[test]
+    fn explain_reports_errors() {
+        let expr = build(&parse_expression("1/(2-2)", &HashMap::new()).unwrap(), true).unwrap();
+        assert_eq!(explain(&expr, AngleMode::Degrees), Err("Division by zero".into()));
+    }
+
+    #[test]
+    fn build_rejects_incomplete() {
+        assert!(build(&[Token::BinaryOp(BinaryOp::Add)], true).is_err());
+        assert!(build(&[], true).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use super::types::*;
+
+const BASE: u64 = 1_000_000_000;
+
+// 2^53: past this an f64 no longer holds every integer.
+const MAX_EXACT_F64: f64 = 9_007_199_254_740_992.0;
+
+// Longer results are left to the float engine, which reports them as too
+// large instead of spending ever longer on multiplication.
+const MAX_DIGITS: usize = 10_000;
+
+/// An integer of any size, so `+ − ×` on whole numbers stay exact past 2^53.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    // Base 10⁹, least significant first, without trailing zeros.
+    limbs: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let mut limbs = Vec::with_capacity(digits.len() / 9 + 1);
+        let mut end = digits.len();
+        while end > 0 {
+            let start = end.saturating_sub(9);
+            limbs.push(digits[start..end].parse().ok()?);
+            end = start;
+        }
+        Some(Self { negative, limbs }.normalized())
+    }
+
+    /// The integer a whole f64 holds; None for fractions and non-finite values.
+    pub fn from_f64(val: f64) -> Option<Self> {
+        if !val.is_finite() || val.fract() != 0.0 {
+            return None;
+        }
+        Self::parse(&format!("{:.0}", val))
+    }
+
+    /// The nearest f64, infinite past its range.
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    pub fn digits(&self) -> usize {
+        match self.limbs.last() {
+            Some(top) => (self.limbs.len() - 1) * 9 + top.to_string().len(),
+            None => 1,
+        }
+    }
+
+    pub fn neg(&self) -> Self {
+        Self { negative: !self.negative, limbs: self.limbs.clone() }.normalized()
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        if self.negative == other.negative {
+            return Self { negative: self.negative, limbs: add_magnitudes(&self.limbs, &other.limbs) };
+        }
+        match compare_magnitudes(&self.limbs, &other.limbs) {
+            Ordering::Less => Self { negative: other.negative, limbs: sub_magnitudes(&other.limbs, &self.limbs) },
+            _ => Self { negative: self.negative, limbs: sub_magnitudes(&self.limbs, &other.limbs) },
+        }
+        .normalized()
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let cur = limbs[i + j] as u64 + a as u64 * b as u64 + carry;
+                limbs[i + j] = (cur % BASE) as u32;
+                carry = cur / BASE;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        Self { negative: self.negative != other.negative, limbs }.normalized()
+    }
+
+    fn normalized(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        if self.limbs.is_empty() {
+            self.negative = false;
+        }
+        self
+    }
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        out.push((sum % BASE) as u32);
+        carry = sum / BASE;
+    }
+    if carry > 0 {
+        out.push(carry as u32);
+    }
+    out
+}
+
+// `a - b` for `a >= b`.
+fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &limb) in a.iter().enumerate() {
+        let mut diff = limb as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if diff < 0 {
+            diff += BASE as i64;
+            borrow = 1;
+        }
+        out.push(diff as u32);
+    }
+    out
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some((top, rest)) = self.limbs.split_last() else { return f.write_str("0") };
+        if self.negative {
+            f.write_str("-")?;
+        }
+        write!(f, "{}", top)?;
+        for limb in rest.iter().rev() {
+            write!(f, "{:09}", limb)?;
+        }
+        Ok(())
+    }
+}
+
+/// A whole-number literal too large for an f64 to hold exactly, such as
+/// `99999999999999999`; None for anything an f64 keeps as typed.
+pub fn literal(text: &str) -> Option<BigInt> {
+    BigInt::parse(text).filter(|n| n.to_f64().abs() >= MAX_EXACT_F64)
+}
+
+/// Evaluates expressions made only of whole numbers, `+`, `−`, `×` and
+/// parentheses exactly. None for anything else, which the float engine
+/// handles: fractions, other operations, or numbers typed in a form such as
+/// `1e20` that was already rounded.
+pub fn evaluate(tokens: &[Token], standard_precedence: bool) -> Option<BigInt> {
+    let mut output: Vec<BigInt> = Vec::new();
+    let mut ops: Vec<Option<BinaryOp>> = Vec::new();
+    let apply = |output: &mut Vec<BigInt>, op: BinaryOp| -> Option<()> {
+        let b = output.pop()?;
+        let a = output.pop()?;
+        let result = match op {
+            BinaryOp::Add => a.add(&b),
+            BinaryOp::Subtract => a.sub(&b),
+            BinaryOp::Multiply => a.mul(&b),
+            _ => return None,
+        };
+        (result.digits() <= MAX_DIGITS).then(|| output.push(result))
+    };
+    for token in tokens {
+        match token {
+            Token::Number(n) if n.abs() <= MAX_EXACT_F64 => output.push(BigInt::from_f64(*n)?),
+            Token::Integer(n) => output.push(n.clone()),
+            Token::BinaryOp(op @ (BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply)) => {
+                while let Some(&Some(top)) = ops.last() {
+                    if top.precedence(standard_precedence) < op.precedence(standard_precedence) {
+                        break;
+                    }
+                    ops.pop();
+                    apply(&mut output, top)?;
+                }
+                ops.push(Some(*op));
+            }
+            Token::LeftParen => ops.push(None),
+            Token::RightParen => {
+                while let Some(op) = ops.pop()? {
+                    apply(&mut output, op)?;
+                }
+            }
+            _ => return None,
+        }
+    }
+    while let Some(op) = ops.pop() {
+        apply(&mut output, op?)?;
+    }
+    let result = output.pop()?;
+    output.is_empty().then_some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::eval;
+    use std::collections::HashMap;
+
+    fn big(text: &str) -> BigInt {
+        BigInt::parse(text).unwrap()
+    }
+
+    fn exact(input: &str) -> Option<String> {
+        let tokens = eval::parse_expression(input, &HashMap::new()).unwrap();
+        evaluate(&tokens, true).map(|n| n.to_string())
+    }
+
+    #[test]
+    fn arithmetic_is_exact() {
+        assert_eq!(big("99999999999999999").add(&big("1")).to_string(), "100000000000000000");
+        assert_eq!(big("1000000000000000000").sub(&big("1")).to_string(), "999999999999999999");
+        assert_eq!(big("5").sub(&big("12")).to_string(), "-7");
+        assert_eq!(big("-5").sub(&big("-5")).to_string(), "0");
+        assert_eq!(
+            big("123456789123456789").mul(&big("-987654321987654321")).to_string(),
+            "-121932631356500531347203169112635269"
+        );
+        assert_eq!(big("0").mul(&big("-3")).to_string(), "0");
+        assert_eq!(big("000120").to_string(), "120");
+    }
+
+    #[test]
+    fn converts_to_and_from_floats() {
+        assert_eq!(BigInt::from_f64(2f64.powi(60)).unwrap().to_string(), "1152921504606846976");
+        assert_eq!(BigInt::from_f64(-0.0).unwrap().to_string(), "0");
+        assert_eq!(BigInt::from_f64(1.5), None);
+        assert_eq!(big("99999999999999999").to_f64(), 1e17);
+        assert_eq!(big("12345").digits(), 5);
+    }
+
+    #[test]
+    fn only_large_whole_literals_need_one() {
+        assert_eq!(literal("9007199254740991"), None);
+        assert!(literal("9007199254740993").is_some());
+        assert_eq!(literal("1.5"), None);
+        assert_eq!(literal("12"), None);
+    }
+
+    #[test]
+    fn evaluates_whole_number_expressions() {
+        assert_eq!(exact("99999999999999999 + 1").as_deref(), Some("100000000000000000"));
+        assert_eq!(exact("2 + 3 * 4").as_deref(), Some("14"));
+        assert_eq!(exact("(2 + 3) * 4").as_deref(), Some("20"));
+        assert_eq!(exact("4294967296 * 4294967296 - 1").as_deref(), Some("18446744073709551615"));
+        assert_eq!(exact("10 / 2"), None);
+        assert_eq!(exact("1.5 + 1"), None);
+        assert_eq!(exact("1e20 + 1"), None);
+    }
+}
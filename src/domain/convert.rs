@@ -89,10 +89,68 @@ fn unit_factor(cat: ConvertCategory, unit: &str) -> f64 {
     }
 }
 
+/// A from/to unit choice, as offered by the "Convert to…" popover.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitPair {
+    pub category: ConvertCategory,
+    pub from: &'static str,
+    pub to: &'static str,
+}
+
+const fn pair(category: ConvertCategory, from: &'static str, to: &'static str) -> UnitPair {
+    UnitPair { category, from, to }
+}
+
+/// Offered when the user has no recent conversions of their own.
+pub const COMMON_PAIRS: &[UnitPair] = &[
+    pair(ConvertCategory::Length, "mi", "km"),
+    pair(ConvertCategory::Length, "km", "mi"),
+    pair(ConvertCategory::Temperature, "F", "C"),
+    pair(ConvertCategory::Temperature, "C", "F"),
+    pair(ConvertCategory::Weight, "lb", "kg"),
+    pair(ConvertCategory::Weight, "kg", "lb"),
+    pair(ConvertCategory::Length, "in", "cm"),
+    pair(ConvertCategory::Volume, "gal", "L"),
+];
+
+impl UnitPair {
+    pub fn apply(self, value: f64) -> f64 {
+        convert(self.category, self.from, self.to, value)
+    }
+
+    pub fn label(self) -> String {
+        format!("{} \u{2192} {}", unit_symbol(self.from), unit_symbol(self.to))
+    }
+}
+
+/// Temperature units are stored as bare letters; show them with a degree sign.
+pub fn unit_symbol(unit: &str) -> String {
+    match unit {
+        "C" | "F" => format!("\u{00b0}{}", unit),
+        other => other.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn common_pairs_use_known_units() {
+        for p in COMMON_PAIRS {
+            let units = p.category.units();
+            assert!(units.iter().any(|(u, _)| *u == p.from), "{}", p.from);
+            assert!(units.iter().any(|(u, _)| *u == p.to), "{}", p.to);
+        }
+    }
+
+    #[test]
+    fn unit_pair_apply_and_label() {
+        let p = pair(ConvertCategory::Temperature, "F", "C");
+        assert!((p.apply(212.0) - 100.0).abs() < 1e-9);
+        assert_eq!(p.label(), "\u{00b0}F \u{2192} \u{00b0}C");
+    }
+
     #[test]
     fn length_conversion() {
         let result = convert(ConvertCategory::Length, "km", "m", 1.0);
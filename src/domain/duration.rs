@@ -0,0 +1,156 @@
+use std::time::{Duration, Instant};
+
+/// A pausable stopwatch; time is passed in so it can be driven by a clock
+/// tick in the UI and by hand in tests.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Stopwatch {
+    started: Option<Instant>,
+    banked: Duration,
+}
+
+impl Stopwatch {
+    pub fn running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    /// Starts a stopped watch, or pauses a running one keeping its time.
+    pub fn toggle(&mut self, now: Instant) {
+        match self.started.take() {
+            Some(start) => self.banked += now.saturating_duration_since(start),
+            None => self.started = Some(now),
+        }
+    }
+
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        self.banked + self.started.map_or(Duration::ZERO, |s| now.saturating_duration_since(s))
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Sums durations like `1:30 + 45m - 0:10:30` into seconds. A term is
+/// `h:mm`, `h:mm:ss`, numbers with `h`/`m`/`s` units (`1h 15m`), or a bare
+/// number of hours.
+pub fn parse(text: &str) -> Result<f64, String> {
+    let bad = || format!("Bad duration \u{2018}{}\u{2019}", text.trim());
+    let mut total = 0.0;
+    let mut sign = 1.0;
+    let mut signed = false;
+    let mut term = String::new();
+    for c in text.chars() {
+        if !matches!(c, '+' | '-' | '\u{2212}') {
+            term.push(c);
+            continue;
+        }
+        if !term.trim().is_empty() {
+            total += sign * parse_term(term.trim())?;
+        } else if signed {
+            return Err(bad());
+        }
+        sign = if c == '+' { 1.0 } else { -1.0 };
+        signed = true;
+        term.clear();
+    }
+    if !term.trim().is_empty() {
+        Ok(total + sign * parse_term(term.trim())?)
+    } else if signed {
+        Err(bad())
+    } else {
+        Err("Enter a duration".into())
+    }
+}
+
+fn parse_term(term: &str) -> Result<f64, String> {
+    let bad = || format!("Bad duration \u{2018}{}\u{2019}", term);
+    let number = |s: &str| s.trim().parse::<f64>().ok().filter(|n| n.is_finite() && *n >= 0.0);
+    if term.contains(':') {
+        let parts: Vec<&str> = term.split(':').collect();
+        if parts.len() > 3 {
+            return Err(bad());
+        }
+        let mut secs = 0.0;
+        for (i, part) in parts.iter().enumerate() {
+            let n = number(part).ok_or_else(bad)?;
+            // Minutes and seconds after the hours must be below 60.
+            if i > 0 && n >= 60.0 {
+                return Err(bad());
+            }
+            secs += n * 3600.0 / 60f64.powi(i as i32);
+        }
+        return Ok(secs);
+    }
+    if let Some(hours) = number(term) {
+        return Ok(hours * 3600.0);
+    }
+    let mut secs = 0.0;
+    let mut rest = term.trim_start();
+    while !rest.is_empty() {
+        let split = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).ok_or_else(bad)?;
+        let n = number(&rest[..split]).ok_or_else(bad)?;
+        rest = rest[split..].trim_start();
+        let end = rest.find(|c: char| !c.is_alphabetic()).unwrap_or(rest.len());
+        secs += n * match &rest[..end] {
+            "h" | "hr" | "hrs" => 3600.0,
+            "m" | "min" | "mins" => 60.0,
+            "s" | "sec" | "secs" => 1.0,
+            _ => return Err(bad()),
+        };
+        rest = rest[end..].trim_start();
+    }
+    Ok(secs)
+}
+
+/// `h:mm:ss`, rounded to the second; hours are not wrapped at 24.
+pub fn format(secs: f64) -> String {
+    let sign = if secs < -0.5 { "-" } else { "" };
+    let total = secs.abs().round() as u64;
+    format!("{}{}:{:02}:{:02}", sign, total / 3600, total / 60 % 60, total % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sums_durations() {
+        assert_eq!(parse("1:30"), Ok(5400.0));
+        assert_eq!(parse("0:10:30"), Ok(630.0));
+        assert_eq!(parse("1h 15m + 45m"), Ok(7200.0));
+        assert_eq!(parse("1.5 - 20m"), Ok(4200.0));
+        assert_eq!(parse("-30s + 1m"), Ok(30.0));
+        assert_eq!(parse("2:00 \u{2212} 0:30"), Ok(5400.0));
+    }
+
+    #[test]
+    fn rejects_malformed_durations() {
+        assert!(parse("").is_err());
+        assert!(parse("1:75").is_err());
+        assert!(parse("3 parsecs").is_err());
+        assert!(parse("1h +").is_err());
+        assert!(parse("1h + - 2m").is_err());
+    }
+
+    #[test]
+    fn formats_as_clock_time() {
+        assert_eq!(format(0.0), "0:00:00");
+        assert_eq!(format(5400.0), "1:30:00");
+        assert_eq!(format(93_784.4), "26:03:04");
+        assert_eq!(format(-90.0), "-0:01:30");
+    }
+
+    #[test]
+    fn stopwatch_banks_time_across_pauses() {
+        let t0 = Instant::now();
+        let mut watch = Stopwatch::default();
+        watch.toggle(t0);
+        assert!(watch.running());
+        watch.toggle(t0 + Duration::from_secs(90));
+        assert_eq!(watch.elapsed(t0 + Duration::from_secs(500)), Duration::from_secs(90));
+        watch.toggle(t0 + Duration::from_secs(600));
+        assert_eq!(watch.elapsed(t0 + Duration::from_secs(630)), Duration::from_secs(120));
+        watch.reset();
+        assert_eq!(watch.elapsed(t0 + Duration::from_secs(700)), Duration::ZERO);
+    }
+}
@@ -1,5 +1,13 @@
+use std::collections::VecDeque;
+
+use super::ast::{self, CopyStyle};
+use super::bigint::{self, BigInt};
+use super::convert::{self, UnitPair};
 use super::eval;
+use super::interval;
+use super::money;
 use super::types::*;
+use super::uncertain::{self, Uncertain};
 
 #[derive(Debug, Clone)]
 struct Snapshot {
@@ -10,6 +18,8 @@ struct Snapshot {
     error: Option<String>,
     open_parens: usize,
     user_calculated: bool,
+    conversion: Option<String>,
+    repeat: Option<(BinaryOp, Token)>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -18,6 +28,10 @@ pub struct EvalSettings {
     pub standard_precedence: bool,
     pub auto_evaluate: bool,
     pub max_history: usize,
+    /// Evaluate over `[lo, hi]` bounds instead of single values.
+    pub interval_arithmetic: bool,
+    /// Show results to the cent, rounded half to even.
+    pub money: bool,
 }
 
 impl Default for EvalSettings {
@@ -27,10 +41,16 @@ impl Default for EvalSettings {
             standard_precedence: true,
             auto_evaluate: true,
             max_history: 200,
+            interval_arithmetic: false,
+            money: false,
         }
     }
 }
 
+/// The calculator behind one tab. Nothing fed to it, keys or text, makes it
+/// panic: input it cannot take is refused with an error message, text is
+/// capped at `eval::MAX_INPUT_LEN` characters and expression trees at
+/// `ast::MAX_DEPTH` levels, so frontends can pass it untrusted input.
 #[derive(Debug)]
 pub struct Engine {
     tokens: Vec<Token>,
@@ -38,11 +58,17 @@ pub struct Engine {
     result: Option<f64>,
     last_value: f64,
     memory: f64,
-    angle_mode: AngleMode,
     error: Option<String>,
     open_parens: usize,
     user_calculated: bool,
-    undo_stack: Vec<Snapshot>,
+    // Set while the shown result came from "Convert to…", e.g. "12 mi → km".
+    conversion: Option<String>,
+    // The trailing operation of the last calculation, which `=` repeats.
+    repeat: Option<(BinaryOp, Token)>,
+    undo_stack: VecDeque<Snapshot>,
+    // Set while `input_str` feeds in characters, so a paste costs one undo
+    // snapshot instead of a copy of the expression per character.
+    batching: bool,
     pub history: Vec<HistoryEntry>,
     pub memory_slots: Vec<MemorySlot>,
     pub pinned: Vec<PinnedCalc>,
@@ -58,11 +84,13 @@ impl Engine {
             result: None,
             last_value: 0.0,
             memory: 0.0,
-            angle_mode: settings.angle_mode,
             error: None,
             open_parens: 0,
             user_calculated: false,
-            undo_stack: Vec::new(),
+            conversion: None,
+            repeat: None,
+            undo_stack: VecDeque::new(),
+            batching: false,
             history: Vec::new(),
             memory_slots: Vec::new(),
             pinned: Vec::new(),
@@ -71,8 +99,8 @@ impl Engine {
         }
     }
 
-    fn save_snapshot(&mut self) {
-        self.undo_stack.push(Snapshot {
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
             tokens: self.tokens.clone(),
             buffer: self.buffer.clone(),
             result: self.result,
@@ -80,21 +108,41 @@ impl Engine {
             error: self.error.clone(),
             open_parens: self.open_parens,
             user_calculated: self.user_calculated,
-        });
+            conversion: self.conversion.clone(),
+            repeat: self.repeat.clone(),
+        }
+    }
+
+    fn restore(&mut self, snap: Snapshot) {
+        self.tokens = snap.tokens;
+        self.buffer = snap.buffer;
+        self.result = snap.result;
+        self.last_value = snap.last_value;
+        self.error = snap.error;
+        self.open_parens = snap.open_parens;
+        self.user_calculated = snap.user_calculated;
+        self.conversion = snap.conversion;
+        self.repeat = snap.repeat;
+    }
+
+    fn save_snapshot(&mut self) {
+        if self.batching {
+            return;
+        }
+        let snap = self.snapshot();
+        self.push_undo(snap);
+    }
+
+    fn push_undo(&mut self, snap: Snapshot) {
+        self.undo_stack.push_back(snap);
         if self.undo_stack.len() > 100 {
-            self.undo_stack.remove(0);
+            self.undo_stack.pop_front();
         }
     }
 
     pub fn undo(&mut self) {
-        if let Some(snap) = self.undo_stack.pop() {
-            self.tokens = snap.tokens;
-            self.buffer = snap.buffer;
-            self.result = snap.result;
-            self.last_value = snap.last_value;
-            self.error = snap.error;
-            self.open_parens = snap.open_parens;
-            self.user_calculated = snap.user_calculated;
+        if let Some(snap) = self.undo_stack.pop_back() {
+            self.restore(snap);
         }
     }
 
@@ -106,20 +154,16 @@ impl Engine {
             return None;
         }
         let mut tokens = self.tokens.clone();
-        if !self.buffer.is_empty() {
-            if let Ok(val) = self.buffer.parse::<f64>() {
-                tokens.push(Token::Number(val));
-            }
-        }
+        tokens.extend(self.buffer_token());
         if tokens.is_empty() {
             return None;
         }
         for _ in 0..self.open_parens {
             tokens.push(Token::RightParen);
         }
-        match eval::evaluate(&tokens, self.angle_mode, self.settings.standard_precedence) {
+        match eval::evaluate(&tokens, self.settings.angle_mode, self.settings.standard_precedence) {
             Ok(val) => {
-                let text = format_number_default(val);
+                let text = value_text(&tokens, val, &self.settings).ok()?;
                 let current = self.main_display_text();
                 if text != current { Some(text) } else { None }
             }
@@ -133,7 +177,7 @@ impl Engine {
         }
         if self.user_calculated {
             if let Some(result) = self.result {
-                return format_number_default(result);
+                return value_text(&self.tokens, result, &self.settings).unwrap_or_else(|_| format_number_default(result));
             }
         }
         let mut s = String::new();
@@ -147,8 +191,27 @@ impl Engine {
         s
     }
 
+    /// The calculated result on screen, when its text is rounded.
+    pub fn rounded_result(&self) -> Option<f64> {
+        if self.error.is_some()
+            || !self.user_calculated
+            || bounded(&self.tokens, &self.settings)
+            || uncertain::has_uncertainty(&self.tokens)
+            || exact_integer(&self.tokens, &self.settings).is_some()
+        {
+            return None;
+        }
+        if self.settings.money {
+            return self.result.filter(|&r| money::lost(r) != 0.0);
+        }
+        self.result.filter(|&r| format_number_rounded(r).1)
+    }
+
     pub fn secondary_display_text(&self) -> String {
         if self.user_calculated && self.result.is_some() {
+            if let Some(ref conversion) = self.conversion {
+                return format!("{} =", conversion);
+            }
             let mut s = String::new();
             for token in &self.tokens {
                 s.push_str(&token_display(token));
@@ -164,6 +227,9 @@ impl Engine {
     }
 
     pub fn expression_text(&self) -> String {
+        if let Some(conversion) = self.conversion.as_ref().filter(|_| self.user_calculated) {
+            return format!("{} =", conversion);
+        }
         let mut s = String::new();
         for token in &self.tokens {
             s.push_str(&token_display(token));
@@ -175,8 +241,101 @@ impl Engine {
         s
     }
 
+    // The whole expression as typed so far, with open parentheses closed.
+    fn complete_tokens(&self) -> Option<Vec<Token>> {
+        if self.error.is_some() {
+            return None;
+        }
+        let mut tokens = self.tokens.clone();
+        tokens.extend(self.buffer_token());
+        if tokens.is_empty() {
+            return None;
+        }
+        for _ in 0..self.open_parens {
+            tokens.push(Token::RightParen);
+        }
+        Some(tokens)
+    }
+
+    pub fn copy_text(&self, style: CopyStyle) -> Option<String> {
+        let tokens = self.complete_tokens()?;
+        let result = match self.result.filter(|_| self.user_calculated) {
+            Some(r) => r,
+            None => eval::evaluate(&tokens, self.settings.angle_mode, self.settings.standard_precedence).ok()?,
+        };
+        if let Some(n) = exact_integer(&tokens, &self.settings) {
+            return Some(format!("{} = {}", exact_expression(&tokens, style), n));
+        }
+        let expr = ast::build(&tokens, self.settings.standard_precedence).ok()?;
+        if bounded(&tokens, &self.settings) || self.settings.money || uncertain::has_uncertainty(&tokens) {
+            let text = value_text(&tokens, result, &self.settings).ok()?;
+            return Some(match style {
+                CopyStyle::Plain => format!("{} = {}", ast::to_plain(&expr), text),
+                CopyStyle::Latex => format!("{} = {}", ast::to_latex(&expr), text.replace('\u{00b1}', "\\pm")),
+            });
+        }
+        Some(ast::render_equation(&expr, result, style))
+    }
+
+    /// How the current expression is evaluated, one operation per step.
+    pub fn steps(&self) -> Result<Vec<ast::Step>, String> {
+        let tokens = self.complete_tokens().ok_or("Nothing to explain")?;
+        let expr = ast::build(&tokens, self.settings.standard_precedence)?;
+        ast::explain(&expr, self.settings.angle_mode)
+    }
+
+    // For an expression ending in an operator, `5 +`, the value before it.
+    fn missing_operand(&self) -> Option<Token> {
+        let [prefix @ .., Token::BinaryOp(op)] = self.tokens.as_slice() else { return None };
+        if *op == BinaryOp::PlusMinus || self.open_parens > 0 {
+            return None;
+        }
+        if let Some(n) = exact_integer(prefix, &self.settings) {
+            return Some(integer_token(&n));
+        }
+        eval::evaluate(prefix, self.settings.angle_mode, self.settings.standard_precedence).ok().map(Token::Number)
+    }
+
+    // The shown result as the start of a new expression, keeping its bounds
+    // or error.
+    fn result_tokens(&self, result: f64) -> Vec<Token> {
+        if let Some(n) = exact_integer(&self.tokens, &self.settings) {
+            return vec![integer_token(&n)];
+        }
+        if bounded(&self.tokens, &self.settings) {
+            let bounds = ast::build(&self.tokens, self.settings.standard_precedence)
+                .and_then(|expr| interval::evaluate(&expr, self.settings.angle_mode));
+            return match bounds {
+                Ok(iv) if iv.lo != iv.hi => vec![Token::Interval(iv.lo, iv.hi)],
+                _ => vec![Token::Number(result)],
+            };
+        }
+        match uncertain_value(&self.tokens, &self.settings) {
+            Ok(Some(u)) if u.error > 0.0 => {
+                vec![Token::Number(result), Token::BinaryOp(BinaryOp::PlusMinus), Token::Number(u.error)]
+            }
+            _ => vec![Token::Number(result)],
+        }
+    }
+
     pub fn angle_mode(&self) -> AngleMode {
-        self.angle_mode
+        self.settings.angle_mode
+    }
+
+    pub fn set_interval_arithmetic(&mut self, on: bool) {
+        self.settings.interval_arithmetic = on;
+    }
+
+    pub fn money(&self) -> bool {
+        self.settings.money
+    }
+
+    pub fn set_money(&mut self, on: bool) {
+        self.settings.money = on;
+    }
+
+    pub fn has_error(&self) -> bool {
+        self.error.is_some()
     }
 
     pub fn has_memory(&self) -> bool {
@@ -198,7 +357,7 @@ impl Engine {
         self.save_snapshot();
         self.start_fresh_if_needed();
         if self.buffer.is_empty()
-            && matches!(self.tokens.last(), Some(Token::Constant(..) | Token::RightParen | Token::PostfixOp(_)))
+            && matches!(self.tokens.last(), Some(Token::Constant(..) | Token::Interval(..) | Token::RightParen | Token::PostfixOp(_)))
         {
             self.tokens.push(Token::BinaryOp(BinaryOp::Multiply));
         }
@@ -209,15 +368,125 @@ impl Engine {
     }
 
     pub fn input_decimal(&mut self) {
-        if self.error.is_some() { return; }
+        if self.buffer.starts_with('[') {
+            self.input_interval_char('.');
+            return;
+        }
+        if self.error.is_some() || (self.result.is_none() && self.buffer.contains('.')) { return; }
         self.save_snapshot();
         self.start_fresh_if_needed();
         if self.buffer.is_empty() { self.buffer.push('0'); }
-        if !self.buffer.contains('.') { self.buffer.push('.'); }
+        self.buffer.push('.');
+    }
+
+    /// Single entry point for typed, clicked and pasted characters. Returns
+    /// false, leaving the input untouched, for characters that are not part
+    /// of an expression or would make the current number invalid: a second
+    /// decimal point, a sign after a lone minus, `)` with nothing open, or
+    /// `%`/`!` with nothing before them.
+    pub fn input_char(&mut self, c: char) -> bool {
+        if self.error.is_some() { return false; }
+        if self.buffer.starts_with('[') {
+            return self.input_interval_char(c);
+        }
+        let fresh = self.result.is_some();
+        let binary = match c {
+            '+' => Some(BinaryOp::Add),
+            '-' | '\u{2212}' => Some(BinaryOp::Subtract),
+            '*' | '\u{00d7}' => Some(BinaryOp::Multiply),
+            '/' | '\u{00f7}' => Some(BinaryOp::Divide),
+            '^' => Some(BinaryOp::Power),
+            '\u{00b1}' => Some(BinaryOp::PlusMinus),
+            _ => None,
+        };
+        if let Some(op) = binary {
+            if self.buffer == "-" && !fresh { return false; }
+            self.input_binary_op(op);
+            return true;
+        }
+        match c {
+            '0'..='9' => self.input_digit(c),
+            '.' if !fresh && self.buffer.contains('.') => return false,
+            '.' => self.input_decimal(),
+            '%' | '!' if !self.has_operand() => return false,
+            '%' => self.input_postfix_op(PostfixOp::Percent),
+            '!' => self.input_postfix_op(PostfixOp::Factorial),
+            '(' => self.input_left_paren(),
+            '[' => self.start_interval(),
+            ')' if self.open_parens == 0 => return false,
+            ')' => self.input_right_paren(),
+            '\u{03c0}' => self.input_constant(std::f64::consts::PI, "\u{03c0}"),
+            _ if c.is_whitespace() => {}
+            _ => return false,
+        }
+        true
+    }
+
+    // Whether a postfix `%` or `!` has something to apply to.
+    fn has_operand(&self) -> bool {
+        self.result.is_some()
+            || self.buffer.parse::<f64>().is_ok()
+            || matches!(self.tokens.last(), Some(Token::Number(_) | Token::Integer(_) | Token::Interval(..) | Token::Constant(..) | Token::RightParen | Token::PostfixOp(_)))
+    }
+
+    // `[lo, hi]` is typed into the buffer and becomes one token at `]`.
+    fn start_interval(&mut self) {
+        self.save_snapshot();
+        self.start_fresh_if_needed();
+        if !self.buffer.is_empty() {
+            self.finalize_buffer();
+            self.tokens.push(Token::BinaryOp(BinaryOp::Multiply));
+        } else if matches!(self.tokens.last(), Some(Token::Number(_) | Token::Integer(_) | Token::Interval(..) | Token::Constant(..) | Token::RightParen | Token::PostfixOp(_))) {
+            self.tokens.push(Token::BinaryOp(BinaryOp::Multiply));
+        }
+        self.buffer.push('[');
+    }
+
+    fn input_interval_char(&mut self, c: char) -> bool {
+        match c {
+            ']' => {
+                let Ok((lo, hi)) = interval::parse_literal(&format!("{}]", self.buffer)) else { return false };
+                self.save_snapshot();
+                self.buffer.clear();
+                self.tokens.push(Token::Interval(lo, hi));
+            }
+            '0'..='9' | '.' | ',' | '-' | '\u{2212}' => {
+                self.save_snapshot();
+                self.buffer.push(if c == '\u{2212}' { '-' } else { c });
+            }
+            _ if c.is_whitespace() => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Feeds pasted text through `input_char`. Either every character is
+    /// accepted and the paste is a single undo step, or nothing changes and
+    /// the first rejected character is reported.
+    pub fn input_str(&mut self, text: &str) -> Result<(), String> {
+        if text.chars().nth(eval::MAX_INPUT_LEN).is_some() {
+            return Err("Expression is too long".into());
+        }
+        let before = self.snapshot();
+        self.batching = true;
+        let rejected = text.chars().find(|&c| !self.input_char(c));
+        self.batching = false;
+        if let Some(c) = rejected {
+            self.restore(before);
+            return Err(format!("Unexpected \u{2018}{}\u{2019}", c));
+        }
+        self.push_undo(before);
+        Ok(())
     }
 
     pub fn input_binary_op(&mut self, op: BinaryOp) {
         if self.error.is_some() { return; }
+        if self.buffer.starts_with('[') {
+            if op == BinaryOp::Subtract {
+                self.input_interval_char('-');
+            }
+            return;
+        }
         self.save_snapshot();
 
         if op == BinaryOp::Subtract && self.buffer.is_empty() && self.result.is_none() {
@@ -232,9 +501,9 @@ impl Engine {
         if self.tokens.is_empty() && self.buffer.is_empty() && self.result.is_none() { return; }
 
         if let Some(result) = self.result.take() {
-            self.tokens.clear();
-            self.tokens.push(Token::Number(result));
+            self.tokens = self.result_tokens(result);
             self.user_calculated = false;
+            self.conversion = None;
         } else {
             self.finalize_buffer();
         }
@@ -250,14 +519,15 @@ impl Engine {
         self.save_snapshot();
 
         if let Some(result) = self.result.take() {
+            let operand = self.result_tokens(result);
             self.tokens.clear();
             self.user_calculated = false;
             self.error = None;
-            match eval::apply_unary(func, result, self.angle_mode) {
+            match eval::apply_unary(func, result, self.settings.angle_mode) {
                 Ok(val) => {
                     self.tokens.push(Token::UnaryFunc(func));
                     self.tokens.push(Token::LeftParen);
-                    self.tokens.push(Token::Number(result));
+                    self.tokens.extend(operand);
                     self.tokens.push(Token::RightParen);
                     self.result = Some(val);
                     self.last_value = val;
@@ -271,7 +541,7 @@ impl Engine {
         if !self.buffer.is_empty() {
             self.finalize_buffer();
             self.tokens.push(Token::BinaryOp(BinaryOp::Multiply));
-        } else if matches!(self.tokens.last(), Some(Token::Number(_) | Token::Constant(..) | Token::RightParen | Token::PostfixOp(_))) {
+        } else if matches!(self.tokens.last(), Some(Token::Number(_) | Token::Integer(_) | Token::Interval(..) | Token::Constant(..) | Token::RightParen | Token::PostfixOp(_))) {
             self.tokens.push(Token::BinaryOp(BinaryOp::Multiply));
         }
         self.tokens.push(Token::UnaryFunc(func));
@@ -284,12 +554,19 @@ impl Engine {
         self.save_snapshot();
 
         if let Some(result) = self.result.take() {
+            let operand = self.result_tokens(result);
             self.tokens.clear();
             self.user_calculated = false;
             self.error = None;
             match eval::apply_postfix(op, result) {
                 Ok(val) => {
-                    self.tokens.push(Token::Number(result));
+                    if operand.len() > 1 {
+                        self.tokens.push(Token::LeftParen);
+                        self.tokens.extend(operand);
+                        self.tokens.push(Token::RightParen);
+                    } else {
+                        self.tokens.extend(operand);
+                    }
                     self.tokens.push(Token::PostfixOp(op));
                     self.result = Some(val);
                     self.last_value = val;
@@ -301,7 +578,7 @@ impl Engine {
 
         self.finalize_buffer();
         self.tokens.push(Token::PostfixOp(op));
-        match eval::evaluate(&self.tokens, self.angle_mode, self.settings.standard_precedence) {
+        match eval::evaluate(&self.tokens, self.settings.angle_mode, self.settings.standard_precedence) {
             Ok(val) => {
                 self.result = Some(val);
                 self.last_value = val;
@@ -317,7 +594,7 @@ impl Engine {
         if !self.buffer.is_empty() {
             self.finalize_buffer();
             self.tokens.push(Token::BinaryOp(BinaryOp::Multiply));
-        } else if matches!(self.tokens.last(), Some(Token::Number(_) | Token::Constant(..) | Token::RightParen | Token::PostfixOp(_))) {
+        } else if matches!(self.tokens.last(), Some(Token::Number(_) | Token::Integer(_) | Token::Interval(..) | Token::Constant(..) | Token::RightParen | Token::PostfixOp(_))) {
             self.tokens.push(Token::BinaryOp(BinaryOp::Multiply));
         }
         self.tokens.push(Token::Constant(name, value));
@@ -331,7 +608,7 @@ impl Engine {
         if !self.buffer.is_empty() {
             self.finalize_buffer();
             self.tokens.push(Token::BinaryOp(BinaryOp::Multiply));
-        } else if matches!(self.tokens.last(), Some(Token::Number(_) | Token::Constant(..) | Token::RightParen | Token::PostfixOp(_))) {
+        } else if matches!(self.tokens.last(), Some(Token::Number(_) | Token::Integer(_) | Token::Interval(..) | Token::Constant(..) | Token::RightParen | Token::PostfixOp(_))) {
             self.tokens.push(Token::BinaryOp(BinaryOp::Multiply));
         }
         self.tokens.push(Token::LeftParen);
@@ -355,36 +632,57 @@ impl Engine {
         self.tokens.push(Token::BinaryOp(BinaryOp::Power));
     }
 
+    /// After a result, `=` repeats the last operation on it (`2+3==` gives
+    /// 8); a digit starts a new calculation and an operator chains from the
+    /// result. With nothing entered, `=` does nothing rather than produce 0,
+    /// and a missing last operand is the value so far: `5 + =` gives 10.
     pub fn calculate(&mut self, timestamp: u64, session: u64) {
         if self.error.is_some() { return; }
-        self.save_snapshot();
-        self.finalize_buffer();
-        for _ in 0..self.open_parens {
-            self.tokens.push(Token::RightParen);
+        if self.user_calculated {
+            let (Some(result), Some((op, operand))) = (self.result, self.repeat.clone()) else { return };
+            self.save_snapshot();
+            let mut tokens = self.result_tokens(result);
+            tokens.extend([Token::BinaryOp(op), operand]);
+            self.tokens = tokens;
+        } else {
+            if self.buffer.starts_with('[') && !self.input_interval_char(']') { return; }
+            if self.tokens.is_empty() && self.buffer.parse::<f64>().is_err() { return; }
+            self.save_snapshot();
+            self.finalize_buffer();
+            if let Some(operand) = self.missing_operand() {
+                self.tokens.push(operand);
+            }
+            for _ in 0..self.open_parens {
+                self.tokens.push(Token::RightParen);
+            }
+            self.open_parens = 0;
         }
-        self.open_parens = 0;
+        self.conversion = None;
 
-        match eval::evaluate(&self.tokens, self.angle_mode, self.settings.standard_precedence) {
-            Ok(val) => {
+        let evaluated = eval::evaluate(&self.tokens, self.settings.angle_mode, self.settings.standard_precedence)
+            .map(|val| exact_integer(&self.tokens, &self.settings).map_or(val, |n| n.to_f64()))
+            .and_then(|val| Ok((val, value_text(&self.tokens, val, &self.settings)?)));
+        match evaluated {
+            Ok((val, text)) => {
                 let mut expr_str = String::new();
                 for token in &self.tokens {
                     expr_str.push_str(&token_display(token));
                 }
-                self.history.push(HistoryEntry {
-                    expression: expr_str,
-                    result_text: format_number_default(val),
-                    result: val,
-                    timestamp,
-                    session,
-                });
-                let max = self.settings.max_history;
-                if self.history.len() > max {
-                    self.history.remove(0);
-                }
+                let mut entry = HistoryEntry::new(expr_str, val, timestamp, session);
+                entry.result_text = text;
+                self.push_history(entry);
                 self.result = Some(val);
                 self.last_value = val;
                 self.error = None;
                 self.user_calculated = true;
+                self.repeat = match self.tokens.as_slice() {
+                    [.., Token::BinaryOp(op), operand @ (Token::Number(_) | Token::Integer(_))]
+                        if *op != BinaryOp::PlusMinus =>
+                    {
+                        Some((*op, operand.clone()))
+                    }
+                    _ => None,
+                };
             }
             Err(msg) => {
                 self.error = Some(msg);
@@ -393,6 +691,75 @@ impl Engine {
         }
     }
 
+    /// Replaces the shown value with its conversion and records it in
+    /// history like a calculation. Returns false while an error is shown.
+    pub fn convert_result(&mut self, pair: UnitPair, timestamp: u64, session: u64) -> bool {
+        if self.error.is_some() { return false; }
+        let value = self.current_value();
+        let converted = pair.apply(value);
+        self.save_snapshot();
+        if let Err(msg) = eval::finite(converted) {
+            self.error = Some(msg);
+            self.result = None;
+            return true;
+        }
+        let description = format!(
+            "{} {} \u{2192} {}",
+            format_number_default(value),
+            convert::unit_symbol(pair.from),
+            convert::unit_symbol(pair.to)
+        );
+        self.push_history(HistoryEntry::new(description.clone(), converted, timestamp, session));
+        self.tokens.clear();
+        self.buffer.clear();
+        self.open_parens = 0;
+        self.result = Some(converted);
+        self.last_value = converted;
+        self.user_calculated = true;
+        self.conversion = Some(description);
+        self.repeat = None;
+        true
+    }
+
+    /// History results, most recent first: the values of `ans1`, `ans2`, ….
+    pub fn answers(&self) -> Vec<f64> {
+        self.history.iter().rev().map(|e| e.result).collect()
+    }
+
+    pub fn load_expression(
+        &mut self,
+        input: &str,
+        plugins: &std::collections::HashMap<String, String>,
+    ) -> Result<(), String> {
+        let mut tokens = eval::parse_expression_with(input, plugins, &self.answers())?;
+        if tokens.is_empty() {
+            return Err("Empty expression".into());
+        }
+        self.save_snapshot();
+        self.buffer = match tokens.last() {
+            Some(Token::Number(n)) => {
+                // Keep the number as typed; formatting it again would cut
+                // `0.12345678901234` to the display's ten decimals.
+                let text = trailing_literal(input, *n).map_or_else(|| format_number_exact(*n), str::to_string);
+                tokens.pop();
+                text
+            }
+            Some(Token::Integer(n)) => {
+                let text = n.to_string();
+                tokens.pop();
+                text
+            }
+            _ => String::new(),
+        };
+        self.tokens = tokens;
+        self.result = None;
+        self.error = None;
+        self.open_parens = 0;
+        self.user_calculated = false;
+        self.conversion = None;
+        Ok(())
+    }
+
     pub fn clear(&mut self) {
         self.tokens.clear();
         self.buffer.clear();
@@ -401,6 +768,8 @@ impl Engine {
         self.error = None;
         self.open_parens = 0;
         self.user_calculated = false;
+        self.conversion = None;
+        self.repeat = None;
     }
 
     pub fn backspace(&mut self) {
@@ -458,20 +827,51 @@ impl Engine {
         let expr = self.expression_text();
         self.pinned.push(PinnedCalc { label, expression: expr, result: val });
     }
-    pub fn clear_history(&mut self) { self.history.clear(); }
+    pub fn clear_history(&mut self) { self.history.retain(|e| e.starred); }
+
+    pub fn toggle_star(&mut self, index: usize) {
+        if let Some(entry) = self.history.get_mut(index) {
+            entry.starred = !entry.starred;
+        }
+    }
+
+    /// Sets or, given a blank label, removes the label of a history entry.
+    pub fn set_history_label(&mut self, index: usize, label: &str) {
+        if let Some(entry) = self.history.get_mut(index) {
+            let label = label.trim();
+            entry.label = (!label.is_empty()).then(|| label.to_string());
+        }
+    }
+
+    // Trims to `max_history` by dropping the oldest unstarred entries.
+    fn push_history(&mut self, entry: HistoryEntry) {
+        self.history.push(entry);
+        while self.history.len() > self.settings.max_history {
+            match self.history.iter().position(|e| !e.starred) {
+                Some(i) => { self.history.remove(i); }
+                None => break,
+            }
+        }
+    }
 
     pub fn toggle_angle_mode(&mut self) {
-        self.angle_mode = match self.angle_mode {
+        self.settings.angle_mode = match self.settings.angle_mode {
             AngleMode::Radians => AngleMode::Degrees,
             AngleMode::Degrees => AngleMode::Radians,
         };
     }
 
+    // The number being typed, kept whole when it is too long for an f64.
+    fn buffer_token(&self) -> Option<Token> {
+        let val = self.buffer.parse::<f64>().ok()?;
+        Some(bigint::literal(&self.buffer).map_or(Token::Number(val), Token::Integer))
+    }
+
     fn finalize_buffer(&mut self) {
         if !self.buffer.is_empty() {
-            if let Ok(val) = self.buffer.parse::<f64>() {
-                self.tokens.push(Token::Number(val));
-                self.last_value = val;
+            if let Some(token) = self.buffer_token() {
+                self.last_value = self.buffer.parse().unwrap_or(self.last_value);
+                self.tokens.push(token);
             }
             self.buffer.clear();
         }
@@ -483,8 +883,89 @@ impl Engine {
             self.result = None;
             self.error = None;
             self.user_calculated = false;
+            self.conversion = None;
+        }
+    }
+}
+
+// The exact result of an expression of whole numbers and `+ − ×`, which
+// floats get wrong once an operand or a step passes 2^53, even when the
+// result is small: `9007199254740993 - 9007199254740992` is 1, not 0.
+pub fn exact_integer(tokens: &[Token], settings: &EvalSettings) -> Option<BigInt> {
+    if settings.interval_arithmetic || settings.money {
+        return None;
+    }
+    bigint::evaluate(tokens, settings.standard_precedence)
+}
+
+// Whether `tokens` are evaluated as intervals: always in interval mode,
+// and whenever `[lo, hi]` is typed, since reading it any other way
+// would not keep the result within its bounds.
+fn bounded(tokens: &[Token], settings: &EvalSettings) -> bool {
+    settings.interval_arithmetic || interval::has_literal(tokens)
+}
+
+// `value ± error` for expressions with a `±` in them, None for the rest.
+fn uncertain_value(tokens: &[Token], settings: &EvalSettings) -> Result<Option<Uncertain>, String> {
+    if !uncertain::has_uncertainty(tokens) {
+        return Ok(None);
+    }
+    let expr = ast::build(tokens, settings.standard_precedence)?;
+    uncertain::evaluate(&expr, settings.angle_mode).map(Some)
+}
+
+/// How `val`, the float value of `tokens`, reads on screen: bounds in
+/// interval mode, every digit of a long whole number, `value ± error` when
+/// the expression has an uncertainty, cents in money mode and plainly
+/// otherwise.
+pub fn value_text(tokens: &[Token], val: f64, settings: &EvalSettings) -> Result<String, String> {
+    if bounded(tokens, settings) {
+        let expr = ast::build(tokens, settings.standard_precedence)?;
+        return interval::evaluate(&expr, settings.angle_mode).map(interval::format);
+    }
+    if let Some(n) = exact_integer(tokens, settings) {
+        return Ok(n.to_string());
+    }
+    Ok(match uncertain_value(tokens, settings)? {
+        Some(u) => uncertain::format(u),
+        None if settings.money => money::format(val),
+        None => format_number_default(val),
+    })
+}
+
+// A whole number as a token, `Integer` only when a float cannot hold it.
+fn integer_token(n: &BigInt) -> Token {
+    bigint::literal(&n.to_string()).map_or(Token::Number(n.to_f64()), Token::Integer)
+}
+
+// The number literal `input` ends with, as typed, when it reads as `val`;
+// with its minus sign when the sign belongs to it.
+fn trailing_literal(input: &str, val: f64) -> Option<&str> {
+    let input = input.trim_end();
+    let start = input.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.').len();
+    let signed = input[..start].strip_suffix('-').map(str::len);
+    [signed, Some(start)]
+        .into_iter()
+        .flatten()
+        .map(|i| &input[i..])
+        .find(|text| text.parse::<f64>() == Ok(val))
+}
+
+// A whole-number `+ − ×` expression with every digit, which the float
+// tree behind `ast::to_plain` would round.
+fn exact_expression(tokens: &[Token], style: CopyStyle) -> String {
+    let mut s = String::new();
+    for token in tokens {
+        match (token, style) {
+            (Token::BinaryOp(BinaryOp::Multiply), CopyStyle::Latex) => s.push_str(" \\times "),
+            (Token::BinaryOp(BinaryOp::Subtract), CopyStyle::Latex) => s.push_str(" - "),
+            (Token::BinaryOp(op), _) => s.push_str(&format!(" {} ", op.symbol())),
+            (Token::LeftParen, CopyStyle::Latex) => s.push_str("\\left("),
+            (Token::RightParen, CopyStyle::Latex) => s.push_str("\\right)"),
+            _ => s.push_str(&token_display(token)),
         }
     }
+    s
 }
 
 #[cfg(test)]
@@ -495,6 +976,171 @@ mod tests {
         Engine::new(EvalSettings::default())
     }
 
+    #[test]
+    fn convert_result_replaces_value() {
+        let mut e = engine();
+        e.input_digit('1');
+        e.input_digit('0');
+        let pair = UnitPair { category: ConvertCategory::Length, from: "mi", to: "km" };
+        assert!(e.convert_result(pair, 0, 0));
+        assert_eq!(e.main_display_text(), "16.09344");
+        assert_eq!(e.secondary_display_text(), "10 mi \u{2192} km =");
+        assert_eq!(e.history.last().unwrap().expression, "10 mi \u{2192} km");
+
+        e.input_binary_op(BinaryOp::Multiply);
+        e.input_digit('2');
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "32.18688");
+        assert_eq!(e.secondary_display_text(), "16.09344\u{00d7}2=");
+
+        e.undo();
+        e.undo();
+        e.undo();
+        assert_eq!(e.main_display_text(), "16.09344");
+    }
+
+    #[test]
+    fn uncertainty_carries_through_results() {
+        let mut e = engine();
+        e.input_str("12.3\u{00b1}0.2").unwrap();
+        assert_eq!(e.auto_eval().as_deref(), Some("12.3 \u{00b1} 0.2"));
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "12.3 \u{00b1} 0.2");
+        assert_eq!(e.history.last().unwrap().result_text, "12.3 \u{00b1} 0.2");
+        assert_eq!(e.rounded_result(), None);
+        // "=" doesn't repeat "± 0.2".
+        e.calculate(0, 0);
+        assert_eq!(e.history.len(), 1);
+
+        e.input_char('*');
+        e.input_digit('2');
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "24.6 \u{00b1} 0.4");
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "49.2 \u{00b1} 0.8");
+    }
+
+    #[test]
+    fn whole_numbers_stay_exact_past_two_to_the_53() {
+        let mut e = engine();
+        for c in "99999999999999999+1".chars() {
+            e.input_char(c);
+        }
+        assert_eq!(e.auto_eval().as_deref(), Some("100000000000000000"));
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "100000000000000000");
+        assert_eq!(e.history.last().unwrap().result_text, "100000000000000000");
+        assert_eq!(e.rounded_result(), None);
+
+        e.input_char('-');
+        e.input_digit('1');
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "99999999999999999");
+        e.input_char('*');
+        e.input_str("99999999999999999").unwrap();
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "9999999999999999800000000000000001");
+        assert_eq!(
+            e.copy_text(CopyStyle::Plain).as_deref(),
+            Some("99999999999999999 \u{00d7} 99999999999999999 = 9999999999999999800000000000000001")
+        );
+
+        // Anything but whole-number + − × is left to floats.
+        e.input_char('/');
+        e.input_digit('3');
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "3.3333333333333333e33");
+    }
+
+    #[test]
+    fn small_results_of_large_whole_numbers_stay_exact() {
+        for (input, expected) in [
+            ("99999999999999999-99999999999999998", "1"),
+            ("9007199254740993-9007199254740992", "1"),
+            ("(99999999999999999-99999999999999998)*3", "3"),
+        ] {
+            let mut e = engine();
+            e.input_str(input).unwrap();
+            e.calculate(0, 0);
+            assert_eq!(e.main_display_text(), expected, "{}", input);
+            assert_eq!(e.result, Some(expected.parse().unwrap()));
+        }
+    }
+
+    #[test]
+    fn money_mode_shows_cents_and_flags_lost_ones() {
+        let mut e = Engine::new(EvalSettings { money: true, ..EvalSettings::default() });
+        e.input_str("100/3").unwrap();
+        assert_eq!(e.auto_eval().as_deref(), Some("33.33"));
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "33.33");
+        assert_eq!(e.history.last().unwrap().result_text, "33.33");
+        assert_eq!(e.rounded_result(), Some(100.0 / 3.0));
+        assert_eq!(e.copy_text(CopyStyle::Plain).as_deref(), Some("100 \u{00f7} 3 = 33.33"));
+
+        e.input_str("0.1+0.2").unwrap();
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "0.30");
+        assert_eq!(e.rounded_result(), None);
+        e.input_str("0.125").unwrap();
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "0.12");
+    }
+
+    #[test]
+    fn intervals_are_typed_and_bounded() {
+        let mut e = engine();
+        e.input_str("[1,2]+[").unwrap();
+        assert_eq!(e.main_display_text(), "[1, 2]+[");
+        e.input_binary_op(BinaryOp::Subtract);
+        e.input_digit('1');
+        assert!(e.input_char(','));
+        assert!(!e.input_char('+'));
+        e.input_digit('1');
+        e.calculate(0, 0);
+        // Typed bounds are kept outside interval mode too: [0, 3] holds
+        // every sum of a value in [1, 2] and one in [-1, 1].
+        assert_eq!(e.main_display_text(), "[0, 3]");
+        e.input_char('*');
+        e.input_digit('2');
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "[0, 6]");
+
+        let mut e = Engine::new(EvalSettings { interval_arithmetic: true, ..EvalSettings::default() });
+        e.input_str("[1,2]*[-1,3]").unwrap();
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "[-2, 6]");
+        e.input_char('+');
+        e.input_digit('1');
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "[-1, 7]");
+        assert_eq!(e.history.last().unwrap().result_text, "[-1, 7]");
+    }
+
+    #[test]
+    fn starred_history_survives_clear_and_trimming() {
+        let mut e = Engine::new(EvalSettings { max_history: 2, ..EvalSettings::default() });
+        for d in ['1', '2', '3'] {
+            e.input_digit(d);
+            e.calculate(0, 0);
+        }
+        assert_eq!(e.history.len(), 2);
+        e.toggle_star(0);
+        e.set_history_label(0, "  monthly budget ");
+        e.input_digit('4');
+        e.calculate(0, 0);
+        let exprs: Vec<&str> = e.history.iter().map(|h| h.expression.as_str()).collect();
+        assert_eq!(exprs, ["2", "4"]);
+        assert_eq!(e.history[0].label.as_deref(), Some("monthly budget"));
+
+        e.clear_history();
+        assert_eq!(e.history.len(), 1);
+        assert!(e.history[0].starred);
+
+        e.set_history_label(0, " ");
+        assert_eq!(e.history[0].label, None);
+    }
+
     #[test]
     fn expression_display() {
         let mut e = engine();
@@ -515,6 +1161,37 @@ mod tests {
         assert_eq!(e.main_display_text(), "8");
     }
 
+    #[test]
+    fn equals_after_an_operator_repeats_the_value() {
+        let mut e = engine();
+        e.input_digit('5');
+        e.calculate(0, 0);
+        e.input_binary_op(BinaryOp::Add);
+        e.calculate(0, 0);
+        assert!(!e.has_error());
+        assert_eq!(e.expression_text(), "5+5=");
+        assert_eq!(e.main_display_text(), "10");
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "15");
+
+        let mut e = engine();
+        e.input_str("2*3+").unwrap();
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "12");
+    }
+
+    #[test]
+    fn equals_repeats_whole_number_operands_past_two_to_the_53() {
+        let mut e = engine();
+        e.input_str("99999999999999999+99999999999999999").unwrap();
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "199999999999999998");
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "299999999999999997");
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "399999999999999996");
+    }
+
     #[test]
     fn chain_from_result() {
         let mut e = engine();
@@ -616,6 +1293,64 @@ mod tests {
         assert_eq!(e.main_display_text(), "0");
     }
 
+    #[test]
+    fn error_is_sticky_until_cleared() {
+        let mut e = engine();
+        e.input_digit('1');
+        e.input_binary_op(BinaryOp::Divide);
+        e.input_digit('0');
+        e.input_binary_op(BinaryOp::Add);
+        e.input_digit('5');
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "Division by zero");
+        e.input_binary_op(BinaryOp::Add);
+        e.input_digit('5');
+        e.calculate(0, 0);
+        assert!(e.has_error());
+        assert_eq!(e.main_display_text(), "Division by zero");
+        assert!(e.history.is_empty());
+        e.clear();
+        e.input_digit('5');
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "5");
+    }
+
+    #[test]
+    fn overflow_and_undefined_results() {
+        let mut e = engine();
+        e.input_digit('1');
+        e.input_ee();
+        e.input_digit('3');
+        e.input_digit('0');
+        e.input_digit('8');
+        e.input_binary_op(BinaryOp::Multiply);
+        e.input_digit('1');
+        e.input_digit('0');
+        assert_eq!(e.auto_eval(), None);
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "Overflow");
+        e.input_binary_op(BinaryOp::Subtract);
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "Overflow");
+        assert!(e.history.is_empty());
+
+        e.clear();
+        e.input_digit('2');
+        e.input_unary_func(UnaryFunc::Asin);
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "Undefined");
+
+        e.clear();
+        e.input_digit('2');
+        e.input_digit('0');
+        e.input_digit('0');
+        e.calculate(0, 0);
+        e.input_postfix_op(PostfixOp::Factorial);
+        assert_eq!(e.main_display_text(), "Overflow");
+        e.undo();
+        assert_eq!(e.main_display_text(), "200");
+    }
+
     #[test]
     fn parentheses() {
         let mut e = engine();
@@ -630,6 +1365,92 @@ mod tests {
         assert_eq!(e.main_display_text(), "20");
     }
 
+    #[test]
+    fn copy_text_uses_expression_tree() {
+        let mut e = engine();
+        e.input_left_paren();
+        e.input_digit('2');
+        e.input_binary_op(BinaryOp::Add);
+        e.input_digit('3');
+        e.input_right_paren();
+        e.input_binary_op(BinaryOp::Divide);
+        e.input_digit('4');
+        e.calculate(0, 0);
+        assert_eq!(e.copy_text(CopyStyle::Plain).unwrap(), "(2 + 3) \u{00f7} 4 = 1.25");
+        assert_eq!(e.copy_text(CopyStyle::Latex).unwrap(), "\\frac{2 + 3}{4} = 1.25");
+        e.clear();
+        assert!(e.copy_text(CopyStyle::Latex).is_none());
+    }
+
+    #[test]
+    fn steps_for_pending_and_finished_expressions() {
+        let mut e = engine();
+        e.input_digit('2');
+        e.input_binary_op(BinaryOp::Add);
+        e.input_digit('3');
+        e.input_binary_op(BinaryOp::Multiply);
+        e.input_left_paren();
+        e.input_digit('4');
+        let steps: Vec<String> = e.steps().unwrap().iter().map(ast::Step::render).collect();
+        assert_eq!(steps, ["3 \u{00d7} 4 = 12", "2 + 12 = 14"]);
+        e.calculate(0, 0);
+        assert_eq!(e.steps().unwrap().len(), 2);
+        e.clear();
+        assert_eq!(e.steps(), Err("Nothing to explain".into()));
+    }
+
+    #[test]
+    fn load_expression_replaces_input() {
+        let mut e = engine();
+        e.input_digit('9');
+        e.load_expression("2\u{00d7}(3+4)", &std::collections::HashMap::new()).unwrap();
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "14");
+
+        e.load_expression("12", &std::collections::HashMap::new()).unwrap();
+        e.input_digit('5');
+        assert_eq!(e.main_display_text(), "125");
+        assert!(e.load_expression("  ", &std::collections::HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn loaded_numbers_keep_every_typed_digit() {
+        let plugins = std::collections::HashMap::new();
+        let mut e = engine();
+        e.load_expression("0.12345678901234", &plugins).unwrap();
+        assert_eq!(e.main_display_text(), "0.12345678901234");
+        e.calculate(0, 0);
+        assert_eq!(e.result, Some(0.12345678901234));
+
+        e.load_expression("2 \u{00d7} -1.2345678901234", &plugins).unwrap();
+        assert_eq!(e.buffer, "-1.2345678901234");
+        e.load_expression("5-3", &plugins).unwrap();
+        assert_eq!(e.buffer, "3");
+
+        // History keeps the digits too, so loading an entry back is lossless.
+        e.load_expression("1 + 0.12345678901234", &plugins).unwrap();
+        e.calculate(0, 0);
+        let expression = e.history.last().unwrap().expression.clone();
+        e.load_expression(&expression, &plugins).unwrap();
+        assert_eq!(e.buffer, "0.12345678901234");
+    }
+
+    #[test]
+    fn answers_follow_history() {
+        let mut e = engine();
+        let plugins = std::collections::HashMap::new();
+        for expr in ["2+3", "10\u{00d7}4"] {
+            e.load_expression(expr, &plugins).unwrap();
+            e.calculate(0, 0);
+        }
+        assert_eq!(e.answers(), [40.0, 5.0]);
+        e.load_expression("ans2 + ans1 \u{00f7} 8", &plugins).unwrap();
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "10");
+        assert_eq!(e.answers(), [10.0, 40.0, 5.0]);
+        assert!(e.load_expression("ans4", &plugins).is_err());
+    }
+
     #[test]
     fn undo_works() {
         let mut e = engine();
@@ -735,6 +1556,117 @@ mod tests {
         assert_eq!(e.main_display_text(), "Division by zero");
     }
 
+    #[test]
+    fn input_char_rejects_invalid_entry() {
+        let mut e = engine();
+        for c in "1.2".chars() {
+            assert!(e.input_char(c));
+        }
+        assert!(!e.input_char('.'));
+        assert!(!e.input_char('x'));
+        assert!(!e.input_char(')'));
+        assert!(e.input_char('*'));
+        assert!(e.input_char('-'));
+        assert!(!e.input_char('-'));
+        assert!(!e.input_char('+'));
+        assert!(e.input_char('3'));
+        assert_eq!(e.expression_text(), "1.2\u{00d7}-3");
+        e.undo();
+        e.undo();
+        assert_eq!(e.expression_text(), "1.2\u{00d7}");
+
+        e.clear();
+        assert!(!e.input_char('%'));
+        assert!(e.input_char('5'));
+        assert!(e.input_char('!'));
+        e.calculate(0, 0);
+        assert!(e.input_char('.'));
+        assert_eq!(e.main_display_text(), "0.");
+    }
+
+    #[test]
+    fn pasted_text_is_all_or_nothing() {
+        let mut e = engine();
+        e.input_digit('7');
+        assert_eq!(e.input_str("+1.2.3"), Err("Unexpected \u{2018}.\u{2019}".into()));
+        assert_eq!(e.expression_text(), "7");
+        assert!(e.input_str("+ (2 \u{00f7} 4)").is_ok());
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "7.5");
+        e.undo();
+        e.undo();
+        assert_eq!(e.expression_text(), "7");
+    }
+
+    #[test]
+    fn hostile_input_is_refused_without_panicking() {
+        use crate::domain::{highlight, worksheet};
+        let plugins = std::collections::HashMap::from([("double".to_string(), "2*x".to_string())]);
+        let pieces = [
+            "(", ")", "[", "]", ",", "1", "9", "0", ".", "e", "pi", "\u{03c0}", "+", "-", "\u{2212}", "*",
+            "\u{00d7}", "/", "^", "%", "!", "\u{00b1}", "+/-", " of ", "mod", "sin", "ln(", "ans", "ans9",
+            "double(", "\u{00e9}", "\u{1f600}", "\u{0301}", "\u{202e}", "#", "=", " ", "\n", "1e308", "170!",
+        ];
+        // A fixed xorshift sequence, so failures reproduce.
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as usize
+        };
+        let mut inputs: Vec<String> = (0..2000)
+            .map(|_| (0..next() % 24).map(|_| pieces[next() % pieces.len()]).collect())
+            .collect();
+        inputs.push("(".repeat(40_000) + "1" + &")".repeat(40_000));
+        inputs.push("(".repeat(ast::MAX_DEPTH - 1) + "1" + &")".repeat(ast::MAX_DEPTH - 1));
+        inputs.push("sqrt(".repeat(5000) + "2");
+        inputs.push("1+".repeat(5000) + "1");
+        inputs.push("2^".repeat(5000) + "2");
+        inputs.push("9".repeat(400) + "^9^9^9");
+        inputs.push("1".repeat(eval::MAX_INPUT_LEN + 1));
+
+        for input in &inputs {
+            let mut e = engine();
+            let _ = e.load_expression(input, &plugins);
+            e.calculate(0, 0);
+            let _ = e.steps();
+            let _ = e.copy_text(CopyStyle::Latex);
+            e.set_interval_arithmetic(true);
+            e.calculate(0, 0);
+            let _ = e.input_str(input);
+            e.calculate(0, 0);
+            let _ = e.steps();
+            let _ = e.main_display_text();
+            if let Ok(expr) = eval::parse_expression(input, &plugins).and_then(|t| ast::build(&t, true)) {
+                let _ = uncertain::evaluate(&expr, AngleMode::Radians);
+            }
+            let _ = worksheet::evaluate(std::slice::from_ref(input), &plugins, EvalSettings::default());
+            let _ = highlight::check(input);
+        }
+
+        let mut e = engine();
+        e.load_expression(&("sqrt(".repeat(ast::MAX_DEPTH) + "2"), &plugins).unwrap();
+        assert_eq!(e.steps(), Err("Expression is nested too deeply".into()));
+        let nested = "(".repeat(ast::MAX_DEPTH - 1) + "1" + &")".repeat(ast::MAX_DEPTH - 1);
+        e.load_expression(&nested, &plugins).unwrap();
+        assert_eq!(e.steps().map(|s| s.len()), Ok(0));
+    }
+
+    #[test]
+    fn long_paste_is_one_undo_step() {
+        let mut e = engine();
+        e.input_digit('1');
+        let text = "+12.5".repeat(5000);
+        assert!(e.input_str(&text).is_ok());
+        assert_eq!(e.expression_text().len(), 1 + text.len());
+        e.calculate(0, 0);
+        assert_eq!(e.main_display_text(), "62501");
+        e.undo();
+        e.undo();
+        assert_eq!(e.expression_text(), "1");
+    }
+
     #[test]
     fn ee_input() {
         let mut e = engine();
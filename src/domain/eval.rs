@@ -1,5 +1,8 @@
 use std::collections::HashMap;
 
+use super::bigint;
+use super::engine::{self, EvalSettings};
+use super::interval::{self, Interval};
 use super::types::*;
 
 enum ShuntOp {
@@ -9,15 +12,29 @@ enum ShuntOp {
 }
 
 pub fn evaluate(tokens: &[Token], angle_mode: AngleMode, standard_precedence: bool) -> Result<f64, String> {
+    evaluate_in(tokens, angle_mode, standard_precedence, &mut Vec::new(), &mut Vec::new())
+}
+
+// `evaluate` with the stacks passed in, so `Evaluator` can reuse them.
+fn evaluate_in(
+    tokens: &[Token],
+    angle_mode: AngleMode,
+    standard_precedence: bool,
+    output: &mut Vec<f64>,
+    ops: &mut Vec<ShuntOp>,
+) -> Result<f64, String> {
     if tokens.is_empty() {
         return Ok(0.0);
     }
-    let mut output: Vec<f64> = Vec::new();
-    let mut ops: Vec<ShuntOp> = Vec::new();
+    output.clear();
+    ops.clear();
 
     for token in tokens {
         match token {
             Token::Number(n) | Token::Constant(_, n) => output.push(*n),
+            Token::Integer(n) => output.push(n.to_f64()),
+            // Plain evaluation carries the midpoint; `interval` keeps the bounds.
+            Token::Interval(lo, hi) => output.push(Interval { lo: *lo, hi: *hi }.midpoint()),
             Token::BinaryOp(op) => {
                 while let Some(top) = ops.last() {
                     let pop = match top {
@@ -33,7 +50,7 @@ pub fn evaluate(tokens: &[Token], angle_mode: AngleMode, standard_precedence: bo
                     };
                     if pop {
                         let popped = ops.pop().unwrap();
-                        apply_shunt(&mut output, &popped, angle_mode)?;
+                        apply_shunt(output, &popped, angle_mode)?;
                     } else {
                         break;
                     }
@@ -48,14 +65,14 @@ pub fn evaluate(tokens: &[Token], angle_mode: AngleMode, standard_precedence: bo
                         break;
                     }
                     let popped = ops.pop().unwrap();
-                    apply_shunt(&mut output, &popped, angle_mode)?;
+                    apply_shunt(output, &popped, angle_mode)?;
                 }
                 if matches!(ops.last(), Some(ShuntOp::LeftParen)) {
                     ops.pop();
                 }
                 if matches!(ops.last(), Some(ShuntOp::Func(_))) {
                     let popped = ops.pop().unwrap();
-                    apply_shunt(&mut output, &popped, angle_mode)?;
+                    apply_shunt(output, &popped, angle_mode)?;
                 }
             }
             Token::PostfixOp(p) => {
@@ -69,7 +86,7 @@ pub fn evaluate(tokens: &[Token], angle_mode: AngleMode, standard_precedence: bo
         if matches!(op, ShuntOp::LeftParen) {
             continue;
         }
-        apply_shunt(&mut output, &op, angle_mode)?;
+        apply_shunt(output, &op, angle_mode)?;
     }
 
     output.pop().ok_or_else(|| "Empty expression".to_string())
@@ -91,8 +108,20 @@ fn apply_shunt(output: &mut Vec<f64>, op: &ShuntOp, angle_mode: AngleMode) -> Re
     Ok(())
 }
 
-fn apply_binary(op: BinaryOp, a: f64, b: f64) -> Result<f64, String> {
-    match op {
+/// Turns a non-finite result into an error, so `inf` and `NaN` never reach
+/// the display or feed into the next operation.
+pub fn finite(val: f64) -> Result<f64, String> {
+    if val.is_nan() {
+        Err("Undefined".into())
+    } else if val.is_infinite() {
+        Err("Overflow".into())
+    } else {
+        Ok(val)
+    }
+}
+
+pub fn apply_binary(op: BinaryOp, a: f64, b: f64) -> Result<f64, String> {
+    let result = match op {
         BinaryOp::Add => Ok(a + b),
         BinaryOp::Subtract => Ok(a - b),
         BinaryOp::Multiply => Ok(a * b),
@@ -104,6 +133,8 @@ fn apply_binary(op: BinaryOp, a: f64, b: f64) -> Result<f64, String> {
             }
         }
         BinaryOp::Power => Ok(a.powf(b)),
+        // Plain evaluation carries the central value; `uncertain` tracks the error.
+        BinaryOp::PlusMinus => Ok(a),
         BinaryOp::Modulo => {
             if b.abs() < f64::EPSILON {
                 Err("Division by zero".to_string())
@@ -111,7 +142,8 @@ fn apply_binary(op: BinaryOp, a: f64, b: f64) -> Result<f64, String> {
                 Ok(a % b)
             }
         }
-    }
+    };
+    result.and_then(finite)
 }
 
 pub fn apply_unary(func: UnaryFunc, a: f64, angle_mode: AngleMode) -> Result<f64, String> {
@@ -124,7 +156,7 @@ pub fn apply_unary(func: UnaryFunc, a: f64, angle_mode: AngleMode) -> Result<f64
         AngleMode::Degrees => v * 180.0 / std::f64::consts::PI,
     };
 
-    match func {
+    let result = match func {
         UnaryFunc::Sin => Ok(to_rad(a).sin()),
         UnaryFunc::Cos => Ok(to_rad(a).cos()),
         UnaryFunc::Tan => Ok(to_rad(a).tan()),
@@ -146,11 +178,12 @@ pub fn apply_unary(func: UnaryFunc, a: f64, angle_mode: AngleMode) -> Result<f64
         UnaryFunc::Cbrt => Ok(a.cbrt()),
         UnaryFunc::Abs => Ok(a.abs()),
         UnaryFunc::Exp => Ok(a.exp()),
-    }
+    };
+    result.and_then(finite)
 }
 
 pub fn apply_postfix(op: PostfixOp, val: f64) -> Result<f64, String> {
-    match op {
+    let result = match op {
         PostfixOp::Square => Ok(val * val),
         PostfixOp::Cube => Ok(val * val * val),
         PostfixOp::Reciprocal => {
@@ -175,23 +208,83 @@ pub fn apply_postfix(op: PostfixOp, val: f64) -> Result<f64, String> {
             Ok(result)
         }
         PostfixOp::Percent => Ok(val / 100.0),
-    }
+    };
+    result.and_then(finite)
+}
+
+// Same wording as the worksheet's syntax check.
+fn malformed(number: &str) -> String {
+    format!("Malformed number \u{2018}{}\u{2019}", number)
 }
 
+/// The longest text, in characters, that the parser takes.
+pub const MAX_INPUT_LEN: usize = 100_000;
+
+/// Turns text into tokens. Any text is either tokenized or refused with an
+/// error, never a panic; text over `MAX_INPUT_LEN` characters is refused.
 pub fn parse_expression(input: &str, plugins: &HashMap<String, String>) -> Result<Vec<Token>, String> {
+    parse_expression_with(input, plugins, &[])
+}
+
+/// Like `parse_expression`, with `answers` (most recent first) standing in
+/// for `ans1`, `ans2`, …; a bare `ans` is `ans1`.
+pub fn parse_expression_with(
+    input: &str,
+    plugins: &HashMap<String, String>,
+    answers: &[f64],
+) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    tokenize_into(input, plugins, answers, &mut tokens, &mut Vec::new())?;
+    Ok(tokens)
+}
+
+// The characters of `input` with the spellings the tokenizer does not
+// handle itself rewritten: `of` as `*`, `+/-` as `±` and the typographic
+// `× ÷ −` as `* / -`. `chars` is cleared first.
+fn normalize_into(input: &str, chars: &mut Vec<char>) {
+    chars.clear();
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with(" of ") {
+            chars.extend([' ', '*', ' ']);
+            rest = &rest[4..];
+            continue;
+        }
+        if rest.starts_with("+/-") {
+            chars.push('\u{00b1}');
+            rest = &rest[3..];
+            continue;
+        }
+        chars.push(match c {
+            '\u{00d7}' => '*',
+            '\u{00f7}' => '/',
+            '\u{2212}' => '-',
+            c => c,
+        });
+        rest = &rest[c.len_utf8()..];
+    }
+}
+
+// `parse_expression_with` writing into `tokens`, which is cleared first;
+// `chars` is scratch space.
+fn tokenize_into(
+    input: &str,
+    plugins: &HashMap<String, String>,
+    answers: &[f64],
+    tokens: &mut Vec<Token>,
+    chars: &mut Vec<char>,
+) -> Result<(), String> {
+    tokens.clear();
+    if input.chars().nth(MAX_INPUT_LEN).is_some() {
+        return Err("Expression is too long".into());
+    }
     let input = input.trim();
     if input.is_empty() {
-        return Ok(vec![]);
+        return Ok(());
     }
 
-    let input = input
-        .replace(" of ", " * ")
-        .replace('\u{00d7}', "*")
-        .replace('\u{00f7}', "/")
-        .replace('\u{2212}', "-");
-
-    let mut tokens = Vec::new();
-    let chars: Vec<char> = input.chars().collect();
+    normalize_into(input, chars);
+    let chars = chars.as_slice();
     let mut i = 0;
 
     while i < chars.len() {
@@ -204,19 +297,20 @@ pub fn parse_expression(input: &str, plugins: &HashMap<String, String>) -> Resul
                     i += 1;
                 }
                 let num_str: String = chars[start..i].iter().collect();
-                let val: f64 = num_str.parse().map_err(|_| "Invalid number".to_string())?;
+                let val: f64 = num_str.parse().map_err(|_| malformed(&num_str))?;
+                let number = bigint::literal(&num_str).map_or(Token::Number(val), Token::Integer);
 
                 if i < chars.len() && chars[i] == '%' {
-                    tokens.push(Token::Number(val));
+                    tokens.push(number);
                     tokens.push(Token::PostfixOp(PostfixOp::Percent));
                     i += 1;
                 } else if i < chars.len()
                     && (chars[i] == '(' || chars[i].is_alphabetic() || chars[i] == '\u{03c0}')
                 {
-                    tokens.push(Token::Number(val));
+                    tokens.push(number);
                     tokens.push(Token::BinaryOp(BinaryOp::Multiply));
                 } else {
-                    tokens.push(Token::Number(val));
+                    tokens.push(number);
                 }
             }
             '+' => { tokens.push(Token::BinaryOp(BinaryOp::Add)); i += 1; }
@@ -230,8 +324,9 @@ pub fn parse_expression(input: &str, plugins: &HashMap<String, String>) -> Resul
                         i += 1;
                     }
                     let num_str: String = chars[start..i].iter().collect();
-                    let val: f64 = num_str.parse::<f64>().map(|v| -v).map_err(|_| "Invalid number".to_string())?;
-                    tokens.push(Token::Number(val));
+                    let val: f64 = num_str.parse::<f64>().map(|v| -v).map_err(|_| malformed(&num_str))?;
+                    let literal = bigint::literal(&format!("-{}", num_str));
+                    tokens.push(literal.map_or(Token::Number(val), Token::Integer));
                 } else {
                     tokens.push(Token::BinaryOp(BinaryOp::Subtract));
                     i += 1;
@@ -240,17 +335,31 @@ pub fn parse_expression(input: &str, plugins: &HashMap<String, String>) -> Resul
             '*' => { tokens.push(Token::BinaryOp(BinaryOp::Multiply)); i += 1; }
             '/' => { tokens.push(Token::BinaryOp(BinaryOp::Divide)); i += 1; }
             '^' => { tokens.push(Token::BinaryOp(BinaryOp::Power)); i += 1; }
+            '\u{00b1}' => { tokens.push(Token::BinaryOp(BinaryOp::PlusMinus)); i += 1; }
             '(' => {
-                if matches!(tokens.last(), Some(Token::RightParen | Token::Number(_) | Token::Constant(..))) {
+                if matches!(tokens.last(), Some(Token::RightParen | Token::Number(_) | Token::Integer(_) | Token::Interval(..) | Token::Constant(..))) {
                     tokens.push(Token::BinaryOp(BinaryOp::Multiply));
                 }
                 tokens.push(Token::LeftParen);
                 i += 1;
             }
             ')' => { tokens.push(Token::RightParen); i += 1; }
+            '[' => {
+                let start = i;
+                while i < chars.len() && chars[i] != ']' {
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                let text: String = chars[start..i].iter().collect();
+                let (lo, hi) = interval::parse_literal(&text)?;
+                if matches!(tokens.last(), Some(Token::RightParen | Token::Number(_) | Token::Integer(_) | Token::Interval(..) | Token::Constant(..))) {
+                    tokens.push(Token::BinaryOp(BinaryOp::Multiply));
+                }
+                tokens.push(Token::Interval(lo, hi));
+            }
             '!' => { tokens.push(Token::PostfixOp(PostfixOp::Factorial)); i += 1; }
             '\u{03c0}' => {
-                if matches!(tokens.last(), Some(Token::Number(_) | Token::Constant(..) | Token::RightParen)) {
+                if matches!(tokens.last(), Some(Token::Number(_) | Token::Integer(_) | Token::Interval(..) | Token::Constant(..) | Token::RightParen)) {
                     tokens.push(Token::BinaryOp(BinaryOp::Multiply));
                 }
                 tokens.push(Token::Constant("\u{03c0}", std::f64::consts::PI));
@@ -266,7 +375,7 @@ pub fn parse_expression(input: &str, plugins: &HashMap<String, String>) -> Resul
 
                 let need_mul = matches!(
                     tokens.last(),
-                    Some(Token::Number(_) | Token::Constant(..) | Token::RightParen)
+                    Some(Token::Number(_) | Token::Integer(_) | Token::Interval(..) | Token::Constant(..) | Token::RightParen)
                 );
 
                 match word_lower.as_str() {
@@ -274,6 +383,20 @@ pub fn parse_expression(input: &str, plugins: &HashMap<String, String>) -> Resul
                         if need_mul { tokens.push(Token::BinaryOp(BinaryOp::Multiply)); }
                         tokens.push(Token::Constant("\u{03c0}", std::f64::consts::PI));
                     }
+                    "ans" => {
+                        let start = i;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                        let digits: String = chars[start..i].iter().collect();
+                        let n: usize = if digits.is_empty() { 1 } else { digits.parse().unwrap_or(0) };
+                        let value = n
+                            .checked_sub(1)
+                            .and_then(|k| answers.get(k))
+                            .ok_or_else(|| format!("No result for ans{}", n))?;
+                        if need_mul { tokens.push(Token::BinaryOp(BinaryOp::Multiply)); }
+                        tokens.push(Token::Number(*value));
+                    }
                     "e" if i >= chars.len() || chars[i] != '(' => {
                         if need_mul { tokens.push(Token::BinaryOp(BinaryOp::Multiply)); }
                         tokens.push(Token::Constant("e", std::f64::consts::E));
@@ -306,7 +429,7 @@ pub fn parse_expression(input: &str, plugins: &HashMap<String, String>) -> Resul
                             }
                         } else if word_lower == "mod" {
                             tokens.push(Token::BinaryOp(BinaryOp::Modulo));
-                        } else if let Some(result) = eval_plugin_function(&word_lower, &chars, &mut i, plugins) {
+                        } else if let Some(result) = eval_plugin_function(&word_lower, chars, &mut i, plugins) {
                             if need_mul { tokens.push(Token::BinaryOp(BinaryOp::Multiply)); }
                             tokens.push(Token::Number(result));
                         }
@@ -323,7 +446,79 @@ pub fn parse_expression(input: &str, plugins: &HashMap<String, String>) -> Resul
         tokens.push(Token::RightParen);
     }
 
-    Ok(tokens)
+    Ok(())
+}
+
+pub fn evaluate_str(
+    input: &str,
+    plugins: &HashMap<String, String>,
+    angle_mode: AngleMode,
+    standard_precedence: bool,
+) -> Result<f64, String> {
+    let tokens = parse_expression(input, plugins)?;
+    if tokens.is_empty() {
+        return Err("Empty expression".into());
+    }
+    evaluate(&tokens, angle_mode, standard_precedence)
+}
+
+/// A result as the calculator shows it: `value` to carry on with, and
+/// `text` with every digit, bound or error the expression keeps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluated {
+    pub value: f64,
+    pub text: String,
+}
+
+/// Evaluates one expression after another with the same plugins and
+/// settings, reusing its token buffer and stacks instead of allocating them
+/// for every expression. Batch input and worksheets, which re-evaluate
+/// every line after each edit, go through this.
+pub struct Evaluator<'a> {
+    plugins: &'a HashMap<String, String>,
+    settings: EvalSettings,
+    tokens: Vec<Token>,
+    chars: Vec<char>,
+    output: Vec<f64>,
+    ops: Vec<ShuntOp>,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(plugins: &'a HashMap<String, String>, settings: EvalSettings) -> Self {
+        Self {
+            plugins,
+            settings,
+            tokens: Vec::new(),
+            chars: Vec::new(),
+            output: Vec::new(),
+            ops: Vec::new(),
+        }
+    }
+
+    /// Evaluates `input` as the engine would after `=`: a whole-number
+    /// `+ − ×` expression exactly, bounds in interval mode, cents in money
+    /// mode.
+    pub fn evaluate(&mut self, input: &str) -> Result<Evaluated, String> {
+        tokenize_into(input, self.plugins, &[], &mut self.tokens, &mut self.chars)?;
+        if self.tokens.is_empty() {
+            return Err("Empty expression".into());
+        }
+        let settings = &self.settings;
+        let val = evaluate_in(&self.tokens, settings.angle_mode, settings.standard_precedence, &mut self.output, &mut self.ops)?;
+        let value = engine::exact_integer(&self.tokens, settings).map_or(val, |n| n.to_f64());
+        let text = engine::value_text(&self.tokens, value, settings)?;
+        Ok(Evaluated { value, text })
+    }
+}
+
+/// Evaluates each of `inputs`, sharing one `Evaluator` between them.
+pub fn evaluate_many(
+    inputs: &[&str],
+    plugins: &HashMap<String, String>,
+    settings: EvalSettings,
+) -> Vec<Result<Evaluated, String>> {
+    let mut evaluator = Evaluator::new(plugins, settings);
+    inputs.iter().map(|input| evaluator.evaluate(input)).collect()
 }
 
 fn eval_plugin_function(name: &str, chars: &[char], i: &mut usize, plugins: &HashMap<String, String>) -> Option<f64> {
@@ -476,6 +671,16 @@ mod tests {
         assert!(evaluate(&tokens, AngleMode::Degrees, true).is_err());
     }
 
+    #[test]
+    fn non_finite_results_are_errors() {
+        assert_eq!(apply_binary(BinaryOp::Multiply, 1e308, 10.0), Err("Overflow".into()));
+        assert_eq!(apply_binary(BinaryOp::Power, -8.0, 0.5), Err("Undefined".into()));
+        assert_eq!(apply_unary(UnaryFunc::Exp, 1000.0, AngleMode::Degrees), Err("Overflow".into()));
+        assert_eq!(apply_unary(UnaryFunc::Asin, 2.0, AngleMode::Degrees), Err("Undefined".into()));
+        assert_eq!(apply_postfix(PostfixOp::Square, 1e200), Err("Overflow".into()));
+        assert_eq!(evaluate_str("10^400 - 10^400", &HashMap::new(), AngleMode::Degrees, true), Err("Overflow".into()));
+    }
+
     #[test]
     fn factorial_edge_cases() {
         assert_eq!(apply_postfix(PostfixOp::Factorial, 0.0).unwrap(), 1.0);
@@ -553,4 +758,46 @@ mod tests {
         assert_eq!(evaluate(&[], AngleMode::Degrees, true).unwrap(), 0.0);
         assert!(parse_expression("", &HashMap::new()).unwrap().is_empty());
     }
+
+    #[test]
+    fn answers_stand_in_for_ans_n() {
+        let parse = |input: &str| parse_expression_with(input, &HashMap::new(), &[5.0, 2.0]);
+        assert_eq!(parse("ans2 + ans").unwrap(), parse("2 + 5").unwrap());
+        assert_eq!(parse("3ans1").unwrap(), parse("3 * 5").unwrap());
+        assert_eq!(parse("ans3"), Err("No result for ans3".into()));
+        assert_eq!(parse("ans0"), Err("No result for ans0".into()));
+        assert!(parse_expression("ans", &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn evaluate_str_parses_and_evaluates() {
+        let result = evaluate_str("2 + 3 * 4", &HashMap::new(), AngleMode::Degrees, true).unwrap();
+        assert_eq!(result, 14.0);
+        assert!(evaluate_str("1/0", &HashMap::new(), AngleMode::Degrees, true).is_err());
+        assert!(evaluate_str("   ", &HashMap::new(), AngleMode::Degrees, true).is_err());
+    }
+
+    #[test]
+    fn evaluator_reuses_buffers_between_expressions() {
+        let plugins = HashMap::new();
+        let mut evaluator = Evaluator::new(&plugins, EvalSettings::default());
+        let mut value = |input: &str| evaluator.evaluate(input).map(|e| e.value);
+        assert_eq!(value("2 + 3 * 4"), Ok(14.0));
+        assert!(value("1/0").is_err());
+        assert_eq!(value("(1 + 2"), Ok(3.0));
+        assert_eq!(value("  "), Err("Empty expression".into()));
+        assert_eq!(value("sin(90)"), Ok(1.0));
+    }
+
+    #[test]
+    fn evaluate_many_matches_evaluate_str() {
+        let plugins = HashMap::new();
+        let inputs = ["2 + 3 \u{00d7} 4", "50% of 200", "1/0", "", "10 \u{2212} 4 \u{00f7} 2", "3 +/- 1"];
+        let many = evaluate_many(&inputs, &plugins, EvalSettings::default());
+        let values: Vec<_> = many.iter().map(|r| r.as_ref().map(|e| e.value).map_err(String::clone)).collect();
+        let one_by_one: Vec<_> = inputs.iter().map(|i| evaluate_str(i, &plugins, AngleMode::Degrees, true)).collect();
+        assert_eq!(values, one_by_one);
+        let texts: Vec<_> = many.iter().flatten().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts, ["14", "100", "8", "3.0 \u{00b1} 1.0"]);
+    }
 }
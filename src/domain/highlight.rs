@@ -0,0 +1,282 @@
+use std::ops::Range;
+
+use super::interval;
+use super::symbols::FUNCTIONS;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Number,
+    Operator,
+    Function,
+    Constant,
+    Paren,
+    /// A variable or plugin function; whether it exists depends on context.
+    Name,
+    /// `#3`, the value of line 3 in a worksheet.
+    Reference,
+    Comment,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub kind: Kind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub range: Range<usize>,
+    pub message: String,
+}
+
+impl SyntaxError {
+    fn at(span: &Span, message: String) -> Self {
+        Self { range: span.range.clone(), message }
+    }
+}
+
+pub fn is_function(word: &str) -> bool {
+    let word = word.to_lowercase();
+    FUNCTIONS.iter().any(|(name, _)| *name == word)
+}
+
+/// Whole-line comments: `// note`, or `#` not followed by a line number.
+pub fn is_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("//") || (line.starts_with('#') && !line[1..].starts_with(|c: char| c.is_ascii_digit()))
+}
+
+fn is_operator(c: char) -> bool {
+    matches!(c, '+' | '-' | '*' | '/' | '^' | '!' | '%' | '=' | '\u{00b1}' | '\u{00d7}' | '\u{00f7}' | '\u{2212}')
+}
+
+/// Splits an expression into coloured spans. Byte offsets index `input`.
+/// Whitespace is not covered by any span.
+pub fn lex(input: &str) -> Vec<Span> {
+    if is_comment(input) {
+        let start = input.len() - input.trim_start().len();
+        return vec![Span { range: start..input.len(), kind: Kind::Comment }];
+    }
+    let mut spans = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        let mut take_while = |end: &mut usize, pred: &dyn Fn(char) -> bool| {
+            while let Some(&(i, next)) = chars.peek() {
+                if !pred(next) {
+                    break;
+                }
+                *end = i + next.len_utf8();
+                chars.next();
+            }
+        };
+        let kind = match c {
+            _ if c.is_whitespace() => continue,
+            '0'..='9' | '.' => {
+                take_while(&mut end, &|n| n.is_ascii_digit() || n == '.');
+                Kind::Number
+            }
+            '#' => {
+                take_while(&mut end, &|n| n.is_ascii_digit());
+                Kind::Reference
+            }
+            // An interval literal, `[lo, hi]`, reads as one number.
+            '[' => {
+                take_while(&mut end, &|n| n != ']');
+                if let Some(&(i, ']')) = chars.peek() {
+                    end = i + 1;
+                    chars.next();
+                }
+                Kind::Number
+            }
+            '(' | ')' => Kind::Paren,
+            '\u{03c0}' => Kind::Constant,
+            _ if is_operator(c) => Kind::Operator,
+            _ if c.is_alphabetic() || c == '_' => {
+                take_while(&mut end, &|n| n.is_alphanumeric() || n == '_');
+                let word = input[start..end].to_lowercase();
+                match word.as_str() {
+                    "pi" | "e" => Kind::Constant,
+                    "mod" | "of" => Kind::Operator,
+                    w if is_function(w) => Kind::Function,
+                    _ => Kind::Name,
+                }
+            }
+            _ => Kind::Unknown,
+        };
+        spans.push(Span { range: start..end, kind });
+    }
+    spans
+}
+
+/// Finds the first structural mistake in an expression: stray characters,
+/// malformed numbers, unbalanced parentheses and operators missing an
+/// operand. Unknown names are left to the caller, which knows what is
+/// defined.
+pub fn check(input: &str) -> Option<SyntaxError> {
+    let spans = lex(input);
+    let text = |s: &Span| &input[s.range.clone()];
+    let mut depth = 0usize;
+    let mut expect_operand = true;
+    let mut prev: Option<&Span> = None;
+    for span in &spans {
+        let t = text(span);
+        match span.kind {
+            Kind::Comment => return None,
+            Kind::Unknown => return Some(SyntaxError::at(span, format!("Unexpected \u{2018}{}\u{2019}", t))),
+            Kind::Number if t.starts_with('[') => match interval::parse_literal(t) {
+                Ok(_) => expect_operand = false,
+                Err(message) => return Some(SyntaxError::at(span, message)),
+            },
+            Kind::Number if t.matches('.').count() > 1 || t == "." => {
+                return Some(SyntaxError::at(span, format!("Malformed number \u{2018}{}\u{2019}", t)));
+            }
+            Kind::Reference if t == "#" => {
+                return Some(SyntaxError::at(span, "Expected a line number after #".into()));
+            }
+            Kind::Number | Kind::Constant | Kind::Name | Kind::Reference => expect_operand = false,
+            Kind::Function => expect_operand = true,
+            Kind::Paren if t == "(" => {
+                depth += 1;
+                expect_operand = true;
+            }
+            Kind::Paren => {
+                if depth == 0 {
+                    return Some(SyntaxError::at(span, "Unmatched \u{2018})\u{2019}".into()));
+                }
+                if expect_operand {
+                    let p = prev.expect("an open paren precedes this one");
+                    return Some(match text(p) {
+                        "(" => SyntaxError { range: p.range.start..span.range.end, message: "Empty parentheses".into() },
+                        pt => SyntaxError::at(p, format!("Missing operand after \u{2018}{}\u{2019}", pt)),
+                    });
+                }
+                depth -= 1;
+            }
+            Kind::Operator => match t {
+                "!" | "%" if expect_operand => {
+                    return Some(SyntaxError::at(span, format!("Nothing before \u{2018}{}\u{2019}", t)));
+                }
+                "!" | "%" => {}
+                // Unary minus.
+                "-" | "\u{2212}" if expect_operand => {}
+                _ if expect_operand => {
+                    return Some(SyntaxError::at(span, format!("Missing operand before \u{2018}{}\u{2019}", t)));
+                }
+                _ => expect_operand = true,
+            },
+        }
+        prev = Some(span);
+    }
+    match prev {
+        // A trailing "=" just asks for the result.
+        Some(last) if expect_operand && text(last) != "=" => {
+            let t = text(last);
+            let message = match last.kind {
+                Kind::Function => format!("\u{2018}{}\u{2019} needs an argument", t),
+                _ => format!("Missing operand after \u{2018}{}\u{2019}", t),
+            };
+            Some(SyntaxError::at(last, message))
+        }
+        _ => None,
+    }
+}
+
+/// Whether pasted or copied text reads as a calculation: a single valid
+/// expression with numbers and at least one operator or function, and no
+/// names, so prose and plain numbers are left alone.
+pub fn looks_like_expression(text: &str) -> bool {
+    let text = text.trim();
+    if text.is_empty() || text.len() > 200 || text.contains('\n') || check(text).is_some() {
+        return false;
+    }
+    let spans = lex(text);
+    let mut operands = false;
+    let mut operations = false;
+    let mut open = 0;
+    for (i, span) in spans.iter().enumerate() {
+        match span.kind {
+            Kind::Number | Kind::Constant => operands = true,
+            Kind::Function => operations = true,
+            // A leading minus and a trailing "=" don't make "-5" or "5 =" sums.
+            Kind::Operator => {
+                let op = &text[span.range.clone()];
+                let leading_minus = i == 0 && matches!(op, "-" | "\u{2212}");
+                let trailing_equals = i == spans.len() - 1 && op == "=";
+                operations |= !leading_minus && !trailing_equals;
+            }
+            Kind::Paren if &text[span.range.clone()] == "(" => open += 1,
+            Kind::Paren => open -= 1,
+            Kind::Name | Kind::Reference | Kind::Comment | Kind::Unknown => return false,
+        }
+    }
+    // Dates, phone numbers and part numbers: digits joined by bare hyphens.
+    let hyphenated = text.chars().all(|c| c.is_ascii_digit() || c == '-') && text.contains('-');
+    // The keypad closes parentheses for you; copied text has to be whole.
+    operands && operations && open == 0 && !hyphenated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<(&str, Kind)> {
+        lex(input).into_iter().map(|s| (&input[s.range], s.kind)).collect()
+    }
+
+    fn error(input: &str) -> Option<(&str, String)> {
+        check(input).map(|e| (&input[e.range], e.message))
+    }
+
+    #[test]
+    fn lexes_each_token_class() {
+        assert_eq!(
+            kinds("2.5\u{00d7}sin(pi) + rate - #3"),
+            [
+                ("2.5", Kind::Number),
+                ("\u{00d7}", Kind::Operator),
+                ("sin", Kind::Function),
+                ("(", Kind::Paren),
+                ("pi", Kind::Constant),
+                (")", Kind::Paren),
+                ("+", Kind::Operator),
+                ("rate", Kind::Name),
+                ("-", Kind::Operator),
+                ("#3", Kind::Reference),
+            ]
+        );
+        assert_eq!(kinds("  // total"), [("// total", Kind::Comment)]);
+        assert_eq!(kinds("7 mod 2 @"), [("7", Kind::Number), ("mod", Kind::Operator), ("2", Kind::Number), ("@", Kind::Unknown)]);
+    }
+
+    #[test]
+    fn valid_expressions_pass() {
+        for ok in ["2+3*4", "-5 + (-2)", "2(3)", "5!", "50%", "sqrt 16", "x = 4", "2+2 =", "", "# heading", "2 \u{00d7} [1.9, 2.1]"] {
+            assert_eq!(check(ok), None, "{}", ok);
+        }
+    }
+
+    #[test]
+    fn recognises_copied_calculations() {
+        for yes in ["12*(3+4)", " 2 + 2 = ", "sqrt(16)", "50% of 200", "-3 - 4", "2\u{00d7}\u{03c0}", "5!"] {
+            assert!(looks_like_expression(yes), "{}", yes);
+        }
+        for no in ["", "42", "-5", "3.14 =", "rate * 2", "call me at 555-1234 ok", "(1 + 2", "1 +\n2", "2023-10-05", "555-1234"] {
+            assert!(!looks_like_expression(no), "{}", no);
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_culprit() {
+        assert_eq!(error("2 + * 3"), Some(("*", "Missing operand before \u{2018}*\u{2019}".into())));
+        assert_eq!(error("(1 + 2))"), Some((")", "Unmatched \u{2018})\u{2019}".into())));
+        assert_eq!(error("4 * ()"), Some(("()", "Empty parentheses".into())));
+        assert_eq!(error("(2 - )"), Some(("-", "Missing operand after \u{2018}-\u{2019}".into())));
+        assert_eq!(error("1.2.3 + 1"), Some(("1.2.3", "Malformed number \u{2018}1.2.3\u{2019}".into())));
+        assert_eq!(error("3 +"), Some(("+", "Missing operand after \u{2018}+\u{2019}".into())));
+        assert_eq!(error("2 * sqrt"), Some(("sqrt", "\u{2018}sqrt\u{2019} needs an argument".into())));
+        assert_eq!(error("12 $ 4"), Some(("$", "Unexpected \u{2018}$\u{2019}".into())));
+        assert_eq!(error("!3"), Some(("!", "Nothing before \u{2018}!\u{2019}".into())));
+    }
+}
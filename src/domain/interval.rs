@@ -0,0 +1,362 @@
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use super::ast::Expr;
+use super::eval;
+use super::types::*;
+
+// libm's transcendental functions are accurate to within a couple of ulps
+// but not correctly rounded, so their results are widened further than the
+// single ulp that covers +, −, × and ÷.
+const LIBM_ULPS: u32 = 4;
+
+/// A closed range `[lo, hi]` known to contain the exact value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+impl Interval {
+    pub fn point(value: f64) -> Self {
+        Self { lo: value, hi: value }
+    }
+
+    /// Encloses a number that may have been rounded on the way in, such as
+    /// the decimal `0.1`. Integers are kept exact.
+    pub fn literal(value: f64) -> Self {
+        if value.fract() == 0.0 && value.abs() <= 2f64.powi(53) {
+            Self::point(value)
+        } else {
+            outward(value, value, 1)
+        }
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        self.lo <= x && x <= self.hi
+    }
+
+    pub fn midpoint(&self) -> f64 {
+        self.lo / 2.0 + self.hi / 2.0
+    }
+
+    fn magnitude(&self) -> f64 {
+        self.lo.abs().max(self.hi.abs())
+    }
+
+    fn as_point(&self) -> Option<f64> {
+        (self.lo == self.hi).then_some(self.lo)
+    }
+}
+
+// Steps each bound `ulps` representable numbers outward, for results of
+// functions whose rounding error is only known to be small.
+fn outward(lo: f64, hi: f64, ulps: u32) -> Interval {
+    let (mut lo, mut hi) = (lo, hi);
+    for _ in 0..ulps {
+        lo = lo.next_down();
+        hi = hi.next_up();
+    }
+    Interval { lo, hi }
+}
+
+fn bounds(lo: f64, hi: f64) -> Result<Interval, String> {
+    if lo.is_nan() || hi.is_nan() {
+        return Err("Undefined".into());
+    }
+    Ok(Interval { lo: eval::finite(lo)?, hi: eval::finite(hi)? })
+}
+
+// The smallest interval around `values`, widened by `ulps`.
+fn widened(values: &[f64], ulps: u32) -> Result<Interval, String> {
+    let lo = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let hi = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if values.iter().any(|v| v.is_nan()) {
+        return Err("Undefined".into());
+    }
+    let r = outward(lo, hi, ulps);
+    bounds(r.lo, r.hi)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Round {
+    Down,
+    Up,
+}
+
+// Directed rounding for the basic operations. The float result is the
+// exact one rounded to nearest; `err` is what that rounding lost, recovered
+// exactly, and decides whether to step one representable number over.
+fn nudge(x: f64, err: f64, dir: Round) -> f64 {
+    match dir {
+        Round::Down if err < 0.0 => x.next_down(),
+        Round::Up if err > 0.0 => x.next_up(),
+        _ => x,
+    }
+}
+
+fn add(a: f64, b: f64, dir: Round) -> f64 {
+    let s = a + b;
+    let b_part = s - a;
+    nudge(s, (a - (s - b_part)) + (b - b_part), dir)
+}
+
+fn mul(a: f64, b: f64, dir: Round) -> f64 {
+    let p = a * b;
+    nudge(p, a.mul_add(b, -p), dir)
+}
+
+fn div(a: f64, b: f64, dir: Round) -> f64 {
+    let q = a / b;
+    nudge(q, -q.mul_add(b, -a) / b, dir)
+}
+
+// Smallest and largest of `op` over every pair of bounds.
+fn corners(a: Interval, b: Interval, op: fn(f64, f64, Round) -> f64) -> Result<Interval, String> {
+    let pairs = [(a.lo, b.lo), (a.lo, b.hi), (a.hi, b.lo), (a.hi, b.hi)];
+    let lo = pairs.iter().map(|&(x, y)| op(x, y, Round::Down)).fold(f64::INFINITY, f64::min);
+    let hi = pairs.iter().map(|&(x, y)| op(x, y, Round::Up)).fold(f64::NEG_INFINITY, f64::max);
+    bounds(lo, hi)
+}
+
+// x^n for x ≥ 0 by repeated directed multiplication.
+fn powi(x: f64, n: u32, dir: Round) -> f64 {
+    (0..n).fold(1.0, |acc, _| mul(acc, x, dir))
+}
+
+/// Reads `[lo, hi]` as typed, e.g. `[1.9, 2.1]` or `[-1,1]`.
+pub fn parse_literal(text: &str) -> Result<(f64, f64), String> {
+    let malformed = || format!("Malformed interval \u{2018}{}\u{2019}", text);
+    let inner = text
+        .trim()
+        .strip_prefix('[')
+        .and_then(|t| t.strip_suffix(']'))
+        .ok_or_else(malformed)?;
+    let (lo, hi) = inner.split_once(',').ok_or_else(malformed)?;
+    let lo: f64 = lo.trim().replace('\u{2212}', "-").parse().map_err(|_| malformed())?;
+    let hi: f64 = hi.trim().replace('\u{2212}', "-").parse().map_err(|_| malformed())?;
+    if lo > hi {
+        return Err(format!("Empty interval \u{2018}{}\u{2019}", text));
+    }
+    Ok((lo, hi))
+}
+
+/// Whether the expression has a typed `[lo, hi]` in it.
+pub fn has_literal(tokens: &[Token]) -> bool {
+    tokens.iter().any(|t| matches!(t, Token::Interval(..)))
+}
+
+/// Evaluates `expr` over intervals. Every operation returns bounds that
+/// contain all results for values within its operands' bounds.
+pub fn evaluate(expr: &Expr, angle_mode: AngleMode) -> Result<Interval, String> {
+    match expr {
+        Expr::Number(n) => Ok(Interval::literal(*n)),
+        Expr::Interval(lo, hi) => Ok(Interval { lo: Interval::literal(*lo).lo, hi: Interval::literal(*hi).hi }),
+        Expr::Constant(_, value) => Ok(outward(*value, *value, 1)),
+        Expr::Group(inner) => evaluate(inner, angle_mode),
+        Expr::Binary(op, a, b) => binary(*op, evaluate(a, angle_mode)?, evaluate(b, angle_mode)?),
+        Expr::Func(f, arg) => unary(*f, evaluate(arg, angle_mode)?, angle_mode),
+        Expr::Postfix(p, inner) => postfix(*p, evaluate(inner, angle_mode)?),
+    }
+}
+
+pub fn binary(op: BinaryOp, a: Interval, b: Interval) -> Result<Interval, String> {
+    match op {
+        BinaryOp::Add => bounds(add(a.lo, b.lo, Round::Down), add(a.hi, b.hi, Round::Up)),
+        BinaryOp::Subtract => bounds(add(a.lo, -b.hi, Round::Down), add(a.hi, -b.lo, Round::Up)),
+        BinaryOp::Multiply => corners(a, b, mul),
+        BinaryOp::Divide => {
+            if b.contains(0.0) {
+                return Err("Division by an interval containing zero".into());
+            }
+            corners(a, b, div)
+        }
+        BinaryOp::PlusMinus => {
+            let m = b.magnitude();
+            bounds(add(a.lo, -m, Round::Down), add(a.hi, m, Round::Up))
+        }
+        BinaryOp::Power => power(a, b),
+        BinaryOp::Modulo => match (a.as_point(), b.as_point()) {
+            (Some(x), Some(y)) => eval::apply_binary(op, x, y).map(Interval::literal),
+            _ => Err("Modulo needs exact operands".into()),
+        },
+    }
+}
+
+fn power(a: Interval, b: Interval) -> Result<Interval, String> {
+    if let Some(n) = b.as_point().filter(|n| n.fract() == 0.0 && n.abs() <= 1024.0) {
+        if n < 0.0 {
+            return binary(BinaryOp::Divide, Interval::point(1.0), power(a, Interval::point(-n))?);
+        }
+        let n = n as u32;
+        let up = |x: f64| powi(x, n, Round::Up);
+        let down = |x: f64| powi(x, n, Round::Down);
+        let even = n.is_multiple_of(2);
+        return if a.lo >= 0.0 {
+            bounds(down(a.lo), up(a.hi))
+        } else if a.hi <= 0.0 && even {
+            bounds(down(-a.hi), up(-a.lo))
+        } else if a.hi <= 0.0 {
+            bounds(-up(-a.lo), -down(-a.hi))
+        } else if even {
+            // Even powers dip to zero inside an interval that straddles it.
+            bounds(0.0, up(-a.lo).max(up(a.hi)))
+        } else {
+            bounds(-up(-a.lo), up(a.hi))
+        };
+    }
+    if a.lo <= 0.0 {
+        return Err("Domain error".into());
+    }
+    widened(&[a.lo.powf(b.lo), a.lo.powf(b.hi), a.hi.powf(b.lo), a.hi.powf(b.hi)], LIBM_ULPS)
+}
+
+fn unary(func: UnaryFunc, a: Interval, angle_mode: AngleMode) -> Result<Interval, String> {
+    let f = |x: f64| eval::apply_unary(func, x, angle_mode);
+    match func {
+        UnaryFunc::Sin => periodic(to_radians(a, angle_mode)?, f64::sin, FRAC_PI_2),
+        UnaryFunc::Cos => periodic(to_radians(a, angle_mode)?, f64::cos, 0.0),
+        UnaryFunc::Tan => {
+            let r = to_radians(a, angle_mode)?;
+            if crosses(r, FRAC_PI_2, PI) {
+                return Err("Undefined".into());
+            }
+            widened(&[r.lo.tan(), r.hi.tan()], LIBM_ULPS)
+        }
+        // Both bottom out at 0, exactly: abs(0) = 0 and cosh(0) = 1.
+        UnaryFunc::Abs | UnaryFunc::Cosh if a.contains(0.0) => {
+            let r = widened(&[f(a.lo)?, f(a.hi)?], LIBM_ULPS)?;
+            Ok(Interval { lo: f(0.0)?, hi: r.hi })
+        }
+        // The rest are monotonic between the bounds, so they map to bounds.
+        _ => widened(&[f(a.lo)?, f(a.hi)?], LIBM_ULPS),
+    }
+}
+
+fn to_radians(a: Interval, angle_mode: AngleMode) -> Result<Interval, String> {
+    match angle_mode {
+        AngleMode::Radians => Ok(a),
+        // π/180 is itself rounded, hence the extra ulp.
+        AngleMode::Degrees => widened(&[a.lo * (PI / 180.0), a.hi * (PI / 180.0)], 2),
+    }
+}
+
+// Whether `[lo, hi]` contains some `offset + k·period`.
+fn crosses(a: Interval, offset: f64, period: f64) -> bool {
+    let k = ((a.lo - offset) / period).ceil();
+    offset + k * period <= a.hi
+}
+
+// sin or cos, given where the function peaks; troughs are half a turn on.
+fn periodic(a: Interval, f: fn(f64) -> f64, peak: f64) -> Result<Interval, String> {
+    if a.hi - a.lo >= TAU {
+        return Ok(Interval { lo: -1.0, hi: 1.0 });
+    }
+    let mut r = widened(&[f(a.lo), f(a.hi)], LIBM_ULPS)?;
+    if crosses(a, peak, TAU) {
+        r.hi = 1.0;
+    }
+    if crosses(a, peak + PI, TAU) {
+        r.lo = -1.0;
+    }
+    Ok(Interval { lo: r.lo.max(-1.0), hi: r.hi.min(1.0) })
+}
+
+fn postfix(op: PostfixOp, a: Interval) -> Result<Interval, String> {
+    match op {
+        PostfixOp::Square => power(a, Interval::point(2.0)),
+        PostfixOp::Cube => power(a, Interval::point(3.0)),
+        PostfixOp::Reciprocal => binary(BinaryOp::Divide, Interval::point(1.0), a),
+        PostfixOp::Percent => bounds(div(a.lo, 100.0, Round::Down), div(a.hi, 100.0, Round::Up)),
+        PostfixOp::Factorial => match a.as_point() {
+            Some(n) => eval::apply_postfix(op, n).map(Interval::literal),
+            None => Err("Factorial needs an exact operand".into()),
+        },
+    }
+}
+
+/// `[lo, hi]`, each bound rounded away from the other so the text still
+/// encloses the value; a single number when the bounds are equal.
+pub fn format(iv: Interval) -> String {
+    if iv.lo == iv.hi {
+        return format_number_default(iv.lo);
+    }
+    format!("[{}, {}]", format_bound(iv.lo, Round::Down), format_bound(iv.hi, Round::Up))
+}
+
+fn format_bound(val: f64, dir: Round) -> String {
+    // Scientific notation prints the shortest text that reads back as `val`.
+    if val != 0.0 && (val.abs() >= 1e15 || val.abs() < 1e-4) {
+        return format!("{:e}", val);
+    }
+    let scaled = match dir {
+        Round::Down => (val * 1e10).floor() / 1e10,
+        Round::Up => (val * 1e10).ceil() / 1e10,
+    };
+    let s = format!("{:.10}", scaled);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".into() } else { s.into() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ast;
+    use std::collections::HashMap;
+
+    fn eval(input: &str) -> Result<Interval, String> {
+        let tokens = eval::parse_expression(input, &HashMap::new())?;
+        evaluate(&ast::build(&tokens, true)?, AngleMode::Degrees)
+    }
+
+    fn encloses(iv: Interval, lo: f64, hi: f64) -> bool {
+        iv.lo <= lo && hi <= iv.hi && iv.hi - iv.lo <= (hi - lo) + 1e-12
+    }
+
+    #[test]
+    fn parses_interval_literals() {
+        assert_eq!(parse_literal("[1.9, 2.1]"), Ok((1.9, 2.1)));
+        assert_eq!(parse_literal("[-1,1]"), Ok((-1.0, 1.0)));
+        assert!(parse_literal("[2, 1]").is_err());
+        assert!(parse_literal("[2 1]").is_err());
+    }
+
+    #[test]
+    fn bounds_enclose_the_exact_result() {
+        assert!(encloses(eval("[1.9, 2.1] + [1, 2]").unwrap(), 2.9, 4.1));
+        assert!(encloses(eval("[1, 2] - [1, 2]").unwrap(), -1.0, 1.0));
+        assert!(encloses(eval("[-2, 3] \u{00d7} [4, 5]").unwrap(), -10.0, 15.0));
+        assert!(encloses(eval("1 / [2, 4]").unwrap(), 0.25, 0.5));
+        assert!(encloses(eval("[-2, 3]^2").unwrap(), 0.0, 9.0));
+        assert!(encloses(eval("2 \u{00b1} 0.5").unwrap(), 1.5, 2.5));
+        let tenth = eval("0.1 + 0.2").unwrap();
+        assert!(tenth.lo < 0.3 && 0.3 < tenth.hi);
+        assert_eq!(eval("2 + 3").unwrap(), Interval::point(5.0));
+    }
+
+    #[test]
+    fn functions_find_their_extremes() {
+        let sin = eval("sin([80, 100])").unwrap();
+        assert_eq!(sin.hi, 1.0);
+        assert!(sin.lo <= 80f64.to_radians().sin());
+        let cos = eval("cos([-10, 10])").unwrap();
+        assert_eq!(cos.hi, 1.0);
+        assert_eq!(eval("sin([0, 720])").unwrap(), Interval { lo: -1.0, hi: 1.0 });
+        assert!(eval("tan([80, 100])").is_err());
+        assert!(encloses(eval("sqrt([4, 9])").unwrap(), 2.0, 3.0));
+        assert_eq!(eval("abs([-3, 2])").unwrap().lo, 0.0);
+    }
+
+    #[test]
+    fn refuses_what_it_cannot_bound() {
+        assert_eq!(eval("1 / [-1, 1]"), Err("Division by an interval containing zero".into()));
+        assert!(eval("[3, 4]!").is_err());
+        assert!(eval("sqrt([-1, 4])").is_err());
+    }
+
+    #[test]
+    fn formats_outward() {
+        assert_eq!(format(Interval { lo: 1.23456789012345, hi: 1.5 }), "[1.2345678901, 1.5]");
+        assert_eq!(format(Interval { lo: 0.2999999999999999, hi: 0.30000000000000004 }), "[0.2999999999, 0.3000000001]");
+        assert_eq!(format(Interval::point(5.0)), "5");
+    }
+}
@@ -1,4 +1,27 @@
+// Parts of these are only reachable from the GTK frontend; the allows keep
+// headless builds quiet about them without hiding dead code elsewhere.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub mod ast;
+pub mod bigint;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 pub mod convert;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub mod duration;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 pub mod engine;
 pub mod eval;
+pub mod highlight;
+pub mod interval;
+pub mod money;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub mod proportion;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub mod search;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub mod snippet;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub mod symbols;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 pub mod types;
+pub mod uncertain;
+pub mod worksheet;
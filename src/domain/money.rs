@@ -0,0 +1,96 @@
+// Amounts are rounded from a fixed-point copy in units of 10⁻⁷, so float
+// noise below that (1.005 is stored as 1.00499…) does not decide a tie.
+const UNITS_PER_CENT: i128 = 100_000;
+const UNITS: f64 = 1e7;
+
+// Past this, an f64 no longer holds every cent anyway.
+const MAX: f64 = 1e15;
+
+// The amount in whole cents, rounded half to even, and the units left over.
+fn split(val: f64) -> (i128, i128) {
+    let units = (val * UNITS).round() as i128;
+    let (cents, rest) = (units / UNITS_PER_CENT, units % UNITS_PER_CENT);
+    let half = UNITS_PER_CENT / 2;
+    let up = rest.abs() > half || (rest.abs() == half && cents % 2 != 0);
+    if up {
+        let step = units.signum();
+        (cents + step, rest - step * UNITS_PER_CENT)
+    } else {
+        (cents, rest)
+    }
+}
+
+/// Rounds to the cent, ties to even as bookkeeping does: 0.125 → 0.12,
+/// 0.135 → 0.14.
+pub fn round(val: f64) -> f64 {
+    if !val.is_finite() || val.abs() >= MAX {
+        return val;
+    }
+    split(val).0 as f64 / 100.0
+}
+
+/// What rounding to the cent drops from `val`; negative when it rounds up,
+/// zero for whole cents.
+pub fn lost(val: f64) -> f64 {
+    if !val.is_finite() || val.abs() >= MAX {
+        return 0.0;
+    }
+    split(val).1 as f64 / UNITS
+}
+
+/// Always two decimals, `33.33`.
+pub fn format(val: f64) -> String {
+    if !val.is_finite() {
+        return "Error".into();
+    }
+    // Adding 0.0 turns a rounded -0 into 0.
+    format!("{:.2}", round(val) + 0.0)
+}
+
+/// "0.3333¢ lost to rounding" for a value that is not whole cents.
+pub fn describe_loss(val: f64) -> Option<String> {
+    let cents = lost(val) * 100.0;
+    if cents == 0.0 {
+        return None;
+    }
+    let amount = format!("{:.4}", cents.abs());
+    let amount = amount.trim_end_matches('0').trim_end_matches('.');
+    Some(if cents > 0.0 {
+        format!("{}\u{00a2} lost to rounding", amount)
+    } else {
+        format!("{}\u{00a2} added by rounding", amount)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_half_to_even() {
+        assert_eq!(round(0.125), 0.12);
+        assert_eq!(round(0.135), 0.14);
+        assert_eq!(round(1.005), 1.0);
+        assert_eq!(round(2.675), 2.68);
+        assert_eq!(round(-0.125), -0.12);
+        assert_eq!(round(-0.135), -0.14);
+        assert_eq!(round(0.126), 0.13);
+        assert_eq!(round(0.1 + 0.2), 0.3);
+    }
+
+    #[test]
+    fn formats_two_decimals() {
+        assert_eq!(format(100.0 / 3.0), "33.33");
+        assert_eq!(format(5.0), "5.00");
+        assert_eq!(format(-0.001), "0.00");
+        assert_eq!(format(-12.345), "-12.34");
+    }
+
+    #[test]
+    fn reports_lost_cents() {
+        assert_eq!(describe_loss(100.0 / 3.0).as_deref(), Some("0.3333\u{00a2} lost to rounding"));
+        assert_eq!(describe_loss(200.0 / 3.0).as_deref(), Some("0.3333\u{00a2} added by rounding"));
+        assert_eq!(describe_loss(0.1 + 0.2), None);
+        assert_eq!(describe_loss(19.99), None);
+    }
+}
@@ -0,0 +1,48 @@
+/// Solves `a : b = c : d` for the one term given as None, by cross
+/// multiplication: `2 : 5 = 6 : x` gives `(3, 15)`, the index of the blank
+/// term and its value.
+pub fn solve(terms: [Option<f64>; 4]) -> Result<(usize, f64), String> {
+    let blanks: Vec<usize> = (0..4).filter(|&i| terms[i].is_none()).collect();
+    let &[blank] = blanks.as_slice() else {
+        return Err(if blanks.is_empty() { "Leave one field blank" } else { "Fill in three fields" }.into());
+    };
+    let t = |i: usize| terms[i].unwrap_or(0.0);
+    // a·d = b·c, so a blank term is the product of its two neighbours over
+    // the term opposite it.
+    let (p, q, opposite) = match blank {
+        0 => (t(1), t(2), t(3)),
+        1 => (t(0), t(3), t(2)),
+        2 => (t(0), t(3), t(1)),
+        _ => (t(1), t(2), t(0)),
+    };
+    if opposite == 0.0 {
+        return Err("Division by zero".into());
+    }
+    let val = p * q / opposite;
+    if !val.is_finite() {
+        return Err("Result too large".into());
+    }
+    Ok((blank, val))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_for_the_blank_term() {
+        assert_eq!(solve([Some(2.0), Some(5.0), Some(6.0), None]), Ok((3, 15.0)));
+        assert_eq!(solve([None, Some(5.0), Some(6.0), Some(15.0)]), Ok((0, 2.0)));
+        assert_eq!(solve([Some(2.0), None, Some(6.0), Some(15.0)]), Ok((1, 5.0)));
+        assert_eq!(solve([Some(2.0), Some(5.0), None, Some(15.0)]), Ok((2, 6.0)));
+        // 250 mg in 5 ml; how many ml hold 400 mg?
+        assert_eq!(solve([Some(250.0), Some(5.0), Some(400.0), None]), Ok((3, 8.0)));
+    }
+
+    #[test]
+    fn needs_exactly_one_blank() {
+        assert_eq!(solve([Some(1.0), Some(2.0), Some(3.0), Some(4.0)]), Err("Leave one field blank".into()));
+        assert_eq!(solve([Some(1.0), None, None, Some(4.0)]), Err("Fill in three fields".into()));
+        assert_eq!(solve([Some(0.0), Some(5.0), Some(6.0), None]), Err("Division by zero".into()));
+    }
+}
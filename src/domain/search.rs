@@ -0,0 +1,183 @@
+use super::types::HistoryEntry;
+
+const WEEKDAYS: [&str; 7] = ["sunday", "monday", "tuesday", "wednesday", "thursday", "friday", "saturday"];
+
+/// Day of the week a timestamp falls on (UTC), 0 = Sunday.
+pub fn weekday(ts: u64) -> usize {
+    // 1970-01-01 was a Thursday.
+    ((ts / 86_400 + 4) % 7) as usize
+}
+
+pub fn weekday_name(ts: u64) -> &'static str {
+    match weekday(ts) {
+        0 => "Sun",
+        1 => "Mon",
+        2 => "Tue",
+        3 => "Wed",
+        4 => "Thu",
+        5 => "Fri",
+        _ => "Sat",
+    }
+}
+
+// "tue", "tues" and "tuesday" all name Tuesday; anything shorter than three
+// letters is too ambiguous to treat as a day.
+fn parse_weekday(term: &str) -> Option<usize> {
+    if term.len() < 3 {
+        return None;
+    }
+    WEEKDAYS.iter().position(|d| d.starts_with(term))
+}
+
+// Lets "*" find "×" and so on, since the display uses typographic symbols.
+fn normalize(s: &str) -> String {
+    s.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '\u{00d7}' => '*',
+            '\u{00f7}' => '/',
+            '\u{2212}' => '-',
+            other => other,
+        })
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+/// Scores `needle` as an in-order subsequence of `haystack`; consecutive
+/// runs score higher than scattered hits.
+pub fn fuzzy_score(needle: &str, haystack: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut run = 0;
+    let mut hay = haystack.chars();
+    for n in needle.chars() {
+        let mut gap = 0;
+        loop {
+            match hay.next() {
+                Some(h) if h == n => break,
+                Some(_) => gap += 1,
+                None => return None,
+            }
+        }
+        run = if gap == 0 { run + 1 } else { 1 };
+        score += run - gap.min(3);
+    }
+    Some(score)
+}
+
+fn term_score(term: &str, entry: &HistoryEntry) -> Option<i32> {
+    if let Some(day) = parse_weekday(term) {
+        if entry.timestamp > 0 && weekday(entry.timestamp) == day {
+            return Some(100);
+        }
+    }
+    let term = normalize(term);
+    let expr = normalize(&entry.expression);
+    let result = normalize(&entry.result_text);
+    let label = normalize(entry.label.as_deref().unwrap_or(""));
+    if expr.contains(&term) || result.contains(&term) || label.contains(&term) {
+        // Exact substring hits always outrank fuzzy ones.
+        return Some(1000 + term.len() as i32);
+    }
+    let haystack = format!("{}={}|{}", expr, result, label);
+    fuzzy_score(&term, &haystack)
+}
+
+/// Filters history by every whitespace-separated term in `query`. Starred
+/// entries come first, then the best matches, newest first among equals.
+/// Terms match the expression, result or label as a substring, as a fuzzy
+/// subsequence, or name a weekday. Returns indices into `history` alongside
+/// the entries.
+pub fn search_history<'a>(history: &'a [HistoryEntry], query: &str) -> Vec<(usize, &'a HistoryEntry)> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let mut scored: Vec<(i32, usize, &HistoryEntry)> = history
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| {
+            let mut total = 0;
+            for term in &terms {
+                total += term_score(term, entry)?;
+            }
+            Some((total, i, entry))
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+        b.2.starred
+            .cmp(&a.2.starred)
+            .then(b.0.cmp(&a.0))
+            .then(b.1.cmp(&a.1))
+    });
+    scored.into_iter().map(|(_, i, e)| (i, e)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-02 was a Tuesday.
+    const TUESDAY: u64 = 1_704_196_800;
+
+    fn entry(expression: &str, result_text: &str, timestamp: u64) -> HistoryEntry {
+        HistoryEntry {
+            expression: expression.into(),
+            result_text: result_text.into(),
+            result: result_text.parse().unwrap_or(0.0),
+            timestamp,
+            ..HistoryEntry::default()
+        }
+    }
+
+    fn exprs(found: Vec<(usize, &HistoryEntry)>) -> Vec<&str> {
+        found.iter().map(|(_, e)| e.expression.as_str()).collect()
+    }
+
+    #[test]
+    fn weekday_from_timestamp() {
+        assert_eq!(weekday(0), 4);
+        assert_eq!(weekday(TUESDAY), 2);
+        assert_eq!(weekday_name(TUESDAY + 86_400), "Wed");
+    }
+
+    #[test]
+    fn empty_query_lists_newest_first() {
+        let h = vec![entry("1+1", "2", 0), entry("2+2", "4", 0)];
+        assert_eq!(exprs(search_history(&h, "  ")), ["2+2", "1+1"]);
+    }
+
+    #[test]
+    fn substring_matches_expression_or_result() {
+        let h = vec![entry("100\u{00d7}1.25", "125", 0), entry("7+8", "15", 0)];
+        assert_eq!(exprs(search_history(&h, "125")), ["100\u{00d7}1.25"]);
+        assert_eq!(exprs(search_history(&h, "100*1.2")), ["100\u{00d7}1.25"]);
+        assert!(search_history(&h, "999").is_empty());
+    }
+
+    #[test]
+    fn fuzzy_ranks_below_substring() {
+        let h = vec![entry("1.25\u{00d7}80", "100", 0), entry("125\u{00d7}4", "500", 0)];
+        // "125" is a substring of the second entry but only a subsequence
+        // of the first.
+        assert_eq!(exprs(search_history(&h, "125")), ["125\u{00d7}4", "1.25\u{00d7}80"]);
+    }
+
+    #[test]
+    fn starred_first_and_labels_searchable() {
+        let mut h = vec![entry("1+1", "2", 0), entry("2+2", "4", 0), entry("3+3", "6", 0)];
+        h[0].starred = true;
+        h[0].label = Some("Monthly budget".into());
+        assert_eq!(exprs(search_history(&h, "")), ["1+1", "3+3", "2+2"]);
+        assert_eq!(exprs(search_history(&h, "budget")), ["1+1"]);
+    }
+
+    #[test]
+    fn weekday_terms_filter_by_day() {
+        let h = vec![
+            entry("49.99\u{00d7}1.25", "62.4875", TUESDAY),
+            entry("49.99\u{00d7}1.25", "62.4875", TUESDAY + 86_400),
+            entry("3+4", "7", TUESDAY + 3600),
+        ];
+        let found = search_history(&h, "1.25 tuesday");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, 0);
+        assert_eq!(search_history(&h, "tue").len(), 2);
+    }
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use super::highlight::{self, Kind, Span};
+
+/// A saved expression such as `price * 1.25`. Its placeholders, names that
+/// are not functions or constants (`_` by convention), stand for the value
+/// it is applied to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    pub expression: String,
+}
+
+impl Snippet {
+    pub fn new(name: &str, expression: &str) -> Self {
+        Self { name: name.into(), expression: expression.into() }
+    }
+
+    /// The placeholder names, in order of appearance and without repeats.
+    pub fn placeholders(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for span in self.placeholder_spans() {
+            let name = &self.expression[span.range];
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// The expression with every placeholder replaced by `value`.
+    pub fn fill(&self, value: f64) -> String {
+        let text = if value < 0.0 { format!("({})", value) } else { value.to_string() };
+        let mut out = String::new();
+        let mut copied = 0;
+        for span in self.placeholder_spans() {
+            out.push_str(&self.expression[copied..span.range.start]);
+            out.push_str(&text);
+            copied = span.range.end;
+        }
+        out.push_str(&self.expression[copied..]);
+        out
+    }
+
+    // Names other than `ans`/`ansN` and calls like `double(2)`, which go to
+    // the parser untouched.
+    fn placeholder_spans(&self) -> impl Iterator<Item = Span> + '_ {
+        highlight::lex(&self.expression).into_iter().filter(|span| {
+            let name = self.expression[span.range.clone()].to_lowercase();
+            let called = self.expression[span.range.end..].trim_start().starts_with('(');
+            let answer = name.strip_prefix("ans").is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()));
+            span.kind == Kind::Name && !called && !answer
+        })
+    }
+}
+
+/// What a fresh install offers, as examples of both placeholder styles.
+pub fn defaults() -> Vec<Snippet> {
+    vec![
+        Snippet::new("Add 25% VAT", "price * 1.25"),
+        Snippet::new("km \u{2192} mi", "_ * 0.6214"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_placeholders() {
+        assert_eq!(Snippet::new("", "price * 1.25").placeholders(), ["price"]);
+        assert_eq!(Snippet::new("", "_ * _ + sin(_)").placeholders(), ["_"]);
+        assert_eq!(Snippet::new("", "a * 2 + b").placeholders(), ["a", "b"]);
+        assert!(Snippet::new("", "ans2 * pi + double(3)").placeholders().is_empty());
+    }
+
+    #[test]
+    fn fills_placeholders_with_the_value() {
+        assert_eq!(Snippet::new("", "price * 1.25").fill(80.0), "80 * 1.25");
+        assert_eq!(Snippet::new("", "_ * 0.6214 + _").fill(-2.5), "(-2.5) * 0.6214 + (-2.5)");
+        assert_eq!(Snippet::new("", "sqrt(x)^2").fill(9.0), "sqrt(9)^2");
+        assert_eq!(Snippet::new("", "1 + 2").fill(7.0), "1 + 2");
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use super::types::format_number_default;
+
+/// Completions need at least this many letters, so single-letter names
+/// don't pop up a list on every keystroke.
+pub const MIN_PREFIX: usize = 2;
+
+pub const FUNCTIONS: &[(&str, &str)] = &[
+    ("sin", "sine"),
+    ("cos", "cosine"),
+    ("tan", "tangent"),
+    ("asin", "inverse sine"),
+    ("arcsin", "inverse sine"),
+    ("acos", "inverse cosine"),
+    ("arccos", "inverse cosine"),
+    ("atan", "inverse tangent"),
+    ("arctan", "inverse tangent"),
+    ("sinh", "hyperbolic sine"),
+    ("cosh", "hyperbolic cosine"),
+    ("tanh", "hyperbolic tangent"),
+    ("ln", "natural logarithm"),
+    ("log", "logarithm base 10"),
+    ("sqrt", "square root"),
+    ("cbrt", "cube root"),
+    ("abs", "absolute value"),
+    ("exp", "e to the power of"),
+];
+
+const CONSTANTS: &[(&str, f64)] = &[("pi", std::f64::consts::PI), ("e", std::f64::consts::E)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SymbolKind {
+    // Declaration order is the order completions are listed in.
+    Variable,
+    Plugin,
+    Function,
+    Constant,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub detail: String,
+}
+
+impl Symbol {
+    fn new(name: &str, kind: SymbolKind, detail: String) -> Self {
+        Self { name: name.to_string(), kind, detail }
+    }
+
+    /// What completing this symbol inserts; functions come with their
+    /// opening parenthesis.
+    pub fn insert_text(&self) -> String {
+        match self.kind {
+            SymbolKind::Function | SymbolKind::Plugin => format!("{}(", self.name),
+            SymbolKind::Variable | SymbolKind::Constant => self.name.clone(),
+        }
+    }
+}
+
+/// Every name an expression can use at some point.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    symbols: Vec<Symbol>,
+}
+
+impl SymbolTable {
+    pub fn builtin() -> Self {
+        let mut symbols: Vec<Symbol> = FUNCTIONS
+            .iter()
+            .map(|(name, detail)| Symbol::new(name, SymbolKind::Function, detail.to_string()))
+            .collect();
+        symbols.extend(
+            CONSTANTS
+                .iter()
+                .map(|(name, value)| Symbol::new(name, SymbolKind::Constant, format_number_default(*value))),
+        );
+        Self { symbols }
+    }
+
+    pub fn with_plugins(mut self, plugins: &HashMap<String, String>) -> Self {
+        for (name, template) in plugins {
+            self.symbols.push(Symbol::new(name, SymbolKind::Plugin, template.clone()));
+        }
+        self
+    }
+
+    /// Adds or updates a user variable.
+    pub fn define(&mut self, name: &str, value: f64) {
+        let detail = format!("= {}", format_number_default(value));
+        match self.symbols.iter_mut().find(|s| s.kind == SymbolKind::Variable && s.name == name) {
+            Some(symbol) => symbol.detail = detail,
+            None => self.symbols.push(Symbol::new(name, SymbolKind::Variable, detail)),
+        }
+    }
+
+    /// Symbols that start with `prefix` (ignoring case) and are longer than
+    /// it: variables first, then plugins, functions and constants, shortest
+    /// first within each group.
+    pub fn complete(&self, prefix: &str) -> Vec<&Symbol> {
+        if prefix.chars().count() < MIN_PREFIX {
+            return Vec::new();
+        }
+        let prefix = prefix.to_lowercase();
+        let mut found: Vec<&Symbol> = self
+            .symbols
+            .iter()
+            .filter(|s| s.name.len() > prefix.len() && s.name.to_lowercase().starts_with(&prefix))
+            .collect();
+        found.sort_by(|a, b| {
+            a.kind
+                .cmp(&b.kind)
+                .then(a.name.len().cmp(&b.name.len()))
+                .then(a.name.cmp(&b.name))
+        });
+        found
+    }
+}
+
+/// The identifier being typed just before `cursor` (a byte offset), and
+/// where it starts.
+pub fn word_before(text: &str, cursor: usize) -> (usize, &str) {
+    let head = &text[..cursor];
+    let start = head
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| c.is_alphanumeric() || c == '_')
+        .last()
+        .map_or(cursor, |(i, _)| i);
+    // "2sin" is a number followed by a word; only the letters count.
+    let start = start + head[start..].find(|c: char| !c.is_ascii_digit()).unwrap_or(head.len() - start);
+    (start, &head[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(found: Vec<&Symbol>) -> Vec<&str> {
+        found.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn completes_functions_by_prefix() {
+        let table = SymbolTable::builtin();
+        assert_eq!(names(table.complete("si")), ["sin", "sinh"]);
+        assert_eq!(names(table.complete("AR")), ["arccos", "arcsin", "arctan"]);
+        assert!(table.complete("s").is_empty());
+        assert!(table.complete("sinh").is_empty());
+    }
+
+    #[test]
+    fn variables_and_plugins_come_first() {
+        let mut plugins = HashMap::new();
+        plugins.insert("sinc".to_string(), "sin(x)/x".to_string());
+        let mut table = SymbolTable::builtin().with_plugins(&plugins);
+        table.define("sine_rule", 1.0);
+        table.define("sine_rule", 2.0);
+        let found = table.complete("sin");
+        assert_eq!(names(found.clone()), ["sine_rule", "sinc", "sinh"]);
+        assert_eq!(found[0].detail, "= 2");
+        assert_eq!(found[1].insert_text(), "sinc(");
+    }
+
+    #[test]
+    fn word_before_cursor() {
+        assert_eq!(word_before("2 + sq", 6), (4, "sq"));
+        assert_eq!(word_before("3si", 3), (1, "si"));
+        assert_eq!(word_before("rate_2", 6), (0, "rate_2"));
+        assert_eq!(word_before("cos(12", 6), (6, ""));
+        assert_eq!(word_before("tan + 1", 3), (0, "tan"));
+    }
+}
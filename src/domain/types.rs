@@ -8,6 +8,8 @@ pub enum BinaryOp {
     Divide,
     Power,
     Modulo,
+    /// `12.3 ± 0.2`: a value with its uncertainty, see `uncertain`.
+    PlusMinus,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -41,6 +43,10 @@ pub enum PostfixOp {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Number(f64),
+    /// A whole number too large for `Number` to hold exactly, see `bigint`.
+    Integer(super::bigint::BigInt),
+    /// `[lo, hi]`, see `interval`.
+    Interval(f64, f64),
     Constant(&'static str, f64),
     BinaryOp(BinaryOp),
     UnaryFunc(UnaryFunc),
@@ -55,7 +61,7 @@ pub enum AngleMode {
     Degrees,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct HistoryEntry {
     pub expression: String,
     pub result_text: String,
@@ -64,6 +70,24 @@ pub struct HistoryEntry {
     pub timestamp: u64,
     #[serde(default)]
     pub session: u64,
+    /// Starred entries sort first and survive "clear history".
+    #[serde(default)]
+    pub starred: bool,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+impl HistoryEntry {
+    pub fn new(expression: String, result: f64, timestamp: u64, session: u64) -> Self {
+        Self {
+            expression,
+            result_text: format_number_default(result),
+            result,
+            timestamp,
+            session,
+            ..Self::default()
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,14 +112,15 @@ impl BinaryOp {
             Self::Divide => "\u{00f7}",
             Self::Power => "^",
             Self::Modulo => " mod ",
+            Self::PlusMinus => "\u{00b1}",
         }
     }
 
     pub fn precedence(self, standard: bool) -> u8 {
-        if !standard {
-            return 1;
-        }
         match self {
+            // Binds tightest, so "2 × 3 ± 0.1" doubles the error too.
+            Self::PlusMinus => 4,
+            _ if !standard => 1,
             Self::Add | Self::Subtract => 1,
             Self::Multiply | Self::Divide | Self::Modulo => 2,
             Self::Power => 3,
@@ -141,9 +166,52 @@ impl PostfixOp {
     }
 }
 
+/// The percent questions offered from a long press on the `%` key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PercentQuery {
+    /// x% of y
+    Of,
+    /// The change from x to y, as a percentage of x.
+    Change,
+    /// What percent x is of y.
+    Share,
+}
+
+impl PercentQuery {
+    pub const ALL: [Self; 3] = [Self::Of, Self::Change, Self::Share];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Of => "x% of y",
+            Self::Change => "Percent change from x to y",
+            Self::Share => "What percent is x of y",
+        }
+    }
+
+    pub fn operand_labels(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Of => ("Percent", "Of"),
+            Self::Change => ("From", "To"),
+            Self::Share => ("Part", "Whole"),
+        }
+    }
+
+    /// The question as an expression for the display.
+    pub fn expression(self, x: f64, y: f64) -> String {
+        let num = |v: f64| if v < 0.0 { format!("({})", v) } else { v.to_string() };
+        match self {
+            Self::Of => format!("{}% \u{00d7} {}", num(x), num(y)),
+            Self::Change => format!("({} \u{2212} {}) \u{00f7} {} \u{00d7} 100", num(y), num(x), num(x)),
+            Self::Share => format!("{} \u{00f7} {} \u{00d7} 100", num(x), num(y)),
+        }
+    }
+}
+
 pub fn token_display(token: &Token) -> String {
     match token {
-        Token::Number(n) => format_number_default(*n),
+        Token::Number(n) => format_number_exact(*n),
+        Token::Integer(n) => n.to_string(),
+        Token::Interval(lo, hi) => format!("[{}, {}]", format_number_default(*lo), format_number_default(*hi)),
         Token::Constant(name, _) => name.to_string(),
         Token::BinaryOp(op) => op.symbol().to_string(),
         Token::UnaryFunc(f) => format!("{}(", f.name()),
@@ -170,6 +238,77 @@ pub fn format_number_default(val: f64) -> String {
     s.trim_end_matches('0').trim_end_matches('.').to_string()
 }
 
+/// Splits a whole number longer than 15 digits into groups of three with
+/// thin spaces, `1 152 921 504 606 846 976`; other text is left alone.
+pub fn group_digits(text: &str) -> String {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    if digits.len() <= 15 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return text.to_string();
+    }
+    let mut out = String::from(&text[..text.len() - digits.len()]);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push('\u{2009}');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Like `format_number_default`, and also whether the text had to round
+/// `val` (reading it back gives a different number).
+pub fn format_number_rounded(val: f64) -> (String, bool) {
+    let text = format_number_default(val);
+    let rounded = text.parse::<f64>().is_ok_and(|shown| shown != val);
+    (text, rounded)
+}
+
+/// Like `format_number_default`, with as many digits as it takes to read
+/// back as `val`, so a typed `0.12345678901234` keeps every digit when an
+/// expression is shown, saved to history and loaded again.
+pub fn format_number_exact(val: f64) -> String {
+    let (text, rounded) = format_number_rounded(val);
+    if !rounded {
+        text
+    } else if val.abs() >= 1e15 || val.abs() < 1e-4 {
+        format!("{:e}", val)
+    } else {
+        format!("{}", val)
+    }
+}
+
+/// The simplest fraction p/q with q up to `max_den` that is exactly `val` as
+/// a float, so `1.0 / 3.0` gives 1/3 but `0.1 + 0.2` gives nothing.
+pub fn fraction(val: f64, max_den: i64) -> Option<(i64, i64)> {
+    if !val.is_finite() || val.fract() == 0.0 {
+        return None;
+    }
+    let target = val.abs();
+    let (mut h0, mut h1, mut k0, mut k1) = (0i64, 1i64, 1i64, 0i64);
+    let mut x = target;
+    for _ in 0..64 {
+        if x >= i64::MAX as f64 {
+            return None;
+        }
+        let a = x.floor() as i64;
+        let h2 = a.checked_mul(h1)?.checked_add(h0)?;
+        let k2 = a.checked_mul(k1)?.checked_add(k0)?;
+        if k2 > max_den {
+            return None;
+        }
+        (h0, h1, k0, k1) = (h1, h2, k1, k2);
+        if h1 as f64 / k1 as f64 == target {
+            return Some((if val < 0.0 { -h1 } else { h1 }, k1));
+        }
+        let rest = x - a as f64;
+        if rest == 0.0 {
+            return None;
+        }
+        x = 1.0 / rest;
+    }
+    None
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ConvertCategory {
     Length,
@@ -198,6 +337,13 @@ mod tests {
         assert_eq!(format_number_default(42.0), "42");
     }
 
+    #[test]
+    fn group_long_whole_numbers() {
+        assert_eq!(group_digits("-1152921504606846976"), "-1\u{2009}152\u{2009}921\u{2009}504\u{2009}606\u{2009}846\u{2009}976");
+        assert_eq!(group_digits("123456789012345"), "123456789012345");
+        assert_eq!(group_digits("1.152921504606847e18"), "1.152921504606847e18");
+    }
+
     #[test]
     fn format_negative_integer() {
         assert_eq!(format_number_default(-7.0), "-7");
@@ -218,6 +364,25 @@ mod tests {
         assert_eq!(format_number_default(2.50), "2.5");
     }
 
+    #[test]
+    fn rounding_is_reported() {
+        assert_eq!(format_number_rounded(1.0 / 3.0), ("0.3333333333".to_string(), true));
+        assert_eq!(format_number_rounded(0.1 + 0.2), ("0.3".to_string(), true));
+        assert_eq!(format_number_rounded(2.5), ("2.5".to_string(), false));
+        assert_eq!(format_number_rounded(1e-7), ("1e-7".to_string(), false));
+        assert!(!format_number_rounded(f64::NAN).1);
+    }
+
+    #[test]
+    fn fractions_must_be_exact() {
+        assert_eq!(fraction(1.0 / 3.0, 1_000_000), Some((1, 3)));
+        assert_eq!(fraction(-22.0 / 7.0, 1_000_000), Some((-22, 7)));
+        assert_eq!(fraction(0.125, 1_000_000), Some((1, 8)));
+        assert_eq!(fraction(0.1 + 0.2, 1_000_000), None);
+        assert_eq!(fraction(std::f64::consts::PI, 1_000_000), None);
+        assert_eq!(fraction(4.0, 1_000_000), None);
+    }
+
     #[test]
     fn format_nan() {
         assert_eq!(format_number_default(f64::NAN), "Error");
@@ -294,6 +459,8 @@ mod tests {
         assert_eq!(BinaryOp::Divide.precedence(true), 2);
         assert_eq!(BinaryOp::Modulo.precedence(true), 2);
         assert_eq!(BinaryOp::Power.precedence(true), 3);
+        assert_eq!(BinaryOp::PlusMinus.precedence(true), 4);
+        assert_eq!(BinaryOp::PlusMinus.precedence(false), 4);
     }
 
     #[test]
@@ -349,17 +516,17 @@ mod tests {
     #[test]
     fn history_entry_roundtrip() {
         let entry = HistoryEntry {
-            expression: "2+3".into(),
-            result_text: "5".into(),
-            result: 5.0,
-            timestamp: 1000,
-            session: 1,
+            label: Some("budget".into()),
+            starred: true,
+            ..HistoryEntry::new("2+3".into(), 5.0, 1000, 1)
         };
         let json = serde_json::to_string(&entry).unwrap();
         let back: HistoryEntry = serde_json::from_str(&json).unwrap();
         assert_eq!(back.expression, "2+3");
         assert_eq!(back.result, 5.0);
         assert_eq!(back.timestamp, 1000);
+        assert!(back.starred);
+        assert_eq!(back.label.as_deref(), Some("budget"));
     }
 
     #[test]
@@ -368,6 +535,8 @@ mod tests {
         let entry: HistoryEntry = serde_json::from_str(json).unwrap();
         assert_eq!(entry.timestamp, 0);
         assert_eq!(entry.session, 0);
+        assert!(!entry.starred);
+        assert_eq!(entry.label, None);
     }
 
     #[test]
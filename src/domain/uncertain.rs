@@ -0,0 +1,149 @@
+use super::ast::Expr;
+use super::eval;
+use super::types::*;
+
+/// A measured value and its standard uncertainty, `value ± error`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Uncertain {
+    pub value: f64,
+    pub error: f64,
+}
+
+impl Uncertain {
+    pub fn exact(value: f64) -> Self {
+        Self { value, error: 0.0 }
+    }
+}
+
+/// Whether the expression has a `±` in it, so its result carries an error.
+pub fn has_uncertainty(tokens: &[Token]) -> bool {
+    tokens.iter().any(|t| matches!(t, Token::BinaryOp(BinaryOp::PlusMinus)))
+}
+
+/// Evaluates `expr` with first-order error propagation: the errors of the
+/// operands are treated as independent and add in quadrature, each weighted
+/// by the partial derivative of the operation.
+pub fn evaluate(expr: &Expr, angle_mode: AngleMode) -> Result<Uncertain, String> {
+    match expr {
+        Expr::Number(n) | Expr::Constant(_, n) => Ok(Uncertain::exact(*n)),
+        // An error estimate could not promise to stay within the bounds.
+        Expr::Interval(..) => Err("Intervals need interval arithmetic".into()),
+        Expr::Group(inner) => evaluate(inner, angle_mode),
+        Expr::Binary(op, a, b) => binary(*op, evaluate(a, angle_mode)?, evaluate(b, angle_mode)?),
+        Expr::Func(f, arg) => {
+            let a = evaluate(arg, angle_mode)?;
+            propagate(a, |x| eval::apply_unary(*f, x, angle_mode))
+        }
+        Expr::Postfix(p, inner) => {
+            let a = evaluate(inner, angle_mode)?;
+            if *p == PostfixOp::Factorial && a.error > 0.0 {
+                return Err("Factorial of an uncertain value".into());
+            }
+            propagate(a, |x| eval::apply_postfix(*p, x))
+        }
+    }
+}
+
+pub fn binary(op: BinaryOp, a: Uncertain, b: Uncertain) -> Result<Uncertain, String> {
+    let value = eval::apply_binary(op, a.value, b.value)?;
+    // ∂result/∂a and ∂result/∂b.
+    let (da, db) = match op {
+        BinaryOp::PlusMinus => {
+            return Ok(Uncertain { value, error: a.error.hypot(b.value) });
+        }
+        BinaryOp::Add => (1.0, 1.0),
+        BinaryOp::Subtract => (1.0, -1.0),
+        BinaryOp::Multiply => (b.value, a.value),
+        BinaryOp::Divide => (1.0 / b.value, -a.value / (b.value * b.value)),
+        BinaryOp::Power => (b.value * a.value.powf(b.value - 1.0), value * a.value.ln()),
+        BinaryOp::Modulo => (1.0, -(a.value / b.value).trunc()),
+    };
+    // An exact operand contributes nothing, even where its derivative blows up.
+    let term = |d: f64, e: f64| if e > 0.0 { d * e } else { 0.0 };
+    let error = eval::finite(term(da, a.error).hypot(term(db, b.error)))?;
+    Ok(Uncertain { value, error })
+}
+
+// |f'(x)|·σ, with the slope taken by central difference; every function on
+// the keypad is smooth wherever it is defined.
+fn propagate(a: Uncertain, f: impl Fn(f64) -> Result<f64, String>) -> Result<Uncertain, String> {
+    let value = f(a.value)?;
+    if a.error == 0.0 {
+        return Ok(Uncertain::exact(value));
+    }
+    let h = a.value.abs().max(1.0) * 1e-6;
+    let slope = (f(a.value + h)? - f(a.value - h)?) / (2.0 * h);
+    Ok(Uncertain { value, error: eval::finite(slope.abs() * a.error)? })
+}
+
+/// `value ± error`, the error given to one significant figure (two when it
+/// starts with a 1) and the value rounded to the same decimal place.
+pub fn format(u: Uncertain) -> String {
+    if u.error == 0.0 {
+        return format_number_default(u.value);
+    }
+    let exponent = u.error.log10().floor() as i32;
+    let leading = u.error / 10f64.powi(exponent);
+    let place = if leading < 2.0 { exponent - 1 } else { exponent };
+    let decimals = (-place).max(0) as usize;
+    // Adding 0.0 turns a rounded -0 into 0.
+    let round = |x: f64| (x / 10f64.powi(place)).round() * 10f64.powi(place) + 0.0;
+    format!("{:.*} \u{00b1} {:.*}", decimals, round(u.value), decimals, round(u.error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ast;
+    use std::collections::HashMap;
+
+    fn evaluate_str(input: &str) -> Result<Option<Uncertain>, String> {
+        let tokens = eval::parse_expression(input, &HashMap::new())?;
+        if !has_uncertainty(&tokens) {
+            return Ok(None);
+        }
+        evaluate(&ast::build(&tokens, true)?, AngleMode::Radians).map(Some)
+    }
+
+    fn eval(input: &str) -> Uncertain {
+        evaluate_str(input).unwrap().unwrap()
+    }
+
+    fn close(u: Uncertain, value: f64, error: f64) -> bool {
+        (u.value - value).abs() < 1e-9 && (u.error - error).abs() < 1e-6
+    }
+
+    #[test]
+    fn errors_propagate_through_arithmetic() {
+        assert!(close(eval("12.3 \u{00b1} 0.2"), 12.3, 0.2));
+        assert!(close(eval("12.3 +/- 0.2"), 12.3, 0.2));
+        assert!(close(eval("(2 \u{00b1} 0.3) + (3 \u{00b1} 0.4)"), 5.0, 0.5));
+        assert!(close(eval("2 \u{00d7} 10 \u{00b1} 1"), 20.0, 2.0));
+        assert!(close(eval("10 \u{00b1} 1 \u{00d7} 5 \u{00b1} 0.5"), 50.0, 50f64.sqrt()));
+        assert!(close(eval("(10 \u{00b1} 1) / 4"), 2.5, 0.25));
+        assert!(close(eval("(3 \u{00b1} 0.1)^2"), 9.0, 0.6));
+    }
+
+    #[test]
+    fn errors_propagate_through_functions() {
+        assert!(close(eval("sqrt(16 \u{00b1} 0.8)"), 4.0, 0.1));
+        assert!(close(eval("ln(2 \u{00b1} 0.1)"), 2f64.ln(), 0.05));
+        assert!(close(eval("exp(0 \u{00b1} 0.1)"), 1.0, 0.1));
+        assert_eq!(evaluate_str("sqrt(16)"), Ok(None));
+    }
+
+    #[test]
+    fn factorial_needs_an_exact_value() {
+        assert!(evaluate_str("(3 \u{00b1} 1)!").is_err());
+    }
+
+    #[test]
+    fn formats_to_the_precision_of_the_error() {
+        let u = |value, error| format(Uncertain { value, error });
+        assert_eq!(u(12.3, 0.2), "12.3 \u{00b1} 0.2");
+        assert_eq!(u(9.81234, 0.0143), "9.812 \u{00b1} 0.014");
+        assert_eq!(u(1234.5, 23.0), "1230 \u{00b1} 20");
+        assert_eq!(u(-0.01, 0.3), "0.0 \u{00b1} 0.3");
+        assert_eq!(u(5.0, 0.0), "5");
+    }
+}
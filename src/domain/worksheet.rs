@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::Range;
+
+use super::engine::EvalSettings;
+use super::eval::{self, Evaluated};
+use super::highlight::{self, Kind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineValue {
+    Blank,
+    Value(Evaluated),
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineResult {
+    /// Set for `name = expr` lines.
+    pub name: Option<String>,
+    pub value: LineValue,
+    /// Byte range of the offending text within the line, when an error can
+    /// be pinned to one.
+    pub error_span: Option<Range<usize>>,
+}
+
+impl LineResult {
+    fn new(name: Option<String>, value: LineValue) -> Self {
+        Self { name, value, error_span: None }
+    }
+
+    pub fn number(&self) -> Option<f64> {
+        match self.value {
+            LineValue::Value(ref v) => Some(v.value),
+            _ => None,
+        }
+    }
+}
+
+struct LineError {
+    message: String,
+    span: Option<Range<usize>>,
+}
+
+impl LineError {
+    fn at(span: Range<usize>, message: String) -> Self {
+        Self { message, span: Some(span) }
+    }
+}
+
+// Words the expression parser already understands; they cannot be used as
+// variable names and are passed through untouched.
+fn is_builtin(word: &str, plugins: &HashMap<String, String>) -> bool {
+    let lower = word.to_lowercase();
+    highlight::is_function(&lower)
+        || matches!(lower.as_str(), "pi" | "e" | "mod" | "of")
+        || plugins.contains_key(&lower)
+}
+
+/// Splits `rate = 1.25` into its name and the offset of its expression.
+fn split_assignment<'a>(line: &'a str, plugins: &HashMap<String, String>) -> Result<(Option<&'a str>, usize), LineError> {
+    let line = line.trim_end_matches('=');
+    match line.split_once('=') {
+        Some((lhs, _)) => {
+            let name = lhs.trim();
+            let valid = matches!(highlight::lex(name).as_slice(), [span] if span.kind == Kind::Name);
+            if valid && !is_builtin(name, plugins) && name != "ans" {
+                Ok((Some(name), lhs.len() + 1))
+            } else {
+                let start = lhs.len() - lhs.trim_start().len();
+                Err(LineError::at(start..start + name.len(), format!("Cannot assign to \u{2018}{}\u{2019}", name)))
+            }
+        }
+        None => Ok((None, 0)),
+    }
+}
+
+/// Replaces variable names, `ans` and `#n` line references with the values
+/// they stand for, so the result can go through the ordinary parser. The
+/// text is written to `out`, which is cleared first.
+fn substitute(
+    expr: &str,
+    vars: &HashMap<String, f64>,
+    earlier: &[LineResult],
+    plugins: &HashMap<String, String>,
+    out: &mut String,
+) -> Result<(), LineError> {
+    out.clear();
+    let mut copied = 0;
+    for span in highlight::lex(expr) {
+        let word = &expr[span.range.clone()];
+        let value = match span.kind {
+            Kind::Reference => {
+                let n: usize = word[1..].parse().map_err(|_| LineError::at(span.range.clone(), "Expected a line number after #".into()))?;
+                let line = n
+                    .checked_sub(1)
+                    .and_then(|idx| earlier.get(idx))
+                    .ok_or_else(|| LineError::at(span.range.clone(), format!("#{} is not an earlier line", n)))?;
+                line.number()
+                    .ok_or_else(|| LineError::at(span.range.clone(), format!("Line {} has no value", n)))?
+            }
+            Kind::Name => match vars.get(word) {
+                Some(&v) => v,
+                None if word == "ans" => earlier
+                    .iter()
+                    .rev()
+                    .find_map(LineResult::number)
+                    .ok_or_else(|| LineError::at(span.range.clone(), "No previous result".into()))?,
+                None if is_builtin(word, plugins) => continue,
+                None => return Err(LineError::at(span.range, format!("Unknown name \u{2018}{}\u{2019}", word))),
+            },
+            _ => continue,
+        };
+        out.push_str(&expr[copied..span.range.start]);
+        let _ = write!(out, "({})", value);
+        copied = span.range.end;
+    }
+    out.push_str(&expr[copied..]);
+    Ok(())
+}
+
+fn evaluate_line(
+    line: &str,
+    vars: &mut HashMap<String, f64>,
+    earlier: &[LineResult],
+    plugins: &HashMap<String, String>,
+    evaluator: &mut eval::Evaluator,
+    text: &mut String,
+) -> LineResult {
+    let (name, offset) = match split_assignment(line, plugins) {
+        Ok(parts) => parts,
+        Err(e) => return LineResult { name: None, value: LineValue::Error(e.message), error_span: e.span },
+    };
+    let expr = line[offset..].trim_end_matches('=');
+    let shift = |span: Option<Range<usize>>| span.map(|r| r.start + offset..r.end + offset);
+    let value = match highlight::check(expr) {
+        Some(e) => Err(LineError { message: e.message, span: Some(e.range) }),
+        None => substitute(expr, vars, earlier, plugins, text).and_then(|()| {
+            evaluator.evaluate(text).map_err(|message| LineError { message, span: None })
+        }),
+    };
+    match value {
+        Ok(v) => {
+            if let Some(name) = name {
+                vars.insert(name.to_string(), v.value);
+            }
+            LineResult::new(name.map(str::to_string), LineValue::Value(v))
+        }
+        Err(e) => {
+            // A failed assignment must not leave a stale value behind.
+            if let Some(name) = name {
+                vars.remove(name);
+            }
+            LineResult { name: name.map(str::to_string), value: LineValue::Error(e.message), error_span: shift(e.span) }
+        }
+    }
+}
+
+/// Evaluates every line of a worksheet top to bottom. Lines can assign a
+/// name (`rate = 1.25`), use names assigned above, `ans` for the nearest
+/// value above, and `#3` for the value of line 3. Because the whole sheet is
+/// recomputed, editing a line updates everything that depends on it.
+pub fn evaluate(
+    lines: &[String],
+    plugins: &HashMap<String, String>,
+    settings: EvalSettings,
+) -> Vec<LineResult> {
+    let mut results: Vec<LineResult> = Vec::with_capacity(lines.len());
+    let mut vars: HashMap<String, f64> = HashMap::new();
+    let mut evaluator = eval::Evaluator::new(plugins, settings);
+    let mut text = String::new();
+    for line in lines {
+        let result = if line.trim().is_empty() || highlight::is_comment(line) {
+            LineResult::new(None, LineValue::Blank)
+        } else {
+            evaluate_line(line, &mut vars, &results, plugins, &mut evaluator, &mut text)
+        };
+        results.push(result);
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(lines: &[&str]) -> Vec<LineValue> {
+        let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        evaluate(&lines, &HashMap::new(), EvalSettings::default()).into_iter().map(|r| r.value).collect()
+    }
+
+    fn value(v: f64) -> LineValue {
+        LineValue::Value(Evaluated { value: v, text: crate::domain::types::format_number_default(v) })
+    }
+
+    #[test]
+    fn plain_lines_and_comments() {
+        assert_eq!(
+            run(&["2 + 3 * 4", "", "// note", "# heading", "10 \u{00f7} 4 ="]),
+            [value(14.0), LineValue::Blank, LineValue::Blank, LineValue::Blank, value(2.5)]
+        );
+    }
+
+    #[test]
+    fn names_ans_and_line_references() {
+        let values = run(&["price = 80", "vat = 25%", "price * (1 + vat)", "ans / 2", "#1 + #3"]);
+        assert_eq!(values[2], value(100.0));
+        assert_eq!(values[3], value(50.0));
+        assert_eq!(values[4], value(180.0));
+    }
+
+    #[test]
+    fn results_read_as_in_the_calculator() {
+        let values = run(&["big = 99999999999999999 + 1", "12.3 \u{00b1} 0.2", "[1,2] + 1"]);
+        let texts: Vec<&str> = values
+            .iter()
+            .map(|v| match v {
+                LineValue::Value(e) => e.text.as_str(),
+                _ => "",
+            })
+            .collect();
+        assert_eq!(texts, ["100000000000000000", "12.3 \u{00b1} 0.2", "[2, 3]"]);
+    }
+
+    #[test]
+    fn names_can_be_reassigned_further_down() {
+        let values = run(&["x = 2", "x^2", "x = 3", "x^2"]);
+        assert_eq!(values[1], value(4.0));
+        assert_eq!(values[3], value(9.0));
+    }
+
+    #[test]
+    fn errors_are_reported_per_line() {
+        let values = run(&["1 / 0", "y + 1", "#5", "ans", "x = 1/0", "x"]);
+        assert!(matches!(values[0], LineValue::Error(_)));
+        assert_eq!(values[1], LineValue::Error("Unknown name \u{2018}y\u{2019}".into()));
+        assert_eq!(values[2], LineValue::Error("#5 is not an earlier line".into()));
+        assert_eq!(values[3], LineValue::Error("No previous result".into()));
+        assert!(matches!(values[5], LineValue::Error(_)));
+    }
+
+    #[test]
+    fn error_spans_index_the_line() {
+        let lines: Vec<String> = ["total = 2 + * 3", "  total + rate", "x = (1 + 2))"].iter().map(|l| l.to_string()).collect();
+        let results = evaluate(&lines, &HashMap::new(), EvalSettings::default());
+        let spans: Vec<&str> = results
+            .iter()
+            .zip(&lines)
+            .map(|(r, line)| &line[r.error_span.clone().unwrap()])
+            .collect();
+        assert_eq!(spans, ["*", "total", ")"]);
+    }
+
+    #[test]
+    fn builtins_are_not_variables() {
+        let values = run(&["sin = 3", "sqrt(16) + pi - pi"]);
+        assert_eq!(values[0], LineValue::Error("Cannot assign to \u{2018}sin\u{2019}".into()));
+        assert_eq!(values[1], value(4.0));
+    }
+}
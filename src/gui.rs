@@ -0,0 +1,2914 @@
+use crate::app::message::Message;
+use crate::app::state::{AppState, ModePanel, Panel};
+use crate::app::display::{self, DisplayUpdate, ExactValue, ResultSize};
+use crate::app::update::{self, SideEffect};
+use crate::domain::ast::CopyStyle;
+use crate::domain::snippet::Snippet;
+use crate::domain::types::{AngleMode, ConvertCategory, PercentQuery};
+use crate::domain::symbols::{Symbol, SymbolTable};
+use crate::domain::worksheet::LineValue;
+use crate::services::config::Config;
+use crate::services::history::ExportFormat;
+use crate::services::theme::{self, Theme, ThemeManager};
+use crate::ui::builder::{CalculatorUI, Keys};
+use crate::ui::layout::{self, ButtonAction, KeySpec, Keypad};
+use crate::{domain, services, ui};
+
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+pub fn run(config: Config, style: Option<PathBuf>) {
+    gtk::init().expect("Failed to initialize GTK");
+    services::resources::install_icons();
+
+    ui::keyboard::init_keymap(&config.keybindings);
+
+    let session_id = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let state = Rc::new(RefCell::new(AppState::new(config, session_id)));
+
+    update::restore_session(&mut state.borrow_mut());
+    state.borrow_mut().snippets = services::snippets::load();
+
+    let screen = gtk::gdk::Screen::default().expect("Failed to get default screen");
+    let mut theme_mgr = {
+        let s = state.borrow();
+        ThemeManager::new(screen, &s.config.theme, &s.config.layout, &s.config.feedback)
+    };
+    let mut stylesheets = vec![theme::user_stylesheet_path()];
+    stylesheets.extend(style);
+    theme_mgr.watch_stylesheets(stylesheets);
+    let theme_mgr = Rc::new(RefCell::new(theme_mgr));
+
+    let mut calc_ui = {
+        let s = state.borrow();
+        ui::builder::build(&s.config, &services::keypad::load())
+    };
+
+    let keys = std::mem::take(&mut calc_ui.keys);
+    let gui = Gui::new(state, &calc_ui, theme_mgr, keys);
+
+    rebuild_tab_buttons(&gui);
+
+    wire_keypad(&gui);
+    wire_panel_buttons(&gui, &calc_ui);
+    wire_menu_buttons(&gui, &calc_ui);
+    wire_converter(&calc_ui);
+    wire_tools(&gui, &calc_ui);
+    wire_notes(&gui, &calc_ui);
+    rebuild_worksheet(&gui);
+    wire_drag_and_drop(&gui, &calc_ui);
+    wire_convert_popover(&gui, &calc_ui);
+    wire_exact_popover(&gui, &calc_ui);
+    wire_clipboard_watch(&gui, &calc_ui);
+    wire_keyboard(&gui, &calc_ui);
+    wire_window_close(&gui, &calc_ui);
+    wire_shutdown_signals(&gui);
+    #[cfg(feature = "tray")]
+    wire_tray(&gui);
+
+    let wcfg = &gui.state.borrow().config.window.clone();
+    if wcfg.always_on_top {
+        calc_ui.window.set_keep_above(true);
+    }
+    if wcfg.opacity < 1.0 && wcfg.opacity > 0.0 {
+        calc_ui.window.set_opacity(wcfg.opacity);
+    }
+    if wcfg.compact_mode {
+        calc_ui.window.set_decorated(false);
+    }
+    if wcfg.remember_geometry {
+        if let Some((x, y, w, h)) = services::session::load_geometry() {
+            calc_ui.window.move_(x, y);
+            calc_ui.window.resize(w, h);
+        }
+    }
+
+    {
+        let s = gui.state.borrow();
+        if s.scientific_mode {
+            calc_ui.menu_sci_btn.style_context().add_class("active");
+            calc_ui.menu_basic_btn.style_context().remove_class("active");
+        }
+    }
+
+    calc_ui.window.show_all();
+
+    {
+        let s = gui.state.borrow();
+        if !s.scientific_mode {
+            calc_ui.sci_grid.hide();
+        }
+    }
+    calc_ui.panel_revealer.set_reveal_child(false);
+    calc_ui.mode_panel_revealer.set_reveal_child(false);
+
+    gui.display.refresh(&gui.state.borrow());
+
+    apply_scale(&gui);
+    wire_orientation(&gui, &calc_ui);
+
+    gtk::main();
+}
+
+/// Rescales the text and switches between the portrait and landscape
+/// arrangement as the window is resized.
+fn wire_orientation(gui: &Gui, calc_ui: &CalculatorUI) {
+    let gui = gui.clone();
+    let view = calc_ui.calc_view.clone();
+    let display = calc_ui.display_event_box.clone();
+    let sci = calc_ui.sci_grid.clone();
+    let main = calc_ui.main_grid.clone();
+    let content = calc_ui.content_box.clone();
+    let mode_panel = calc_ui.mode_panel_revealer.clone();
+    calc_ui.window.connect_configure_event(move |window, _| {
+        apply_scale(&gui);
+        let (w, h) = window.size();
+        let changed = gui.state.borrow_mut().fit_window(w, h);
+        if changed {
+            let landscape = gui.state.borrow().landscape;
+            ui::builder::place_panes(&view, &display, &sci, &main, landscape);
+            ui::builder::place_mode_panel(&content, &mode_panel, landscape);
+        }
+        false
+    });
+}
+
+/// Sizes keypad and display text for the zoom level and window size.
+fn apply_scale(gui: &Gui) {
+    let s = gui.state.borrow();
+    let wcfg = &s.config.window;
+    let growth = theme::display_growth((wcfg.default_width, wcfg.default_height), gui.window.size());
+    gui.theme_mgr.borrow_mut().set_scale(theme::scale_css(&s.config.layout, s.zoom, growth));
+}
+
+// ── Display rendering ────────────────────────────────────────────────────────
+
+#[derive(Clone)]
+struct DisplayWidgets {
+    expr: gtk::Label,
+    result_l: gtk::Label,
+    preview: gtk::Label,
+    exact_btn: gtk::Button,
+    /// Shared with `Gui`; the angle key changes when the keypad is rebuilt.
+    keys: Rc<RefCell<Keys>>,
+}
+
+impl DisplayWidgets {
+    fn new(calc_ui: &CalculatorUI, keys: Rc<RefCell<Keys>>) -> Self {
+        Self {
+            expr: calc_ui.expr_label.clone(),
+            result_l: calc_ui.result_label.clone(),
+            preview: calc_ui.preview_label.clone(),
+            exact_btn: calc_ui.exact_btn.clone(),
+            keys,
+        }
+    }
+
+    // Only what changed is touched: every set_text relayouts the label,
+    // which adds up during fast typing and key repeat.
+    fn render(&self, update: &DisplayUpdate) {
+        if self.result_l.text() != update.main {
+            let ctx = self.result_l.style_context();
+            ctx.remove_class("result-medium");
+            ctx.remove_class("result-small");
+            if let Some(class) = update.size.css_class() {
+                ctx.add_class(class);
+            }
+            self.result_l.set_text(&update.main);
+            // Long whole numbers break over lines instead of losing digits.
+            let wrap = update.size == ResultSize::Wrapped;
+            self.result_l.set_line_wrap(wrap);
+            self.result_l.set_ellipsize(if wrap { gtk::pango::EllipsizeMode::None } else { gtk::pango::EllipsizeMode::Start });
+            // Screen readers pick up the new value through the name change.
+            ui::a11y::describe(&self.result_l, &ui::a11y::spoken_result(&update.main), "Result");
+        }
+        set_optional_label(&self.expr, update.secondary.as_deref());
+        set_optional_label(&self.preview, update.preview.as_deref());
+        self.exact_btn.set_visible(update.exact.is_some());
+        for effect in &update.effects {
+            if let SideEffect::Speak(text) = effect {
+                services::speech::say(text);
+            }
+        }
+        if let Some(abtn) = self.keys.borrow().angle_btn.as_ref().filter(|b| b.label().as_deref() != Some(update.angle)) {
+            abtn.set_label(update.angle);
+            let mode = if update.angle == "Deg" { "degrees" } else { "radians" };
+            ui::a11y::describe(abtn, &format!("angle mode, {}", mode), ui::a11y::key_description("toggle-button"));
+        }
+    }
+
+    fn refresh(&self, state: &AppState) {
+        self.render(&DisplayUpdate::from_state(state));
+    }
+}
+
+fn set_optional_label(label: &gtk::Label, text: Option<&str>) {
+    let (text, opacity) = match text {
+        Some(t) => (t, 1.0),
+        None => (" ", 0.0),
+    };
+    if label.text() != text {
+        label.set_text(text);
+    }
+    if label.opacity() != opacity {
+        label.set_opacity(opacity);
+    }
+}
+
+// ── Message dispatch ─────────────────────────────────────────────────────────
+
+/// The state and every widget a message's side effects can touch. Built
+/// once in `run`; each handler keeps a clone and sends its message through
+/// `dispatch`.
+#[derive(Clone)]
+struct Gui {
+    state: Rc<RefCell<AppState>>,
+    theme_mgr: Rc<RefCell<ThemeManager>>,
+    keys: Rc<RefCell<Keys>>,
+    display: DisplayWidgets,
+    tab_bar: gtk::Box,
+    sci_grid: gtk::Grid,
+    main_grid: gtk::Grid,
+    window: gtk::Window,
+    menu_basic_btn: gtk::Button,
+    menu_sci_btn: gtk::Button,
+    panel_revealer: gtk::Revealer,
+    panel_stack: gtk::Stack,
+    mode_panel_revealer: gtk::Revealer,
+    mode_panel_stack: gtk::Stack,
+    menu_popover: gtk::Popover,
+    history_list: gtk::Box,
+    memory_list: gtk::Box,
+    pinned_list: gtk::Box,
+    worksheet_list: gtk::Box,
+    display_event_box: gtk::EventBox,
+    p_history_btn: gtk::Button,
+    p_memory_btn: gtk::Button,
+    p_pinned_btn: gtk::Button,
+    // Window position and size from before a summon, while the popup is up.
+    summoned: Rc<std::cell::Cell<Option<SavedGeometry>>>,
+}
+
+impl Gui {
+    fn new(
+        state: Rc<RefCell<AppState>>,
+        calc_ui: &CalculatorUI,
+        theme_mgr: Rc<RefCell<ThemeManager>>,
+        keys: Keys,
+    ) -> Self {
+        let keys = Rc::new(RefCell::new(keys));
+        Self {
+            state,
+            theme_mgr,
+            display: DisplayWidgets::new(calc_ui, keys.clone()),
+            keys,
+            tab_bar: calc_ui.tab_bar.clone(),
+            sci_grid: calc_ui.sci_grid.clone(),
+            main_grid: calc_ui.main_grid.clone(),
+            window: calc_ui.window.clone(),
+            menu_basic_btn: calc_ui.menu_basic_btn.clone(),
+            menu_sci_btn: calc_ui.menu_sci_btn.clone(),
+            panel_revealer: calc_ui.panel_revealer.clone(),
+            panel_stack: calc_ui.panel_stack.clone(),
+            mode_panel_revealer: calc_ui.mode_panel_revealer.clone(),
+            mode_panel_stack: calc_ui.mode_panel_stack.clone(),
+            menu_popover: calc_ui.menu_popover.clone(),
+            history_list: calc_ui.history_list.clone(),
+            memory_list: calc_ui.memory_list.clone(),
+            pinned_list: calc_ui.pinned_list.clone(),
+            worksheet_list: calc_ui.worksheet_list.clone(),
+            display_event_box: calc_ui.display_event_box.clone(),
+            p_history_btn: calc_ui.panel_history_btn.clone(),
+            p_memory_btn: calc_ui.panel_memory_btn.clone(),
+            p_pinned_btn: calc_ui.panel_pinned_btn.clone(),
+            summoned: Rc::new(std::cell::Cell::new(None)),
+        }
+    }
+}
+
+/// Runs a message through `display::reduce`, renders the result and
+/// carries out every side effect. The effects are handed back for the few
+/// callers that also change the widget they came from.
+fn dispatch(gui: &Gui, msg: Message) -> Vec<SideEffect> {
+    let update = display::reduce(&mut gui.state.borrow_mut(), msg);
+    gui.display.render(&update);
+
+    for eff in &update.effects {
+        match eff {
+            SideEffect::UpdateTabs => rebuild_tab_buttons(gui),
+            SideEffect::ToggleScientific(mode) => {
+                if *mode {
+                    gui.sci_grid.show_all();
+                    gui.menu_sci_btn.style_context().add_class("active");
+                    gui.menu_basic_btn.style_context().remove_class("active");
+                } else {
+                    gui.sci_grid.hide();
+                    gui.menu_basic_btn.style_context().add_class("active");
+                    gui.menu_sci_btn.style_context().remove_class("active");
+                }
+            }
+            SideEffect::ResizeWindow => {
+                let s = gui.state.borrow();
+                if s.scientific_mode {
+                    gui.window.resize(580, s.config.window.default_height);
+                } else {
+                    gui.window.resize(s.config.window.default_width, s.config.window.default_height);
+                }
+            }
+            SideEffect::ToggleTheme => {
+                let s = gui.state.borrow();
+                gui.theme_mgr.borrow_mut().toggle(&s.config.theme, &s.config.layout, &s.config.feedback);
+            }
+            SideEffect::TogglePanel => {
+                let s = gui.state.borrow();
+                gui.panel_revealer.set_reveal_child(s.panel_visible);
+                if s.panel_visible {
+                    gui.p_history_btn.style_context().remove_class("active");
+                    gui.p_memory_btn.style_context().remove_class("active");
+                    gui.p_pinned_btn.style_context().remove_class("active");
+                    match s.active_panel {
+                        Panel::History => {
+                            gui.panel_stack.set_visible_child_name("history");
+                            gui.p_history_btn.style_context().add_class("active");
+                        }
+                        Panel::Memory => {
+                            gui.panel_stack.set_visible_child_name("memory");
+                            gui.p_memory_btn.style_context().add_class("active");
+                        }
+                        Panel::Pinned => {
+                            gui.panel_stack.set_visible_child_name("pinned");
+                            gui.p_pinned_btn.style_context().add_class("active");
+                        }
+                    }
+                }
+            }
+            SideEffect::ToggleModePanel => {
+                let s = gui.state.borrow();
+                gui.mode_panel_revealer.set_reveal_child(s.mode_panel_visible);
+                if s.mode_panel_visible {
+                    if let Some(mode) = s.active_mode {
+                        let name = match mode {
+                            ModePanel::Converter => "converter",
+                            ModePanel::Tools => "tools",
+                            ModePanel::Notes => "notes",
+                            ModePanel::Worksheet => "worksheet",
+                        };
+                        gui.mode_panel_stack.set_visible_child_name(name);
+                    }
+                }
+            }
+            SideEffect::RefreshHistory => refresh_history(gui),
+            SideEffect::RefreshWorksheet => rebuild_worksheet(gui),
+            SideEffect::RefreshMemory => {
+                let s = gui.state.borrow();
+                refresh_memory(&s.engine().memory_slots, s.engine().has_memory(), &gui.memory_list);
+            }
+            SideEffect::RefreshPinned => {
+                let s = gui.state.borrow();
+                refresh_pinned(&s.engine().pinned, &gui.pinned_list);
+            }
+            SideEffect::ExportedFile(path) => {
+                eprintln!("Exported: {}", path.display());
+            }
+            SideEffect::CopyToClipboard(text) => {
+                gtk::Clipboard::get(&gtk::gdk::SELECTION_CLIPBOARD).set_text(text);
+            }
+            SideEffect::PasteFromClipboard => {
+                let gui = gui.clone();
+                gtk::Clipboard::get(&gtk::gdk::SELECTION_CLIPBOARD).request_text(move |_, text| {
+                    if let Some(text) = text {
+                        dispatch(&gui, Message::Paste(text.to_string()));
+                    }
+                });
+            }
+            SideEffect::ShowSteps(steps) => {
+                show_steps_popover(&gui.display_event_box, steps);
+            }
+            SideEffect::OpenPalette => {
+                show_command_palette(gui);
+            }
+            SideEffect::ShowHelp => {
+                show_help_dialog(&gui.window);
+            }
+            SideEffect::Navigate(dir) => {
+                let (mode_open, sci) = {
+                    let s = gui.state.borrow();
+                    (s.mode_panel_visible, s.scientific_mode)
+                };
+                if !mode_open {
+                    ui::navigation::navigate(&gui.keys.borrow().nav_buttons, *dir, sci);
+                }
+            }
+            SideEffect::ActivateButton => {
+                let (mode_open, sci) = {
+                    let s = gui.state.borrow();
+                    (s.mode_panel_visible, s.scientific_mode)
+                };
+                if !mode_open {
+                    ui::navigation::activate_focused(&gui.keys.borrow().nav_buttons, sci);
+                }
+            }
+            SideEffect::OpenMenu => {
+                gui.menu_popover.popup();
+            }
+            SideEffect::Quit => quit(gui),
+            SideEffect::ApplyZoom => apply_scale(gui),
+            SideEffect::UpdateDisplay | SideEffect::Speak(_) | SideEffect::Noop => {}
+        }
+    }
+    update.effects
+}
+
+// ── Tab bar helpers ──────────────────────────────────────────────────────────
+
+/// Brings the tab buttons in line with the tabs. Buttons are only recreated
+/// when tabs come or go, so switching or renaming keeps the one clicked.
+fn rebuild_tab_buttons(gui: &Gui) {
+    let (names, active): (Vec<String>, usize) = {
+        let s = gui.state.borrow();
+        (s.tabs.iter().map(|t| t.name.clone()).collect(), s.active_tab)
+    };
+    let buttons: Vec<gtk::Button> = gui
+        .tab_bar
+        .children()
+        .into_iter()
+        .filter(|c| c.style_context().has_class("tab-button"))
+        .filter_map(|c| c.downcast().ok())
+        .collect();
+    if buttons.len() == names.len() {
+        for (i, (btn, name)) in buttons.iter().zip(&names).enumerate() {
+            btn.set_label(name);
+            if i == active {
+                btn.style_context().add_class("active");
+            } else {
+                btn.style_context().remove_class("active");
+            }
+        }
+        return;
+    }
+    for btn in buttons {
+        gui.tab_bar.remove(&btn);
+    }
+    for (i, name) in names.iter().enumerate() {
+        let btn = gtk::Button::with_label(name);
+        btn.style_context().add_class("tab-button");
+        if i == active {
+            btn.style_context().add_class("active");
+        }
+        btn.set_can_focus(false);
+        gui.tab_bar.pack_start(&btn, false, false, 0);
+        gui.tab_bar.reorder_child(&btn, i as i32);
+        btn.show();
+        wire_tab_button(&btn, i, gui);
+    }
+}
+
+fn wire_tab_button(btn: &gtk::Button, idx: usize, gui: &Gui) {
+    // Left-click: switch to tab
+    {
+        let gui = gui.clone();
+        btn.connect_clicked(move |_| {
+            dispatch(&gui, Message::SwitchTab(idx));
+        });
+    }
+
+    // Button-press: double-click rename, right-click context menu
+    let gui = gui.clone();
+    btn.connect_button_press_event(move |clicked_btn, event| {
+        match (event.button(), event.event_type()) {
+            (1, gtk::gdk::EventType::DoubleButtonPress) => {
+                show_rename_popover(clicked_btn, idx, &gui);
+                gtk::Inhibit(true)
+            }
+            (3, gtk::gdk::EventType::ButtonPress) => {
+                let can_delete = gui.state.borrow().tabs.len() > 1;
+                let menu = gtk::Menu::new();
+
+                let rename_item = gtk::MenuItem::with_label("Rename Tab");
+                {
+                    let gui = gui.clone();
+                    let btn_r = clicked_btn.clone();
+                    rename_item.connect_activate(move |_| {
+                        show_rename_popover(&btn_r, idx, &gui);
+                    });
+                }
+
+                let delete_item = gtk::MenuItem::with_label("Delete Tab");
+                delete_item.set_sensitive(can_delete);
+                {
+                    let gui = gui.clone();
+                    delete_item.connect_activate(move |_| {
+                        dispatch(&gui, Message::CloseTabAt(idx));
+                    });
+                }
+
+                menu.append(&rename_item);
+                menu.append(&delete_item);
+                menu.show_all();
+                menu.popup_at_pointer(None::<&gtk::gdk::Event>);
+                gtk::Inhibit(true)
+            }
+            _ => gtk::Inhibit(false),
+        }
+    });
+}
+
+fn show_rename_popover(btn: &gtk::Button, idx: usize, gui: &Gui) {
+    let current_name = {
+        let st = gui.state.borrow();
+        st.tabs.get(idx).map(|t| t.name.clone()).unwrap_or_default()
+    };
+    let popover = gtk::Popover::new(Some(btn));
+    let entry = gtk::Entry::new();
+    entry.set_text(&current_name);
+    entry.set_margin_top(4);
+    entry.set_margin_bottom(4);
+    entry.set_margin_start(4);
+    entry.set_margin_end(4);
+    popover.add(&entry);
+    entry.show();
+    popover.popup();
+    entry.grab_focus();
+
+    let gui = gui.clone();
+    let popover_c = popover.clone();
+    entry.connect_activate(move |e| {
+        popover_c.popdown();
+        let new_name = e.text().to_string();
+        if !new_name.is_empty() {
+            dispatch(&gui, Message::RenameTab(idx, new_name));
+        }
+    });
+}
+
+// ── Signal wiring ────────────────────────────────────────────────────────────
+
+fn wire_keypad(gui: &Gui) {
+    for (button, action) in &gui.keys.borrow().action_buttons {
+        let gui = gui.clone();
+        let action = *action;
+        button.connect_clicked(move |_| {
+            if gui.state.borrow().config.feedback.click_sound {
+                services::sound::click();
+            }
+            dispatch(&gui, action.message());
+        });
+    }
+    wire_percent_menu(gui);
+}
+
+/// Swaps the keypad buttons for a new layout straight away.
+fn rebuild_keypad(gui: &Gui, keypad: &Keypad) {
+    *gui.keys.borrow_mut() = ui::builder::fill_keypads(&gui.sci_grid, &gui.main_grid, keypad);
+    wire_keypad(gui);
+    // Showing the buttons rather than the grids keeps the scientific keypad
+    // hidden in basic mode.
+    for grid in [&gui.sci_grid, &gui.main_grid] {
+        for child in grid.children() {
+            child.show_all();
+        }
+    }
+    gui.display.refresh(&gui.state.borrow());
+}
+
+fn wire_panel_buttons(gui: &Gui, calc_ui: &CalculatorUI) {
+    {
+        let stack = calc_ui.panel_stack.clone();
+        let h_btn = calc_ui.panel_history_btn.clone();
+        let m_btn = calc_ui.panel_memory_btn.clone();
+        let p_btn = calc_ui.panel_pinned_btn.clone();
+
+        let switch_panel = move |name: &str| {
+            stack.set_visible_child_name(name);
+            h_btn.style_context().remove_class("active");
+            m_btn.style_context().remove_class("active");
+            p_btn.style_context().remove_class("active");
+            match name {
+                "history" => h_btn.style_context().add_class("active"),
+                "memory" => m_btn.style_context().add_class("active"),
+                "pinned" => p_btn.style_context().add_class("active"),
+                _ => {}
+            }
+        };
+
+        let sw = switch_panel.clone();
+        calc_ui.panel_history_btn.connect_clicked(move |_| sw("history"));
+        let sw = switch_panel.clone();
+        calc_ui.panel_memory_btn.connect_clicked(move |_| sw("memory"));
+        let sw = switch_panel;
+        calc_ui.panel_pinned_btn.connect_clicked(move |_| sw("pinned"));
+    }
+
+    {
+        let gui = gui.clone();
+        calc_ui.history_clear_btn.connect_clicked(move |_| {
+            dispatch(&gui, Message::ClearHistory);
+        });
+    }
+
+    for (btn, msg, label) in [
+        (&calc_ui.history_export_json_btn, Message::ExportHistoryJson, "JSON"),
+        (&calc_ui.history_export_csv_btn, Message::ExportHistoryCsv, "CSV"),
+    ] {
+        let gui = gui.clone();
+        btn.connect_clicked(move |btn| {
+            let effects = dispatch(&gui, msg.clone());
+            if effects.iter().any(|eff| matches!(eff, SideEffect::ExportedFile(_))) {
+                btn.set_label("Saved!");
+                let btn_c = btn.clone();
+                gtk::glib::timeout_add_local(std::time::Duration::from_secs(2), move || {
+                    btn_c.set_label(label);
+                    gtk::glib::Continue(false)
+                });
+            }
+        });
+    }
+
+    {
+        let gui = gui.clone();
+        calc_ui.history_search_entry.connect_changed(move |entry| {
+            dispatch(&gui, Message::SearchHistory(entry.text().to_string()));
+        });
+    }
+}
+
+fn wire_menu_buttons(gui: &Gui, calc_ui: &CalculatorUI) {
+    for (btn, scientific) in [(&calc_ui.menu_basic_btn, false), (&calc_ui.menu_sci_btn, true)] {
+        let gui = gui.clone();
+        btn.connect_clicked(move |_| {
+            gui.menu_popover.popdown();
+            let is_sci = gui.state.borrow().scientific_mode;
+            if is_sci != scientific {
+                dispatch(&gui, Message::ToggleScientific);
+            }
+        });
+    }
+
+    {
+        let gui = gui.clone();
+        calc_ui.menu_help_btn.connect_clicked(move |_| {
+            gui.menu_popover.popdown();
+            show_help_dialog(&gui.window);
+        });
+    }
+
+    {
+        let gui = gui.clone();
+        calc_ui.menu_about_btn.connect_clicked(move |_| {
+            gui.menu_popover.popdown();
+            show_about_dialog(&gui.window);
+        });
+    }
+
+    for (btn, msg) in [
+        (&calc_ui.menu_notes_btn, Message::OpenNotes),
+        (&calc_ui.menu_converter_btn, Message::OpenConverter),
+        (&calc_ui.menu_tools_btn, Message::OpenTools),
+        (&calc_ui.menu_steps_btn, Message::ShowSteps),
+        (&calc_ui.menu_copy_latex_btn, Message::CopyAs(CopyStyle::Latex)),
+        (&calc_ui.menu_copy_plain_btn, Message::CopyAs(CopyStyle::Plain)),
+    ] {
+        let gui = gui.clone();
+        btn.connect_clicked(move |_| {
+            gui.menu_popover.popdown();
+            dispatch(&gui, msg.clone());
+        });
+    }
+
+    {
+        let gui = gui.clone();
+        calc_ui.menu_worksheet_btn.connect_clicked(move |_| {
+            gui.menu_popover.popdown();
+            dispatch(&gui, Message::OpenWorksheet);
+            if gui.state.borrow().mode_panel_visible {
+                if let Some(entry) = worksheet_entry(&gui.worksheet_list, 0) {
+                    entry.grab_focus();
+                }
+            }
+        });
+    }
+
+    {
+        let gui = gui.clone();
+        calc_ui.menu_export_btn.connect_clicked(move |_| {
+            gui.menu_popover.popdown();
+            show_export_dialog(&gui);
+        });
+    }
+
+    {
+        let gui = gui.clone();
+        calc_ui.menu_keypad_btn.connect_clicked(move |_| {
+            gui.menu_popover.popdown();
+            show_keypad_editor(&gui);
+        });
+    }
+
+    {
+        let gui = gui.clone();
+        calc_ui.menu_snippets_btn.connect_clicked(move |_| {
+            gui.menu_popover.popdown();
+            show_snippets_popover(&gui);
+        });
+    }
+
+    for (btn, idx) in &calc_ui.menu_theme_btns {
+        let gui = gui.clone();
+        let theme_val = Theme::ALL[*idx];
+        let all_btns: Vec<(gtk::Button, usize)> = calc_ui.menu_theme_btns.clone();
+        let current_idx = *idx;
+        btn.connect_clicked(move |_| {
+            gui.menu_popover.popdown();
+            let s = gui.state.borrow();
+            gui.theme_mgr.borrow_mut().set_theme(
+                theme_val,
+                &s.config.theme,
+                &s.config.layout,
+                &s.config.feedback,
+            );
+            for (b, i) in &all_btns {
+                if *i == current_idx {
+                    b.style_context().add_class("menu-item-active");
+                } else {
+                    b.style_context().remove_class("menu-item-active");
+                }
+            }
+        });
+    }
+
+    for btn in [
+        &calc_ui.conv_back_btn,
+        &calc_ui.tools_back_btn,
+        &calc_ui.notes_back_btn,
+        &calc_ui.worksheet_back_btn,
+    ] {
+        let gui = gui.clone();
+        btn.connect_clicked(move |_| {
+            dispatch(&gui, Message::CloseMode);
+        });
+    }
+}
+
+fn wire_converter(calc_ui: &CalculatorUI) {
+    let conv_category = Rc::new(std::cell::Cell::new(0usize));
+
+    {
+        let entry = calc_ui.conv_value_entry.clone();
+        let from_combo = calc_ui.conv_from_combo.clone();
+        let to_combo = calc_ui.conv_to_combo.clone();
+        let result_lbl = calc_ui.conv_result_label.clone();
+        let cat = conv_category.clone();
+
+        let do_convert = move || {
+            let val: f64 = entry.text().parse().unwrap_or(0.0);
+            let category = ConvertCategory::ALL[cat.get()];
+            let from = from_combo.active_text().map(|s| s.to_string()).unwrap_or_default();
+            let to = to_combo.active_text().map(|s| s.to_string()).unwrap_or_default();
+            if !from.is_empty() && !to.is_empty() {
+                let result = domain::convert::convert(category, &from, &to, val);
+                result_lbl.set_text(&domain::types::format_number_default(result));
+            }
+        };
+
+        let dc = do_convert.clone();
+        calc_ui.conv_value_entry.connect_changed(move |_| dc());
+        let dc = do_convert.clone();
+        calc_ui.conv_from_combo.connect_changed(move |_| dc());
+        let dc = do_convert;
+        calc_ui.conv_to_combo.connect_changed(move |_| dc());
+    }
+
+    for (i, btn) in calc_ui.conv_cat_btns.iter().enumerate() {
+        let cat = conv_category.clone();
+        let from_combo = calc_ui.conv_from_combo.clone();
+        let to_combo = calc_ui.conv_to_combo.clone();
+        let result_lbl = calc_ui.conv_result_label.clone();
+        let entry = calc_ui.conv_value_entry.clone();
+        let all_btns: Vec<gtk::Button> = calc_ui.conv_cat_btns.clone();
+
+        btn.connect_clicked(move |_| {
+            cat.set(i);
+            for b in &all_btns {
+                b.style_context().remove_class("active");
+            }
+            all_btns[i].style_context().add_class("active");
+
+            from_combo.remove_all();
+            to_combo.remove_all();
+            let category = ConvertCategory::ALL[i];
+            for (abbr, _name) in category.units() {
+                from_combo.append_text(abbr);
+                to_combo.append_text(abbr);
+            }
+            from_combo.set_active(Some(0));
+            to_combo.set_active(Some(1));
+
+            let val: f64 = entry.text().parse().unwrap_or(1.0);
+            let units = category.units();
+            if units.len() >= 2 {
+                let result = domain::convert::convert(category, units[0].0, units[1].0, val);
+                result_lbl.set_text(&domain::types::format_number_default(result));
+            }
+        });
+    }
+
+    {
+        let from = calc_ui.conv_from_combo.clone();
+        let to = calc_ui.conv_to_combo.clone();
+        calc_ui.conv_swap_btn.connect_clicked(move |_| {
+            let f = from.active();
+            let t = to.active();
+            from.set_active(t);
+            to.set_active(f);
+        });
+    }
+}
+
+fn wire_tools(gui: &Gui, calc_ui: &CalculatorUI) {
+    {
+        let amount_entry = calc_ui.tip_amount_entry.clone();
+        let result_lbl = calc_ui.tip_result_label.clone();
+
+        let calc_tip = move |pct: f64| {
+            let amount: f64 = amount_entry.text().parse().unwrap_or(0.0);
+            let tip = amount * pct / 100.0;
+            result_lbl.set_text(&format!("Tip: {:.2}  |  Total: {:.2}", tip, amount + tip));
+        };
+
+        for (btn, pct) in &calc_ui.tip_pct_btns {
+            let ct = calc_tip.clone();
+            let pct = *pct;
+            btn.connect_clicked(move |_| ct(pct));
+        }
+
+        let ct = calc_tip;
+        calc_ui.tip_custom_entry.connect_changed(move |entry| {
+            let pct: f64 = entry.text().parse().unwrap_or(0.0);
+            ct(pct);
+        });
+    }
+
+    {
+        let price_entry = calc_ui.discount_price_entry.clone();
+        let pct_entry = calc_ui.discount_pct_entry.clone();
+        let result_lbl = calc_ui.discount_result_label.clone();
+
+        let calc_disc = move || {
+            let price: f64 = price_entry.text().parse().unwrap_or(0.0);
+            let pct: f64 = pct_entry.text().parse().unwrap_or(0.0);
+            let savings = price * pct / 100.0;
+            result_lbl.set_text(&format!(
+                "Save: {:.2}  |  Final: {:.2}",
+                savings,
+                price - savings
+            ));
+        };
+
+        let cd = calc_disc.clone();
+        calc_ui.discount_price_entry.connect_changed(move |_| cd());
+        let cd = calc_disc;
+        calc_ui.discount_pct_entry.connect_changed(move |_| cd());
+    }
+
+    {
+        let amount_entry = calc_ui.tax_amount_entry.clone();
+        let rate_entry = calc_ui.tax_rate_entry.clone();
+        let result_lbl = calc_ui.tax_result_label.clone();
+
+        let calc_tax = move || {
+            let amount: f64 = amount_entry.text().parse().unwrap_or(0.0);
+            let rate: f64 = rate_entry.text().parse().unwrap_or(0.0);
+            let tax = amount * rate / 100.0;
+            result_lbl.set_text(&format!("Tax: {:.2}  |  Total: {:.2}", tax, amount + tax));
+        };
+
+        let ct = calc_tax.clone();
+        calc_ui.tax_amount_entry.connect_changed(move |_| ct());
+        let ct = calc_tax;
+        calc_ui.tax_rate_entry.connect_changed(move |_| ct());
+    }
+
+    wire_time_tracking(gui, calc_ui);
+    wire_proportion(gui, calc_ui);
+}
+
+fn wire_time_tracking(gui: &Gui, calc_ui: &CalculatorUI) {
+    use domain::duration::{self, Stopwatch};
+    use std::time::Instant;
+
+    let watch = Rc::new(RefCell::new(Stopwatch::default()));
+    let tick: Rc<RefCell<Option<gtk::glib::SourceId>>> = Rc::default();
+    let show = {
+        let watch = watch.clone();
+        let label = calc_ui.time_watch_label.clone();
+        move || {
+            let secs = watch.borrow().elapsed(Instant::now()).as_secs_f64();
+            label.set_text(&duration::format(secs));
+        }
+    };
+
+    {
+        let watch = watch.clone();
+        let tick = tick.clone();
+        let show = show.clone();
+        calc_ui.time_start_btn.connect_clicked(move |btn| {
+            let running = {
+                let mut w = watch.borrow_mut();
+                w.toggle(Instant::now());
+                w.running()
+            };
+            btn.set_label(if running { "Pause" } else { "Start" });
+            if running {
+                let show = show.clone();
+                let id = gtk::glib::timeout_add_local(std::time::Duration::from_secs(1), move || {
+                    show();
+                    gtk::glib::Continue(true)
+                });
+                *tick.borrow_mut() = Some(id);
+            } else if let Some(id) = tick.borrow_mut().take() {
+                id.remove();
+            }
+            show();
+        });
+    }
+    {
+        let watch = watch.clone();
+        let start_btn = calc_ui.time_start_btn.clone();
+        let show = show.clone();
+        calc_ui.time_reset_btn.connect_clicked(move |_| {
+            if let Some(id) = tick.borrow_mut().take() {
+                id.remove();
+            }
+            watch.borrow_mut().reset();
+            start_btn.set_label("Start");
+            show();
+        });
+    }
+    {
+        let entry = calc_ui.time_entry.clone();
+        calc_ui.time_log_btn.connect_clicked(move |_| {
+            let secs = watch.borrow().elapsed(Instant::now()).as_secs_f64();
+            if secs < 1.0 {
+                return;
+            }
+            let logged = duration::format(secs);
+            let text = entry.text();
+            if text.trim().is_empty() {
+                entry.set_text(&logged);
+            } else {
+                entry.set_text(&format!("{} + {}", text.trim_end(), logged));
+            }
+        });
+    }
+
+    let billable = {
+        let entry = calc_ui.time_entry.clone();
+        let rate_entry = calc_ui.time_rate_entry.clone();
+        move || -> Result<(f64, f64), String> {
+            let text = entry.text();
+            let secs = if text.trim().is_empty() { 0.0 } else { duration::parse(&text)? };
+            let rate: f64 = rate_entry.text().parse().unwrap_or(0.0);
+            Ok((secs, secs / 3600.0 * rate))
+        }
+    };
+    {
+        let result_lbl = calc_ui.time_result_label.clone();
+        let billable = billable.clone();
+        let calc_time = move || match billable() {
+            Ok((secs, amount)) => result_lbl.set_text(&format!(
+                "Time: {}  |  Billable: {:.2}",
+                duration::format(secs),
+                amount
+            )),
+            Err(e) => result_lbl.set_text(&e),
+        };
+        let ct = calc_time.clone();
+        calc_ui.time_entry.connect_changed(move |_| ct());
+        let ct = calc_time;
+        calc_ui.time_rate_entry.connect_changed(move |_| ct());
+    }
+    {
+        let gui = gui.clone();
+        calc_ui.time_use_btn.connect_clicked(move |_| {
+            let Ok((_, amount)) = billable() else { return };
+            dispatch(&gui, Message::LoadExpression(format!("{:.2}", amount)));
+            dispatch(&gui, Message::CloseMode);
+        });
+    }
+}
+
+fn wire_proportion(gui: &Gui, calc_ui: &CalculatorUI) {
+    // Each field takes an expression (`250 mg` is not one, `2*125` is);
+    // returns the blank field's answer as text the display can load.
+    let solve = {
+        let gui = gui.clone();
+        let entries = calc_ui.ratio_entries.clone();
+        move || -> Result<(usize, String), String> {
+            let (plugins, angle_mode, standard) = {
+                let s = gui.state.borrow();
+                let settings = s.eval_settings();
+                (s.config.plugins.functions.clone(), settings.angle_mode, settings.standard_precedence)
+            };
+            let mut terms = [None; 4];
+            for (term, entry) in terms.iter_mut().zip(&entries) {
+                let text = entry.text();
+                if !text.trim().is_empty() {
+                    *term = Some(domain::eval::evaluate_str(&text, &plugins, angle_mode, standard)?);
+                }
+            }
+            let (blank, val) = domain::proportion::solve(terms)?;
+            Ok((blank, domain::types::format_number_default(val)))
+        }
+    };
+    {
+        let result_lbl = calc_ui.ratio_result_label.clone();
+        let solve = solve.clone();
+        let calc_ratio = move || match solve() {
+            Ok((blank, answer)) => {
+                let names = ["a", "b", "c", "x"];
+                result_lbl.set_text(&format!("{} = {}", names[blank], answer));
+            }
+            Err(e) => result_lbl.set_text(&e),
+        };
+        for entry in &calc_ui.ratio_entries {
+            let cr = calc_ratio.clone();
+            entry.connect_changed(move |_| cr());
+        }
+    }
+    {
+        let gui = gui.clone();
+        calc_ui.ratio_use_btn.connect_clicked(move |_| {
+            let Ok((_, answer)) = solve() else { return };
+            dispatch(&gui, Message::LoadExpression(answer));
+            dispatch(&gui, Message::CloseMode);
+        });
+    }
+}
+
+fn wire_notes(gui: &Gui, calc_ui: &CalculatorUI) {
+    let result_lbl = calc_ui.notes_result_label.clone();
+    let textview = calc_ui.notes_textview.clone();
+    let gui = gui.clone();
+
+    if let Some(buf) = textview.buffer() {
+        buf.connect_changed(move |buf| {
+            let text = buf
+                .text(&buf.start_iter(), &buf.end_iter(), false)
+                .map(|s| s.to_string())
+                .unwrap_or_default();
+
+            let (plugins, answers) = {
+                let s = gui.state.borrow();
+                (s.config.plugins.functions.clone(), s.engine().answers())
+            };
+
+            let mut results = Vec::new();
+            for line in text.lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+                    results.push(String::new());
+                    continue;
+                }
+                match domain::eval::parse_expression_with(line, &plugins, &answers) {
+                    Ok(tokens) if !tokens.is_empty() => {
+                        match domain::eval::evaluate(&tokens, AngleMode::Degrees, true) {
+                            Ok(val) => results.push(format!(
+                                "= {}",
+                                domain::types::format_number_default(val)
+                            )),
+                            Err(e) => results.push(format!("  {}", e)),
+                        }
+                    }
+                    _ => results.push(String::new()),
+                }
+            }
+            result_lbl.set_text(&results.join("\n"));
+        });
+    }
+}
+
+// ── Worksheet ──
+
+fn worksheet_row_child(list: &gtk::Box, index: usize, child: usize) -> Option<gtk::Widget> {
+    let row = list.children().into_iter().nth(index)?.downcast::<gtk::Box>().ok()?;
+    row.children().into_iter().nth(child)
+}
+
+fn worksheet_entry(list: &gtk::Box, index: usize) -> Option<gtk::Entry> {
+    worksheet_row_child(list, index, 1)?.downcast().ok()
+}
+
+/// Puts the cursor at the end of a worksheet line.
+fn focus_worksheet_line(list: &gtk::Box, index: usize) {
+    if let Some(entry) = worksheet_entry(list, index) {
+        entry.grab_focus();
+        entry.set_position(-1);
+    }
+}
+
+/// Recreates one row per worksheet line. Only needed when lines are added or
+/// removed; edits just refresh the results.
+fn rebuild_worksheet(gui: &Gui) {
+    let list = &gui.worksheet_list;
+    for child in list.children() {
+        list.remove(&child);
+    }
+    let lines = gui.state.borrow().worksheet.clone();
+    let count = lines.len();
+    for (index, text) in lines.iter().enumerate() {
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        row.style_context().add_class("worksheet-line");
+
+        let number = gtk::Label::new(Some(&(index + 1).to_string()));
+        number.style_context().add_class("worksheet-line-number");
+        number.set_xalign(1.0);
+
+        let entry = gtk::Entry::new();
+        entry.set_text(text);
+        entry.set_hexpand(true);
+        ui::a11y::describe(&entry, &format!("Line {}", index + 1), "Worksheet expression");
+
+        {
+            let gui = gui.clone();
+            attach_completion(&entry, move || gui.state.borrow().worksheet_symbols(index));
+        }
+
+        let result = gtk::Label::new(None);
+        result.style_context().add_class("worksheet-result");
+        result.set_xalign(1.0);
+        result.set_selectable(true);
+        result.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        result.set_max_width_chars(18);
+
+        {
+            let gui = gui.clone();
+            entry.connect_changed(move |e| {
+                dispatch(&gui, Message::SetWorksheetLine(index, e.text().to_string()));
+                show_worksheet_results(&gui);
+            });
+        }
+        {
+            let gui = gui.clone();
+            entry.connect_activate(move |_| {
+                dispatch(&gui, Message::InsertWorksheetLine(index + 1));
+                focus_worksheet_line(&gui.worksheet_list, index + 1);
+            });
+        }
+        {
+            let gui = gui.clone();
+            entry.connect_key_press_event(move |e, event| {
+                use gtk::gdk::keys::constants as key;
+                let keyval = event.keyval();
+                let target = if keyval == key::Up {
+                    index.checked_sub(1)
+                } else if keyval == key::Down {
+                    Some(index + 1).filter(|&i| i < count)
+                } else if keyval == key::BackSpace && e.text().is_empty() && count > 1 {
+                    dispatch(&gui, Message::RemoveWorksheetLine(index));
+                    focus_worksheet_line(&gui.worksheet_list, index.saturating_sub(1));
+                    return gtk::Inhibit(true);
+                } else {
+                    return gtk::Inhibit(false);
+                };
+                if let Some(entry) = target.and_then(|i| worksheet_entry(&gui.worksheet_list, i)) {
+                    entry.grab_focus();
+                }
+                gtk::Inhibit(true)
+            });
+        }
+
+        row.pack_start(&number, false, false, 0);
+        row.pack_start(&entry, true, true, 0);
+        row.pack_start(&result, false, false, 0);
+        list.pack_start(&row, false, false, 0);
+    }
+    show_worksheet_results(gui);
+    list.show_all();
+}
+
+/// Colours each token of an expression and puts a squiggly underline under
+/// `error`. Pango indexes by byte, as the spans do.
+fn expression_attributes(text: &str, error: Option<std::ops::Range<usize>>) -> gtk::pango::AttrList {
+    use domain::highlight::Kind;
+    use gtk::pango::{AttrColor, AttrInt, AttrList, Attribute, Style, Underline};
+
+    fn place(mut attr: Attribute, range: &std::ops::Range<usize>) -> Attribute {
+        attr.set_start_index(range.start as u32);
+        attr.set_end_index(range.end as u32);
+        attr
+    }
+
+    let attrs = AttrList::new();
+    for span in domain::highlight::lex(text) {
+        let color = match span.kind {
+            Kind::Number => Some((0x35, 0x84, 0xe4)),
+            Kind::Operator => Some((0xe6, 0x61, 0x00)),
+            Kind::Function => Some((0x91, 0x41, 0xac)),
+            Kind::Constant | Kind::Reference => Some((0x26, 0xa2, 0x69)),
+            Kind::Paren | Kind::Name | Kind::Comment | Kind::Unknown => None,
+        };
+        if let Some((r, g, b)) = color {
+            attrs.insert(place(AttrColor::new_foreground(r * 257, g * 257, b * 257).into(), &span.range));
+        }
+        match span.kind {
+            Kind::Paren => attrs.insert(place(AttrInt::new_foreground_alpha(0x9000).into(), &span.range)),
+            Kind::Comment => {
+                attrs.insert(place(AttrInt::new_foreground_alpha(0x9000).into(), &span.range));
+                attrs.insert(place(AttrInt::new_style(Style::Italic).into(), &span.range));
+            }
+            _ => {}
+        }
+    }
+    if let Some(range) = error {
+        attrs.insert(place(AttrInt::new_underline(Underline::Error).into(), &range));
+        attrs.insert(place(AttrColor::new_underline_color(0xe0 * 257, 0x1b * 257, 0x24 * 257).into(), &range));
+    }
+    attrs
+}
+
+fn show_worksheet_results(gui: &Gui) {
+    let list = &gui.worksheet_list;
+    let results = gui.state.borrow().worksheet_results();
+    for (index, result) in results.iter().enumerate() {
+        if let Some(entry) = worksheet_entry(list, index) {
+            let error = match &result.value {
+                LineValue::Error(e) => result.error_span.clone().map(|span| (span, e.as_str())),
+                _ => None,
+            };
+            entry.set_attributes(&expression_attributes(&entry.text(), error.as_ref().map(|(span, _)| span.clone())));
+            entry.set_tooltip_text(error.map(|(_, message)| message));
+        }
+        let label = match worksheet_row_child(list, index, 2).and_then(|w| w.downcast::<gtk::Label>().ok()) {
+            Some(label) => label,
+            None => continue,
+        };
+        let ctx = label.style_context();
+        ctx.remove_class("error");
+        let text = match &result.value {
+            LineValue::Blank => String::new(),
+            LineValue::Value(v) => format!("= {}", v.text),
+            LineValue::Error(e) => {
+                ctx.add_class("error");
+                e.clone()
+            }
+        };
+        label.set_text(&text);
+        label.set_tooltip_text(Some(&text).filter(|t| !t.is_empty()).map(String::as_str));
+    }
+}
+
+// ── Completion ──
+
+const MAX_COMPLETIONS: usize = 8;
+
+fn set_completing(entry: &gtk::Entry, popover: &gtk::Popover, completing: bool) {
+    if completing {
+        entry.style_context().add_class("completing");
+        popover.show_all();
+    } else {
+        entry.style_context().remove_class("completing");
+        popover.hide();
+    }
+}
+
+fn insert_completion(entry: &gtk::Entry, symbol: &Symbol) {
+    let text = entry.text().to_string();
+    let cursor = text.char_indices().nth(entry.position() as usize).map_or(text.len(), |(i, _)| i);
+    let (start, _) = domain::symbols::word_before(&text, cursor);
+    let start_pos = text[..start].chars().count() as i32;
+    entry.delete_text(start_pos, entry.position());
+    let mut pos = start_pos;
+    entry.insert_text(&symbol.insert_text(), &mut pos);
+    entry.set_position(pos);
+}
+
+/// Pops up matching function, constant and variable names under `entry`
+/// while a word is typed; Tab or Enter inserts the highlighted one.
+/// `symbols` is asked on every keystroke so newly defined names show up.
+fn attach_completion(entry: &gtk::Entry, symbols: impl Fn() -> SymbolTable + 'static) {
+    let popover = gtk::Popover::new(Some(entry));
+    popover.set_modal(false);
+    popover.set_position(gtk::PositionType::Bottom);
+    let list = gtk::ListBox::new();
+    list.set_selection_mode(gtk::SelectionMode::Browse);
+    popover.add(&list);
+    let matches: Rc<RefCell<Vec<Symbol>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let refresh = {
+        let popover = popover.clone();
+        let list = list.clone();
+        let matches = matches.clone();
+        Rc::new(move |e: &gtk::Entry| {
+            let text = e.text().to_string();
+            let cursor = text.char_indices().nth(e.position() as usize).map_or(text.len(), |(i, _)| i);
+            let (_, word) = domain::symbols::word_before(&text, cursor);
+            let found: Vec<Symbol> = if e.has_focus() {
+                symbols().complete(word).into_iter().take(MAX_COMPLETIONS).cloned().collect()
+            } else {
+                Vec::new()
+            };
+            for child in list.children() {
+                list.remove(&child);
+            }
+            for symbol in &found {
+                let row = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+                let name = gtk::Label::new(Some(&symbol.name));
+                name.set_xalign(0.0);
+                let detail = gtk::Label::new(Some(&symbol.detail));
+                detail.style_context().add_class("panel-item-label");
+                detail.set_ellipsize(gtk::pango::EllipsizeMode::End);
+                detail.set_max_width_chars(24);
+                row.pack_start(&name, true, true, 0);
+                row.pack_end(&detail, false, false, 0);
+                list.add(&row);
+            }
+            list.select_row(list.row_at_index(0).as_ref());
+            set_completing(e, &popover, !found.is_empty());
+            *matches.borrow_mut() = found;
+        })
+    };
+    // The cursor has not moved yet while "changed" runs, so look at the
+    // word once GTK is done with the edit.
+    entry.connect_changed(move |e| {
+        let e = e.clone();
+        let refresh = refresh.clone();
+        gtk::glib::idle_add_local_once(move || refresh(&e));
+    });
+    {
+        let popover = popover.clone();
+        let list = list.clone();
+        let matches = matches.clone();
+        entry.connect_key_press_event(move |e, event| {
+            use gtk::gdk::keys::constants as key;
+            if !popover.is_visible() {
+                return gtk::Inhibit(false);
+            }
+            let keyval = event.keyval();
+            let selected = list.selected_row().map_or(0, |r| r.index());
+            if keyval == key::Up || keyval == key::Down {
+                let step = if keyval == key::Up { -1 } else { 1 };
+                let count = matches.borrow().len() as i32;
+                list.select_row(list.row_at_index((selected + step).rem_euclid(count)).as_ref());
+            } else if keyval == key::Tab || keyval == key::Return || keyval == key::KP_Enter {
+                let symbol = matches.borrow().get(selected as usize).cloned();
+                set_completing(e, &popover, false);
+                if let Some(symbol) = symbol {
+                    insert_completion(e, &symbol);
+                }
+            } else if keyval == key::Escape {
+                set_completing(e, &popover, false);
+            } else {
+                return gtk::Inhibit(false);
+            }
+            gtk::Inhibit(true)
+        });
+    }
+    {
+        let entry_c = entry.clone();
+        let popover_c = popover.clone();
+        list.connect_row_activated(move |_, row| {
+            let symbol = matches.borrow().get(row.index() as usize).cloned();
+            set_completing(&entry_c, &popover_c, false);
+            if let Some(symbol) = symbol {
+                insert_completion(&entry_c, &symbol);
+            }
+            entry_c.grab_focus_without_selecting();
+        });
+    }
+    entry.connect_focus_out_event(move |e, _| {
+        set_completing(e, &popover, false);
+        gtk::Inhibit(false)
+    });
+}
+
+fn wire_keyboard(gui: &Gui, calc_ui: &CalculatorUI) {
+    let gui = gui.clone();
+    let pending_g = Rc::new(RefCell::new(false));
+
+    {
+        let gui = gui.clone();
+        calc_ui.menu_palette_btn.connect_clicked(move |_| {
+            gui.menu_popover.popdown();
+            show_command_palette(&gui);
+        });
+    }
+    wire_summon(&gui);
+
+    calc_ui.window.connect_key_press_event(move |win, event| {
+        let keyval = event.keyval();
+        let mods = event.state();
+        let ctrl = mods.contains(gtk::gdk::ModifierType::CONTROL_MASK);
+        let alt = mods.contains(gtk::gdk::ModifierType::MOD1_MASK);
+        let shift = mods.contains(gtk::gdk::ModifierType::SHIFT_MASK);
+
+        // Plain keys belong to whichever text field has focus (worksheet,
+        // notes, search); only shortcuts and Escape reach the keymap.
+        let focus = win.focused_widget();
+        let typing = focus.as_ref().is_some_and(|w| w.is::<gtk::Entry>() || w.is::<gtk::TextView>());
+        // An open completion list takes Escape before the panel does.
+        let completing = focus.is_some_and(|w| w.style_context().has_class("completing"));
+        if typing && !ctrl && !alt && (completing || keyval != gtk::gdk::keys::constants::Escape) {
+            return gtk::Inhibit(false);
+        }
+
+        // Escape sends a summoned popup away, unless a mode panel is open.
+        if keyval == gtk::gdk::keys::constants::Escape
+            && !ctrl
+            && !gui.state.borrow().mode_panel_visible
+            && dismiss_summon(&gui)
+        {
+            return gtk::Inhibit(true);
+        }
+
+        // Handle pending g+t / g+T chord
+        if *pending_g.borrow() {
+            *pending_g.borrow_mut() = false;
+            if !ctrl && !alt {
+                let chord_msg = match keyval.to_unicode() {
+                    Some('t') => Some(Message::NextTab),
+                    Some('T') => Some(Message::PrevTab),
+                    _ => None,
+                };
+                if let Some(msg) = chord_msg {
+                    dispatch(&gui, msg);
+                    return gtk::Inhibit(true);
+                }
+            }
+            // chord not completed — fall through to normal processing
+        }
+
+        // 'g' alone (no modifiers, not in mode panel) initiates chord
+        if !ctrl && !alt && !shift && keyval.to_unicode() == Some('g') {
+            let mode_open = gui.state.borrow().mode_panel_visible;
+            if !mode_open {
+                *pending_g.borrow_mut() = true;
+                return gtk::Inhibit(true);
+            }
+        }
+
+        let msg = ui::keyboard::map_key(event);
+        // Text fields paste into themselves.
+        if matches!(msg, Message::Noop) || (typing && matches!(msg, Message::RequestPaste)) {
+            return gtk::Inhibit(false);
+        }
+        // While the arrow keys have a keypad button focused, Enter presses it
+        // like Space; any other key hands Enter back to "=".
+        let keypad_focused = gui.keys.borrow().nav_buttons.iter().any(|b| b.button.has_focus());
+        let enter = keyval == gtk::gdk::keys::constants::Return || keyval == gtk::gdk::keys::constants::KP_Enter;
+        let msg = match msg {
+            Message::Equals if keypad_focused && enter => Message::Activate,
+            Message::Navigate(_) | Message::Activate => msg,
+            _ => {
+                if keypad_focused {
+                    win.set_focus(None::<&gtk::Widget>);
+                }
+                msg
+            }
+        };
+        key_feedback(&gui, &msg);
+        dispatch(&gui, msg);
+        gtk::Inhibit(true)
+    });
+}
+
+/// Gives a typed key the same feedback as a click: the click sound and a
+/// brief pressed look on the matching keypad button.
+fn key_feedback(gui: &Gui, msg: &Message) {
+    let Some(action) = ButtonAction::for_message(msg) else { return };
+    let feedback = gui.state.borrow().config.feedback.clone();
+    if feedback.click_sound {
+        services::sound::click();
+    }
+    if !feedback.key_highlight {
+        return;
+    }
+    for (button, _) in gui.keys.borrow().action_buttons.iter().filter(|(b, a)| *a == action && b.is_mapped()) {
+        let style = button.style_context();
+        style.add_class("key-flash");
+        gtk::glib::timeout_add_local_once(std::time::Duration::from_millis(120), move || {
+            style.remove_class("key-flash");
+        });
+    }
+}
+
+// ── Summon popup ──
+
+const SUMMON_SIZE: (i32, i32) = (320, 440);
+
+/// x, y, width and height of the window.
+type SavedGeometry = (i32, i32, i32, i32);
+
+fn wire_summon(gui: &Gui) {
+    let combo = gui.state.borrow().config.window.summon_shortcut.clone();
+    if combo.is_empty() {
+        return;
+    }
+    let gui = gui.clone();
+    let bound = services::portal::bind_global_shortcut("summon", "Show Fredulator", &combo, move |timestamp| {
+        if !dismiss_summon(&gui) {
+            summon(&gui, timestamp);
+        }
+    });
+    if let Err(e) = bound {
+        eprintln!("fredulator: summon shortcut unavailable: {}", e);
+    }
+}
+
+/// Raises the window as a small undecorated popup next to the pointer.
+fn summon(gui: &Gui, timestamp: u32) {
+    let window = &gui.window;
+    let (x, y) = window.position();
+    let (w, h) = window.size();
+    gui.summoned.set(Some((x, y, w, h)));
+    window.set_decorated(false);
+    window.set_keep_above(true);
+    window.resize(SUMMON_SIZE.0, SUMMON_SIZE.1);
+    let display = gtk::gdk::Display::default();
+    let pointer = display.as_ref().and_then(|d| d.default_seat()).and_then(|seat| seat.pointer());
+    if let (Some(display), Some(pointer)) = (display, pointer) {
+        let (_, px, py) = pointer.position();
+        let (left, top) = match display.monitor_at_point(px, py) {
+            Some(monitor) => {
+                let area = monitor.workarea();
+                (
+                    (px - SUMMON_SIZE.0 / 2).clamp(area.x(), (area.x() + area.width() - SUMMON_SIZE.0).max(area.x())),
+                    (py - 24).clamp(area.y(), (area.y() + area.height() - SUMMON_SIZE.1).max(area.y())),
+                )
+            }
+            None => ((px - SUMMON_SIZE.0 / 2).max(0), (py - 24).max(0)),
+        };
+        window.move_(left, top);
+    }
+    window.deiconify();
+    window.show();
+    window.present_with_time(timestamp);
+}
+
+/// Puts the window back the way it was and minimizes it. Returns false when
+/// no popup was up.
+fn dismiss_summon(gui: &Gui) -> bool {
+    let Some((x, y, w, h)) = gui.summoned.take() else { return false };
+    let cfg = gui.state.borrow().config.window.clone();
+    gui.window.set_decorated(!cfg.compact_mode);
+    gui.window.set_keep_above(cfg.always_on_top);
+    gui.window.move_(x, y);
+    gui.window.resize(w, h);
+    gui.window.iconify();
+    true
+}
+
+// ── Command palette ──
+
+fn palette_row(command: &ui::commands::Command) -> gtk::ListBoxRow {
+    let hbox = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+    let title = gtk::Label::new(Some(&command.title));
+    title.set_xalign(0.0);
+    hbox.pack_start(&title, true, true, 0);
+    if let Some(hint) = &command.hint {
+        let hint = gtk::Label::new(Some(hint));
+        hint.style_context().add_class("palette-hint");
+        hbox.pack_end(&hint, false, false, 0);
+    }
+    let row = gtk::ListBoxRow::new();
+    row.style_context().add_class("palette-row");
+    row.add(&hbox);
+    row
+}
+
+fn show_command_palette(gui: &Gui) {
+    let commands = Rc::new(ui::commands::all(&gui.state.borrow().conversion_choices()));
+    // Indices into `commands` of the rows currently shown.
+    let shown: Rc<RefCell<Vec<usize>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let popover = gtk::Popover::new(Some(&gui.display_event_box));
+    popover.style_context().add_class("command-palette");
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    vbox.set_margin_top(8);
+    vbox.set_margin_bottom(8);
+    vbox.set_margin_start(8);
+    vbox.set_margin_end(8);
+    let search = gtk::SearchEntry::new();
+    search.set_placeholder_text(Some("Type a command\u{2026}"));
+    // Lets Escape reach the popover instead of closing the mode panel.
+    search.style_context().add_class("completing");
+    let scroll = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    scroll.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic);
+    scroll.set_size_request(360, 320);
+    let list = gtk::ListBox::new();
+    list.set_selection_mode(gtk::SelectionMode::Browse);
+    scroll.add(&list);
+    vbox.pack_start(&search, false, false, 0);
+    vbox.pack_start(&scroll, true, true, 0);
+    popover.add(&vbox);
+
+    let fill = {
+        let commands = commands.clone();
+        let shown = shown.clone();
+        let list = list.clone();
+        move |query: &str| {
+            for child in list.children() {
+                list.remove(&child);
+            }
+            let found = ui::commands::filter(&commands, query);
+            *shown.borrow_mut() = found
+                .iter()
+                .map(|c| commands.iter().position(|x| std::ptr::eq(x, *c)).unwrap_or(0))
+                .collect();
+            for command in found {
+                list.add(&palette_row(command));
+            }
+            list.show_all();
+            if let Some(first) = list.row_at_index(0) {
+                list.select_row(Some(&first));
+            }
+        }
+    };
+    fill("");
+    search.connect_search_changed(move |entry| fill(&entry.text()));
+
+    let run = {
+        let gui = gui.clone();
+        let popover = popover.clone();
+        move |index: i32| {
+            let command = shown.borrow().get(index as usize).map(|&i| commands[i].message.clone());
+            popover.popdown();
+            if let Some(msg) = command {
+                dispatch(&gui, msg);
+            }
+        }
+    };
+    let run = Rc::new(run);
+    {
+        let run = run.clone();
+        list.connect_row_activated(move |_, row| run(row.index()));
+    }
+    {
+        let list = list.clone();
+        search.connect_activate(move |_| {
+            if let Some(row) = list.selected_row() {
+                run(row.index());
+            }
+        });
+    }
+    {
+        let list = list.clone();
+        search.connect_key_press_event(move |_, event| {
+            let keyval = event.keyval();
+            let step = if keyval == gtk::gdk::keys::constants::Down {
+                1
+            } else if keyval == gtk::gdk::keys::constants::Up {
+                -1
+            } else {
+                return gtk::Inhibit(false);
+            };
+            let current = list.selected_row().map_or(0, |r| r.index());
+            if let Some(row) = list.row_at_index((current + step).max(0)) {
+                list.select_row(Some(&row));
+            }
+            gtk::Inhibit(true)
+        });
+    }
+
+    discard_when_closed(&popover);
+    vbox.show_all();
+    popover.popup();
+    search.grab_focus();
+}
+
+#[cfg(feature = "tray")]
+fn wire_tray(gui: &Gui) {
+    use services::tray::TrayEvent;
+
+    let (tx, rx) = gtk::glib::MainContext::channel(gtk::glib::PRIORITY_DEFAULT);
+    if let Err(e) = services::tray::start(tx) {
+        eprintln!("fredulator: tray icon unavailable: {}", e);
+        return;
+    }
+    publish_tray_results(&gui.state.borrow());
+    let gui = gui.clone();
+    rx.attach(None, move |event| {
+        match event {
+            TrayEvent::Show => {
+                gui.window.deiconify();
+                gui.window.show();
+                gui.window.present();
+            }
+            TrayEvent::Copy(text) => gtk::Clipboard::get(&gtk::gdk::SELECTION_CLIPBOARD).set_text(&text),
+            TrayEvent::Quit => quit(&gui),
+        }
+        gtk::glib::Continue(true)
+    });
+}
+
+#[cfg(feature = "tray")]
+fn publish_tray_results(s: &AppState) {
+    let results = s
+        .engine()
+        .history
+        .iter()
+        .rev()
+        .take(services::tray::MAX_RESULTS)
+        .map(|entry| entry.result_text.clone())
+        .collect();
+    services::tray::set_results(results);
+}
+
+fn quit(gui: &Gui) {
+    let s = gui.state.borrow();
+    if s.config.window.remember_geometry {
+        let (x, y) = gui.window.position();
+        let (w, h) = gui.window.size();
+        services::session::save_geometry(x, y, w, h);
+    }
+    update::save_on_exit(&s);
+    gtk::main_quit();
+}
+
+/// Saves history and the session when the app is told to stop by a signal
+/// (`kill`, logging out, Ctrl+C in the launching terminal) rather than
+/// through its window, which would otherwise end it without a word.
+fn wire_shutdown_signals(gui: &Gui) {
+    const SIGHUP: i32 = 1;
+    const SIGINT: i32 = 2;
+    const SIGTERM: i32 = 15;
+    for signum in [SIGHUP, SIGINT, SIGTERM] {
+        let gui = gui.clone();
+        gtk::glib::unix_signal_add_local_once(signum, move || quit(&gui));
+    }
+}
+
+fn wire_window_close(gui: &Gui, calc_ui: &CalculatorUI) {
+    {
+        let gui = gui.clone();
+        calc_ui.window.connect_delete_event(move |_, _| {
+            #[cfg(feature = "tray")]
+            if gui.state.borrow().config.window.close_to_tray && services::tray::is_hosted() {
+                gui.window.hide();
+                return gtk::Inhibit(true);
+            }
+            quit(&gui);
+            gtk::Inhibit(false)
+        });
+    }
+
+    {
+        let gui = gui.clone();
+        calc_ui.tab_add_btn.connect_clicked(move |_| {
+            dispatch(&gui, Message::NewTab);
+        });
+    }
+}
+
+// ── Help dialog ──────────────────────────────────────────────────────────────
+
+fn show_help_dialog(window: &gtk::Window) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some("Keyboard Shortcuts"),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[("Close", gtk::ResponseType::Close)],
+    );
+    dialog.set_default_size(480, 560);
+
+    let content = dialog.content_area();
+    let scroll =
+        gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    scroll.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic);
+
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    vbox.set_margin_top(12);
+    vbox.set_margin_bottom(16);
+    vbox.set_margin_start(20);
+    vbox.set_margin_end(20);
+
+    let sections: &[(&str, &[(&str, &str)])] = &[
+        (
+            "CALCULATOR",
+            &[
+                ("0 – 9", "Digits"),
+                ("+ − × ÷", "Arithmetic operators"),
+                ("^", "Power / exponent"),
+                ("( )", "Parentheses"),
+                (".", "Decimal point"),
+                ("= / Enter", "Calculate result"),
+                ("%", "Percent"),
+                ("!", "Factorial"),
+                ("±", "Value with an uncertainty"),
+                ("[ , ]", "Interval [lo, hi]"),
+                ("n", "Negate (+/−)"),
+                ("Backspace", "Delete last character"),
+                ("Escape", "Clear / close panel"),
+                ("Space", "Activate focused button"),
+                ("u / Ctrl+Z", "Undo"),
+                ("Ctrl+V", "Paste (invalid text is ignored)"),
+                ("Ctrl+K", "Command palette"),
+            ],
+        ),
+        (
+            "DISPLAY & APP",
+            &[
+                ("s", "Toggle scientific mode"),
+                ("t", "Cycle theme"),
+                ("Ctrl+= / Ctrl+-", "Zoom in / out"),
+                ("Ctrl+0", "Reset zoom"),
+                ("Ctrl+Q", "Quit"),
+                (";", "Open menu"),
+                ("? / F1", "Show this help"),
+            ],
+        ),
+        (
+            "NAVIGATION",
+            &[
+                ("h / j / k / l", "Navigate buttons (vim-style)"),
+                ("Arrow keys", "Navigate buttons"),
+                ("Space / Enter", "Activate focused button"),
+                ("Alt+letter", "Underlined menu item or panel"),
+            ],
+        ),
+        (
+            "TABS",
+            &[
+                ("Ctrl+T", "New tab"),
+                ("Ctrl+W", "Close current tab"),
+                ("Tab", "Next tab"),
+                ("Shift+Tab", "Previous tab"),
+                ("g + t", "Next tab (chord)"),
+                ("g + T", "Previous tab (chord)"),
+                ("Click", "Switch to tab"),
+                ("Double-click", "Rename tab"),
+                ("Right-click", "Delete / rename tab"),
+            ],
+        ),
+        (
+            "PANELS",
+            &[
+                ("Ctrl+H", "Toggle history panel"),
+                ("Ctrl+M", "Toggle memory panel"),
+                ("Ctrl+P", "Toggle pinned panel"),
+                ("Ctrl+S", "Pin current result"),
+                ("S", "Store value to memory"),
+                ("Ctrl+Shift+E", "Export history"),
+            ],
+        ),
+        (
+            "MODES",
+            &[
+                ("Ctrl+E", "Unit converter"),
+                ("Ctrl+R", "Quick tools (tip / discount / tax / time / ratio)"),
+                ("Ctrl+N", "Math notes"),
+                ("Ctrl+J", "Worksheet"),
+            ],
+        ),
+    ];
+
+    for (i, (title, entries)) in sections.iter().enumerate() {
+        if i > 0 {
+            let sep = gtk::Separator::new(gtk::Orientation::Horizontal);
+            sep.set_margin_top(8);
+            sep.set_margin_bottom(4);
+            vbox.pack_start(&sep, false, false, 0);
+        }
+
+        let header = gtk::Label::new(Some(title));
+        header.style_context().add_class("help-section-header");
+        header.set_xalign(0.0);
+        vbox.pack_start(&header, false, false, 0);
+
+        let grid = gtk::Grid::new();
+        grid.set_row_spacing(3);
+        grid.set_column_spacing(16);
+        grid.set_margin_top(4);
+        grid.set_margin_bottom(4);
+
+        for (row, (key, desc)) in entries.iter().enumerate() {
+            let key_lbl = gtk::Label::new(Some(key));
+            key_lbl.style_context().add_class("help-key-badge");
+            key_lbl.set_xalign(1.0);
+            key_lbl.set_hexpand(false);
+
+            let desc_lbl = gtk::Label::new(Some(desc));
+            desc_lbl.style_context().add_class("help-desc");
+            desc_lbl.set_xalign(0.0);
+            desc_lbl.set_hexpand(true);
+
+            grid.attach(&key_lbl, 0, row as i32, 1, 1);
+            grid.attach(&desc_lbl, 1, row as i32, 1, 1);
+        }
+
+        vbox.pack_start(&grid, false, false, 0);
+    }
+
+    scroll.add(&vbox);
+    content.pack_start(&scroll, true, true, 0);
+    dialog.show_all();
+    dialog.run();
+    unsafe { dialog.destroy(); }
+}
+
+fn show_about_dialog(window: &gtk::Window) {
+    let dialog = gtk::AboutDialog::new();
+    dialog.set_transient_for(Some(window));
+    dialog.set_modal(true);
+    dialog.set_program_name("Fredulator");
+    dialog.set_version(Some(env!("CARGO_PKG_VERSION")));
+    dialog.set_comments(Some("Calculator with vim motions"));
+    dialog.set_logo_icon_name(Some(services::resources::ICON_NAME));
+    dialog.set_website(Some("https://github.com/fredrir/fredulator"));
+    dialog.set_license_type(gtk::License::MitX11);
+    dialog.run();
+    dialog.close();
+}
+
+// ── Drag and drop ────────────────────────────────────────────────────────────
+
+fn wire_drag_and_drop(gui: &Gui, calc_ui: &CalculatorUI) {
+    let display = &calc_ui.display_event_box;
+
+    display.drag_source_set(gtk::gdk::ModifierType::BUTTON1_MASK, &[], gtk::gdk::DragAction::COPY);
+    display.drag_source_add_text_targets();
+    {
+        let gui = gui.clone();
+        display.connect_drag_data_get(move |_, _, data, _, _| {
+            let s = gui.state.borrow();
+            data.set_text(&s.engine().main_display_text());
+        });
+    }
+
+    display.drag_dest_set(gtk::DestDefaults::ALL, &[], gtk::gdk::DragAction::COPY);
+    display.drag_dest_add_text_targets();
+    {
+        let gui = gui.clone();
+        display.connect_drag_data_received(move |_, _, _, _, data, _, _| {
+            if let Some(text) = data.text() {
+                dispatch(&gui, Message::LoadExpression(text.to_string()));
+            }
+        });
+    }
+}
+
+// ── Clipboard watcher ────────────────────────────────────────────────────────
+
+fn wire_clipboard_watch(gui: &Gui, calc_ui: &CalculatorUI) {
+    let revealer = calc_ui.clipboard_revealer.clone();
+    let offered: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+    {
+        let revealer = revealer.clone();
+        let offered = offered.clone();
+        calc_ui.clipboard_offer_btn.connect_clicked(move |_| {
+            if let Some(text) = offered.borrow_mut().take() {
+                gtk::Clipboard::get(&gtk::gdk::SELECTION_CLIPBOARD).set_text(&text);
+            }
+            revealer.set_reveal_child(false);
+        });
+    }
+    {
+        let revealer = revealer.clone();
+        let offered = offered.clone();
+        calc_ui.clipboard_dismiss_btn.connect_clicked(move |_| {
+            offered.borrow_mut().take();
+            revealer.set_reveal_child(false);
+        });
+    }
+
+    if !gui.state.borrow().config.behavior.watch_clipboard {
+        return;
+    }
+    let gui = gui.clone();
+    let offer_btn = calc_ui.clipboard_offer_btn.clone();
+    let clipboard = gtk::Clipboard::get(&gtk::gdk::SELECTION_CLIPBOARD);
+    // gtk-rs has no binding for "owner-change", so connect it by name.
+    clipboard.clone().connect_local("owner-change", false, move |_| {
+        // Copies made from Fredulator itself aren't worth offering back.
+        if gui.window.is_active() {
+            return None;
+        }
+        let gui = gui.clone();
+        let revealer = revealer.clone();
+        let offered = offered.clone();
+        let offer_btn = offer_btn.clone();
+        clipboard.request_text(move |_, text| {
+            let Some(text) = text else { return };
+            let Some(value) = gui.state.borrow().clipboard_offer(text) else { return };
+            let result = domain::types::format_number_default(value);
+            offer_btn.set_label(&format!("{} = {}", text.trim().trim_end_matches('=').trim_end(), result));
+            *offered.borrow_mut() = Some(result);
+            revealer.set_reveal_child(true);
+        });
+        None
+    });
+}
+
+// ── Convert-to popover ───────────────────────────────────────────────────────
+
+fn wire_convert_popover(gui: &Gui, calc_ui: &CalculatorUI) {
+    let popover = gtk::Popover::new(Some(&calc_ui.display_event_box));
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 2);
+    vbox.set_margin_top(8);
+    vbox.set_margin_bottom(8);
+    popover.add(&vbox);
+
+    let show: Rc<dyn Fn()> = {
+        let gui = gui.clone();
+        let popover = popover.clone();
+        Rc::new(move || {
+            let (value, choices) = {
+                let s = gui.state.borrow();
+                if s.engine().has_error() {
+                    return;
+                }
+                (s.engine().current_value(), s.conversion_choices())
+            };
+            for child in vbox.children() {
+                vbox.remove(&child);
+            }
+            let steps_btn = gtk::Button::with_label("\u{2261} Show steps");
+            steps_btn.style_context().add_class("menu-item");
+            steps_btn.set_halign(gtk::Align::Fill);
+            {
+                let gui = gui.clone();
+                let popover = popover.clone();
+                steps_btn.connect_clicked(move |_| {
+                    popover.popdown();
+                    dispatch(&gui, Message::ShowSteps);
+                });
+            }
+            vbox.pack_start(&steps_btn, false, false, 0);
+
+            let header = gtk::Label::new(Some("CONVERT TO\u{2026}"));
+            header.style_context().add_class("menu-header");
+            header.set_xalign(0.0);
+            vbox.pack_start(&header, false, false, 0);
+
+            for pair in choices {
+                let text = format!(
+                    "{}    {} {}",
+                    pair.label(),
+                    domain::types::format_number_default(pair.apply(value)),
+                    domain::convert::unit_symbol(pair.to)
+                );
+                let btn = gtk::Button::with_label(&text);
+                btn.style_context().add_class("menu-item");
+                btn.set_halign(gtk::Align::Fill);
+                let gui = gui.clone();
+                let popover = popover.clone();
+                btn.connect_clicked(move |_| {
+                    popover.popdown();
+                    dispatch(&gui, Message::ConvertResult(pair));
+                });
+                vbox.pack_start(&btn, false, false, 0);
+            }
+            vbox.show_all();
+            popover.popup();
+        })
+    };
+
+    {
+        let show = show.clone();
+        calc_ui.display_event_box.connect_button_press_event(move |_, event| {
+            if event.button() == 3 {
+                show();
+                return gtk::Inhibit(true);
+            }
+            gtk::Inhibit(false)
+        });
+    }
+    calc_ui.display_long_press.connect_pressed(move |_, _, _| show());
+}
+
+// ── Steps popover ──
+
+fn wire_exact_popover(gui: &Gui, calc_ui: &CalculatorUI) {
+    let gui = gui.clone();
+    calc_ui.exact_btn.connect_clicked(move |btn| {
+        if let Some(exact) = DisplayUpdate::from_state(&gui.state.borrow()).exact {
+            show_exact_popover(btn, &exact);
+        }
+    });
+}
+
+// ── Percent questions ──
+
+fn wire_percent_menu(gui: &Gui) {
+    for (button, gesture) in &gui.keys.borrow().percent_long_press {
+        let show: Rc<dyn Fn()> = {
+            let gui = gui.clone();
+            let button = button.clone();
+            Rc::new(move || show_percent_menu(&button, &gui))
+        };
+        // Claiming the press keeps the key from also typing a `%`.
+        gesture.set_propagation_phase(gtk::PropagationPhase::Capture);
+        {
+            let show = show.clone();
+            gesture.connect_pressed(move |g, _, _| {
+                g.set_state(gtk::EventSequenceState::Claimed);
+                show();
+            });
+        }
+        button.connect_button_press_event(move |_, event| {
+            if event.button() == 3 {
+                show();
+                return gtk::Inhibit(true);
+            }
+            gtk::Inhibit(false)
+        });
+    }
+}
+
+fn show_percent_menu(anchor: &gtk::Button, gui: &Gui) {
+    let popover = gtk::Popover::new(Some(anchor));
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 2);
+    vbox.set_margin_top(8);
+    vbox.set_margin_bottom(8);
+    let header = gtk::Label::new(Some("PERCENT"));
+    header.style_context().add_class("menu-header");
+    header.set_xalign(0.0);
+    vbox.pack_start(&header, false, false, 0);
+    for query in PercentQuery::ALL {
+        let btn = gtk::Button::with_label(query.title());
+        btn.style_context().add_class("menu-item");
+        btn.set_halign(gtk::Align::Fill);
+        let anchor = anchor.clone();
+        let gui = gui.clone();
+        let popover_c = popover.clone();
+        btn.connect_clicked(move |_| {
+            popover_c.popdown();
+            let gui = gui.clone();
+            show_operand_form(&anchor, query.title(), query.operand_labels(), move |x, y| {
+                dispatch(&gui, Message::PercentQuery(query, x, y));
+            });
+        });
+        vbox.pack_start(&btn, false, false, 0);
+    }
+    popover.add(&vbox);
+    discard_when_closed(&popover);
+    vbox.show_all();
+    popover.popup();
+}
+
+/// A popover asking for two numbers, each of which may be an expression;
+/// `submit` gets them once both evaluate.
+fn show_operand_form(
+    anchor: &impl IsA<gtk::Widget>,
+    title: &str,
+    labels: (&str, &str),
+    submit: impl Fn(f64, f64) + 'static,
+) {
+    let popover = gtk::Popover::new(Some(anchor));
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(6);
+    grid.set_column_spacing(8);
+    grid.set_margin_top(8);
+    grid.set_margin_bottom(8);
+    grid.set_margin_start(12);
+    grid.set_margin_end(12);
+    let header = gtk::Label::new(Some(&title.to_uppercase()));
+    header.style_context().add_class("menu-header");
+    header.set_xalign(0.0);
+    grid.attach(&header, 0, 0, 2, 1);
+
+    let mut entries = Vec::new();
+    for (row, text) in [labels.0, labels.1].into_iter().enumerate() {
+        let label = gtk::Label::new(Some(text));
+        label.set_xalign(0.0);
+        let entry = gtk::Entry::new();
+        entry.set_activates_default(true);
+        ui::a11y::describe(&entry, text, title);
+        grid.attach(&label, 0, row as i32 + 1, 1, 1);
+        grid.attach(&entry, 1, row as i32 + 1, 1, 1);
+        entries.push(entry);
+    }
+    let ok_btn = gtk::Button::with_label("=");
+    ok_btn.style_context().add_class("menu-item");
+    ok_btn.set_can_default(true);
+    grid.attach(&ok_btn, 1, 3, 1, 1);
+
+    {
+        let popover = popover.clone();
+        let entries = entries.clone();
+        ok_btn.connect_clicked(move |_| {
+            let values: Vec<Option<f64>> = entries
+                .iter()
+                .map(|e| {
+                    let plugins = Default::default();
+                    let value = domain::eval::evaluate_str(&e.text(), &plugins, AngleMode::Degrees, true).ok();
+                    e.set_tooltip_text(value.is_none().then_some("Not a number"));
+                    value
+                })
+                .collect();
+            if let [Some(x), Some(y)] = values[..] {
+                popover.popdown();
+                submit(x, y);
+            }
+        });
+    }
+    popover.add(&grid);
+    popover.set_default_widget(Some(&ok_btn));
+    discard_when_closed(&popover);
+    grid.show_all();
+    popover.popup();
+    entries[0].grab_focus();
+}
+
+fn show_exact_popover(anchor: &impl IsA<gtk::Widget>, exact: &ExactValue) {
+    let popover = gtk::Popover::new(Some(anchor));
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 4);
+    vbox.set_margin_top(8);
+    vbox.set_margin_bottom(8);
+    vbox.set_margin_start(12);
+    vbox.set_margin_end(12);
+    let header = gtk::Label::new(Some("EXACT VALUE"));
+    header.style_context().add_class("menu-header");
+    header.set_xalign(0.0);
+    vbox.pack_start(&header, false, false, 0);
+    for line in exact.fraction.iter().chain(std::iter::once(&exact.digits)) {
+        let label = gtk::Label::new(Some(line));
+        label.style_context().add_class("steps-line");
+        label.set_xalign(0.0);
+        label.set_selectable(true);
+        vbox.pack_start(&label, false, false, 0);
+    }
+    if let Some(lost) = &exact.lost {
+        let label = gtk::Label::new(Some(lost));
+        label.style_context().add_class("help-desc");
+        label.set_xalign(0.0);
+        vbox.pack_start(&label, false, false, 0);
+    }
+    popover.add(&vbox);
+    discard_when_closed(&popover);
+    vbox.show_all();
+    popover.popup();
+}
+
+/// Popovers made on demand are thrown away once closed: detaching one from
+/// its anchor drops the window's reference so it is freed with the last clone.
+fn discard_when_closed(popover: &gtk::Popover) {
+    popover.connect_closed(|p| {
+        let p = p.clone();
+        gtk::glib::idle_add_local_once(move || p.set_relative_to(None::<&gtk::Widget>));
+    });
+}
+
+fn show_steps_popover(anchor: &impl IsA<gtk::Widget>, steps: &Result<Vec<domain::ast::Step>, String>) {
+    let popover = gtk::Popover::new(Some(anchor));
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 4);
+    vbox.set_margin_top(8);
+    vbox.set_margin_bottom(8);
+    vbox.set_margin_start(12);
+    vbox.set_margin_end(12);
+    let header = gtk::Label::new(Some("STEPS"));
+    header.style_context().add_class("menu-header");
+    header.set_xalign(0.0);
+    vbox.pack_start(&header, false, false, 0);
+
+    let lines: Vec<String> = match steps {
+        Ok(steps) if steps.is_empty() => vec!["A single number \u{2014} nothing to break down".into()],
+        Ok(steps) => steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| format!("{}.  {}", i + 1, step.render()))
+            .collect(),
+        Err(e) => vec![e.clone()],
+    };
+    for line in lines {
+        let label = gtk::Label::new(Some(&line));
+        label.style_context().add_class("steps-line");
+        label.set_xalign(0.0);
+        label.set_selectable(true);
+        vbox.pack_start(&label, false, false, 0);
+    }
+    popover.add(&vbox);
+    discard_when_closed(&popover);
+    vbox.show_all();
+    popover.popup();
+}
+
+// ── Export dialog ────────────────────────────────────────────────────────────
+
+fn show_export_dialog(gui: &Gui) {
+    let dialog = gtk::FileChooserNative::new(
+        Some("Export History"),
+        Some(&gui.window),
+        gtk::FileChooserAction::Save,
+        Some("Export"),
+        Some("Cancel"),
+    );
+    dialog.set_do_overwrite_confirmation(true);
+    dialog.set_current_name("history.csv");
+
+    let mut filters = Vec::new();
+    for format in ExportFormat::ALL {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&format!("{} (*.{})", format.name(), format.extension())));
+        filter.add_pattern(&format!("*.{}", format.extension()));
+        dialog.add_filter(&filter);
+        filters.push((filter, *format));
+    }
+
+    if dialog.run() == gtk::ResponseType::Accept {
+        if let Some(mut path) = dialog.filename() {
+            if ExportFormat::from_path(&path).is_none() {
+                let chosen = dialog.filter();
+                let format = filters
+                    .iter()
+                    .find(|(f, _)| Some(f) == chosen.as_ref())
+                    .map(|(_, fmt)| *fmt)
+                    .unwrap_or(ExportFormat::Csv);
+                path.set_extension(format.extension());
+            }
+            dispatch(gui, Message::ExportHistoryTo(path));
+        }
+    }
+    dialog.destroy();
+}
+
+// ── Keypad editor ────────────────────────────────────────────────────────────
+
+const RESPONSE_RESET: gtk::ResponseType = gtk::ResponseType::Other(1);
+
+#[derive(Clone)]
+struct KeyRow {
+    row: gtk::ListBoxRow,
+    label: gtk::Entry,
+    action: gtk::ComboBoxText,
+    col: gtk::SpinButton,
+    grid_row: gtk::SpinButton,
+    span: gtk::SpinButton,
+}
+
+impl KeyRow {
+    fn spec(&self) -> Result<KeySpec, String> {
+        let code = self.action.active_id().map(|id| id.to_string()).unwrap_or_default();
+        Ok(KeySpec::custom(
+            self.label.text().to_string(),
+            ButtonAction::from_code(&code)?,
+            self.col.value_as_int() as usize,
+            self.grid_row.value_as_int() as usize,
+            self.span.value_as_int() as usize,
+        ))
+    }
+}
+
+#[derive(Clone)]
+struct KeypadPage {
+    list: gtk::ListBox,
+    rows: Rc<RefCell<Vec<KeyRow>>>,
+}
+
+impl KeypadPage {
+    fn new() -> Self {
+        let list = gtk::ListBox::new();
+        list.set_selection_mode(gtk::SelectionMode::None);
+        Self { list, rows: Rc::new(RefCell::new(Vec::new())) }
+    }
+
+    fn set_keys(&self, keys: &[KeySpec]) {
+        for r in self.rows.borrow_mut().drain(..) {
+            self.list.remove(&r.row);
+        }
+        for spec in keys {
+            self.add_key(spec);
+        }
+    }
+
+    fn add_key(&self, spec: &KeySpec) {
+        let spin = |min: usize, value: usize| {
+            let s = gtk::SpinButton::with_range(min as f64, (layout::MAX_GRID - 1 + min) as f64, 1.0);
+            s.set_value(value as f64);
+            s
+        };
+        let label = gtk::Entry::new();
+        label.set_text(&spec.label);
+        label.set_width_chars(6);
+        let action = gtk::ComboBoxText::new();
+        for (code, a) in layout::ACTION_CODES {
+            let name = ui::a11y::key_name(*a);
+            let text = if name == *code { name } else { format!("{} \u{2014} {}", code, name) };
+            action.append(Some(code), &text);
+        }
+        action.set_active_id(Some(spec.action.code()));
+        let remove = gtk::Button::with_label("\u{2715}");
+
+        let hbox = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        let key = KeyRow {
+            row: gtk::ListBoxRow::new(),
+            label,
+            action,
+            col: spin(0, spec.col),
+            grid_row: spin(0, spec.row),
+            span: spin(1, spec.span),
+        };
+        hbox.pack_start(&key.label, false, false, 0);
+        hbox.pack_start(&key.action, true, true, 0);
+        hbox.pack_start(&key.col, false, false, 0);
+        hbox.pack_start(&key.grid_row, false, false, 0);
+        hbox.pack_start(&key.span, false, false, 0);
+        hbox.pack_start(&remove, false, false, 0);
+        key.row.add(&hbox);
+        key.row.show_all();
+        self.list.add(&key.row);
+
+        let page = self.clone();
+        let row = key.row.clone();
+        remove.connect_clicked(move |_| {
+            page.list.remove(&row);
+            page.rows.borrow_mut().retain(|r| r.row != row);
+        });
+        self.rows.borrow_mut().push(key);
+    }
+
+    /// A new key goes on its own row below everything else.
+    fn add_blank(&self) {
+        let next_row = self.rows.borrow().iter().map(|r| r.grid_row.value_as_int() as usize + 1).max().unwrap_or(0);
+        self.add_key(&KeySpec::custom("0".into(), ButtonAction::Digit('0'), 0, next_row.min(layout::MAX_GRID - 1), 1));
+    }
+
+    fn keys(&self) -> Result<Vec<KeySpec>, String> {
+        self.rows.borrow().iter().map(KeyRow::spec).collect()
+    }
+}
+
+fn show_keypad_editor(gui: &Gui) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some("Edit Keypad"),
+        Some(&gui.window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            ("Reset to Defaults", RESPONSE_RESET),
+            ("Cancel", gtk::ResponseType::Cancel),
+            ("Save", gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_size(560, 520);
+
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    vbox.set_margin_top(12);
+    vbox.set_margin_bottom(12);
+    vbox.set_margin_start(16);
+    vbox.set_margin_end(16);
+
+    let hint = gtk::Label::new(Some(
+        "Label \u{00b7} action \u{00b7} column \u{00b7} row \u{00b7} span.",
+    ));
+    hint.set_xalign(0.0);
+    hint.set_line_wrap(true);
+    hint.style_context().add_class("help-desc");
+
+    let stack = gtk::Stack::new();
+    let switcher = gtk::StackSwitcher::new();
+    switcher.set_stack(Some(&stack));
+    switcher.set_halign(gtk::Align::Center);
+
+    let keypad = services::keypad::load();
+    let basic = KeypadPage::new();
+    let scientific = KeypadPage::new();
+    for (page, keys, name, title) in [
+        (&basic, &keypad.basic, "basic", "Basic"),
+        (&scientific, &keypad.scientific, "scientific", "Scientific"),
+    ] {
+        page.set_keys(keys);
+        let scroll = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+        scroll.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic);
+        scroll.set_vexpand(true);
+        scroll.add(&page.list);
+
+        let add_btn = gtk::Button::with_label("+ Add Key");
+        add_btn.set_halign(gtk::Align::Start);
+        let page_c = page.clone();
+        add_btn.connect_clicked(move |_| page_c.add_blank());
+
+        let page_box = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        page_box.pack_start(&scroll, true, true, 0);
+        page_box.pack_start(&add_btn, false, false, 0);
+        stack.add_titled(&page_box, name, title);
+    }
+
+    let status = gtk::Label::new(None);
+    status.set_xalign(0.0);
+    status.set_line_wrap(true);
+
+    vbox.pack_start(&switcher, false, false, 0);
+    vbox.pack_start(&hint, false, false, 0);
+    vbox.pack_start(&stack, true, true, 0);
+    vbox.pack_start(&status, false, false, 0);
+    dialog.content_area().pack_start(&vbox, true, true, 0);
+    dialog.show_all();
+
+    loop {
+        match dialog.run() {
+            gtk::ResponseType::Accept => {
+                let edited = basic.keys().and_then(|basic| Ok(Keypad { basic, scientific: scientific.keys()? }));
+                match edited.and_then(|k| services::keypad::save(&k).map(|()| k)) {
+                    Ok(keypad) => {
+                        rebuild_keypad(gui, &keypad);
+                        break;
+                    }
+                    Err(e) => status.set_text(&e),
+                }
+            }
+            RESPONSE_RESET => match services::keypad::reset() {
+                Ok(()) => {
+                    let defaults = Keypad::default();
+                    basic.set_keys(&defaults.basic);
+                    scientific.set_keys(&defaults.scientific);
+                    rebuild_keypad(gui, &defaults);
+                    status.set_text("Restored the built-in layout.");
+                }
+                Err(e) => status.set_text(&e),
+            },
+            _ => break,
+        }
+    }
+    dialog.close();
+}
+
+// ── Snippets ─────────────────────────────────────────────────────────────────
+
+fn show_snippets_popover(gui: &Gui) {
+    let popover = gtk::Popover::new(Some(&gui.display_event_box));
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 2);
+    vbox.set_margin_top(8);
+    vbox.set_margin_bottom(8);
+    let header = gtk::Label::new(Some("SNIPPETS"));
+    header.style_context().add_class("menu-header");
+    header.set_xalign(0.0);
+    vbox.pack_start(&header, false, false, 0);
+
+    let snippets = gui.state.borrow().snippets.clone();
+    if snippets.is_empty() {
+        let empty = gtk::Label::new(Some("No snippets saved"));
+        empty.style_context().add_class("panel-empty");
+        vbox.pack_start(&empty, false, false, 0);
+    }
+    for (index, snippet) in snippets.iter().enumerate() {
+        let btn = gtk::Button::with_label(&format!("{}    {}", snippet.name, snippet.expression));
+        btn.style_context().add_class("menu-item");
+        btn.set_halign(gtk::Align::Fill);
+        let placeholders = snippet.placeholders();
+        if !placeholders.is_empty() {
+            btn.set_tooltip_text(Some(&format!("Fills {} with the displayed value", placeholders.join(", "))));
+        }
+        let gui = gui.clone();
+        let popover = popover.clone();
+        btn.connect_clicked(move |_| {
+            popover.popdown();
+            dispatch(&gui, Message::InsertSnippet(index));
+        });
+        vbox.pack_start(&btn, false, false, 0);
+    }
+
+    let manage_btn = gtk::Button::with_label("Manage Snippets\u{2026}");
+    manage_btn.style_context().add_class("menu-item");
+    manage_btn.set_halign(gtk::Align::Fill);
+    {
+        let gui = gui.clone();
+        let popover = popover.clone();
+        manage_btn.connect_clicked(move |_| {
+            popover.popdown();
+            show_snippet_editor(&gui);
+        });
+    }
+    vbox.pack_start(&gtk::Separator::new(gtk::Orientation::Horizontal), false, false, 4);
+    vbox.pack_start(&manage_btn, false, false, 0);
+
+    popover.add(&vbox);
+    discard_when_closed(&popover);
+    vbox.show_all();
+    popover.popup();
+}
+
+#[derive(Clone)]
+struct SnippetRow {
+    row: gtk::ListBoxRow,
+    name: gtk::Entry,
+    expression: gtk::Entry,
+}
+
+impl SnippetRow {
+    fn snippet(&self, plugins: &std::collections::HashMap<String, String>) -> Result<Snippet, String> {
+        let snippet = Snippet::new(self.name.text().trim(), self.expression.text().trim());
+        if snippet.name.is_empty() {
+            return Err(format!("\u{2018}{}\u{2019} needs a name", snippet.expression));
+        }
+        match domain::eval::parse_expression(&snippet.fill(1.0), plugins) {
+            Ok(tokens) if !tokens.is_empty() => Ok(snippet),
+            Ok(_) => Err(format!("{}: the expression is empty", snippet.name)),
+            Err(e) => Err(format!("{}: {}", snippet.name, e)),
+        }
+    }
+}
+
+fn show_snippet_editor(gui: &Gui) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some("Snippets"),
+        Some(&gui.window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[("Cancel", gtk::ResponseType::Cancel), ("Save", gtk::ResponseType::Accept)],
+    );
+    dialog.set_default_size(480, 400);
+
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    vbox.set_margin_top(12);
+    vbox.set_margin_bottom(12);
+    vbox.set_margin_start(16);
+    vbox.set_margin_end(16);
+
+    let hint = gtk::Label::new(Some(
+        "Name \u{00b7} expression. Names in the expression that are not functions, such as \
+         price or _, are filled with the value on the display.",
+    ));
+    hint.set_xalign(0.0);
+    hint.set_line_wrap(true);
+    hint.style_context().add_class("help-desc");
+
+    let list = gtk::ListBox::new();
+    list.set_selection_mode(gtk::SelectionMode::None);
+    let rows: Rc<RefCell<Vec<SnippetRow>>> = Rc::default();
+    let add_row = {
+        let list = list.clone();
+        let rows = rows.clone();
+        move |snippet: &Snippet| {
+            let row = SnippetRow {
+                row: gtk::ListBoxRow::new(),
+                name: gtk::Entry::new(),
+                expression: gtk::Entry::new(),
+            };
+            row.name.set_text(&snippet.name);
+            row.name.set_placeholder_text(Some("Name"));
+            row.name.set_width_chars(14);
+            row.expression.set_text(&snippet.expression);
+            row.expression.set_placeholder_text(Some("_ * 0.6214"));
+            let remove = gtk::Button::with_label("\u{2715}");
+            let hbox = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+            hbox.pack_start(&row.name, false, false, 0);
+            hbox.pack_start(&row.expression, true, true, 0);
+            hbox.pack_start(&remove, false, false, 0);
+            row.row.add(&hbox);
+            row.row.show_all();
+            list.add(&row.row);
+
+            let list = list.clone();
+            let rows_c = rows.clone();
+            let list_row = row.row.clone();
+            remove.connect_clicked(move |_| {
+                list.remove(&list_row);
+                rows_c.borrow_mut().retain(|r| r.row != list_row);
+            });
+            rows.borrow_mut().push(row);
+        }
+    };
+    for snippet in &gui.state.borrow().snippets {
+        add_row(snippet);
+    }
+
+    let scroll = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    scroll.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic);
+    scroll.set_vexpand(true);
+    scroll.add(&list);
+
+    let add_btn = gtk::Button::with_label("+ Add Snippet");
+    add_btn.set_halign(gtk::Align::Start);
+    add_btn.connect_clicked(move |_| add_row(&Snippet::new("", "")));
+
+    let status = gtk::Label::new(None);
+    status.set_xalign(0.0);
+    status.set_line_wrap(true);
+
+    vbox.pack_start(&hint, false, false, 0);
+    vbox.pack_start(&scroll, true, true, 0);
+    vbox.pack_start(&add_btn, false, false, 0);
+    vbox.pack_start(&status, false, false, 0);
+    dialog.content_area().pack_start(&vbox, true, true, 0);
+    dialog.show_all();
+
+    while dialog.run() == gtk::ResponseType::Accept {
+        let plugins = gui.state.borrow().config.plugins.functions.clone();
+        let edited = rows.borrow().iter().map(|r| r.snippet(&plugins)).collect::<Result<Vec<_>, _>>();
+        match edited.and_then(|s| services::snippets::save(&s).map(|()| s)) {
+            Ok(snippets) => {
+                dispatch(gui, Message::SetSnippets(snippets));
+                break;
+            }
+            Err(e) => status.set_text(&e),
+        }
+    }
+    dialog.close();
+}
+
+// ── Panel refresh helpers ────────────────────────────────────────────────────
+
+fn format_timestamp(ts: u64) -> String {
+    let secs = ts % 60;
+    let mins = (ts / 60) % 60;
+    let hours = (ts / 3600) % 24;
+    format!("{} {:02}:{:02}:{:02}", domain::search::weekday_name(ts), hours, mins, secs)
+}
+
+fn refresh_history(gui: &Gui) {
+    let list = &gui.history_list;
+    for child in list.children() {
+        list.remove(&child);
+    }
+    let s = gui.state.borrow();
+    #[cfg(feature = "tray")]
+    publish_tray_results(&s);
+    let search = s.history_search.as_str();
+    let show_timestamps = s.config.history.show_timestamps;
+    let filtered = domain::search::search_history(&s.engine().history, search);
+    let count = s.engine().history.len();
+
+    if filtered.is_empty() {
+        let msg = if search.trim().is_empty() {
+            "No calculations yet"
+        } else {
+            "No matching results"
+        };
+        let empty = gtk::Label::new(Some(msg));
+        empty.style_context().add_class("panel-empty");
+        list.pack_start(&empty, false, false, 0);
+    } else {
+        for (index, entry) in filtered {
+            let item = gtk::Box::new(gtk::Orientation::Vertical, 2);
+            item.style_context().add_class("panel-item");
+            item.set_margin_bottom(2);
+            item.set_tooltip_text(Some(&format!("ans{} \u{00b7} double-click to edit", count - index)));
+            // Double-clicking loads the expression back; "=" saves the edit
+            // as a new entry and this one stays as it was.
+            let row = gtk::EventBox::new();
+            row.add(&item);
+            {
+                let gui = gui.clone();
+                row.connect_button_press_event(move |_, event| {
+                    if event.button() != 1 || event.event_type() != gtk::gdk::EventType::DoubleButtonPress {
+                        return gtk::Inhibit(false);
+                    }
+                    dispatch(&gui, Message::EditHistory(index));
+                    gtk::Inhibit(true)
+                });
+            }
+
+            let header = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+            let star_btn = gtk::Button::with_label(if entry.starred { "\u{2605}" } else { "\u{2606}" });
+            star_btn.style_context().add_class("history-star");
+            if entry.starred {
+                star_btn.style_context().add_class("starred");
+            }
+            star_btn.set_relief(gtk::ReliefStyle::None);
+            star_btn.set_tooltip_text(Some(if entry.starred { "Unstar" } else { "Star (kept when clearing)" }));
+            {
+                let gui = gui.clone();
+                star_btn.connect_clicked(move |_| {
+                    dispatch(&gui, Message::ToggleStar(index));
+                });
+            }
+            header.pack_start(&star_btn, false, false, 0);
+
+            if show_timestamps && entry.timestamp > 0 {
+                let ts_lbl = gtk::Label::new(Some(&format_timestamp(entry.timestamp)));
+                ts_lbl.style_context().add_class("panel-item-label");
+                ts_lbl.set_xalign(0.0);
+                header.pack_start(&ts_lbl, true, true, 0);
+            }
+
+            let label_btn = gtk::Button::with_label("\u{270e}");
+            label_btn.style_context().add_class("history-star");
+            label_btn.set_relief(gtk::ReliefStyle::None);
+            label_btn.set_tooltip_text(Some("Label this entry"));
+            {
+                let gui = gui.clone();
+                let current = entry.label.clone().unwrap_or_default();
+                label_btn.connect_clicked(move |btn| {
+                    let popover = gtk::Popover::new(Some(btn));
+                    let label_entry = gtk::Entry::new();
+                    label_entry.set_placeholder_text(Some("Label, e.g. monthly budget"));
+                    label_entry.set_text(&current);
+                    label_entry.set_margin(6);
+                    popover.add(&label_entry);
+                    let gui = gui.clone();
+                    let popover_c = popover.clone();
+                    label_entry.connect_activate(move |e| {
+                        popover_c.popdown();
+                        dispatch(&gui, Message::LabelHistory(index, e.text().to_string()));
+                    });
+                    popover.show_all();
+                    popover.popup();
+                });
+            }
+            header.pack_end(&label_btn, false, false, 0);
+            item.pack_start(&header, false, false, 0);
+
+            if let Some(label) = &entry.label {
+                let name_lbl = gtk::Label::new(Some(label));
+                name_lbl.style_context().add_class("panel-item-label");
+                name_lbl.set_xalign(0.0);
+                name_lbl.set_ellipsize(gtk::pango::EllipsizeMode::End);
+                item.pack_start(&name_lbl, false, false, 0);
+            }
+
+            let expr_lbl = gtk::Label::new(Some(&entry.expression));
+            expr_lbl.style_context().add_class("panel-item-expr");
+            expr_lbl.set_xalign(1.0);
+            expr_lbl.set_ellipsize(gtk::pango::EllipsizeMode::End);
+
+            let res_lbl = gtk::Label::new(Some(&format!("= {}", entry.result_text)));
+            res_lbl.style_context().add_class("panel-item-result");
+            res_lbl.set_xalign(1.0);
+
+            item.pack_start(&expr_lbl, false, false, 0);
+            item.pack_start(&res_lbl, false, false, 0);
+            list.pack_start(&row, false, false, 0);
+        }
+    }
+    list.show_all();
+}
+
+fn refresh_memory(
+    memory_slots: &[domain::types::MemorySlot],
+    has_memory: bool,
+    list: &gtk::Box,
+) {
+    for child in list.children() {
+        list.remove(&child);
+    }
+    if has_memory {
+        let item = gtk::Box::new(gtk::Orientation::Vertical, 2);
+        item.style_context().add_class("panel-item");
+        item.set_margin_bottom(2);
+        let lbl = gtk::Label::new(Some("Quick Memory (M+/M-)"));
+        lbl.style_context().add_class("panel-item-label");
+        lbl.set_xalign(0.0);
+        item.pack_start(&lbl, false, false, 0);
+        list.pack_start(&item, false, false, 0);
+    }
+
+    if memory_slots.is_empty() && !has_memory {
+        let empty = gtk::Label::new(Some(
+            "No stored values\n\nPress S to store current value\nUse M+/M- in scientific mode",
+        ));
+        empty.style_context().add_class("panel-empty");
+        list.pack_start(&empty, false, false, 0);
+    } else {
+        for slot in memory_slots {
+            let item = gtk::Box::new(gtk::Orientation::Vertical, 2);
+            item.style_context().add_class("panel-item");
+            item.set_margin_bottom(2);
+
+            let lbl = gtk::Label::new(Some(&slot.label));
+            lbl.style_context().add_class("panel-item-label");
+            lbl.set_xalign(0.0);
+
+            let val = gtk::Label::new(Some(&domain::types::format_number_default(slot.value)));
+            val.style_context().add_class("panel-item-result");
+            val.set_xalign(1.0);
+
+            item.pack_start(&lbl, false, false, 0);
+            item.pack_start(&val, false, false, 0);
+            list.pack_start(&item, false, false, 0);
+        }
+    }
+    list.show_all();
+}
+
+fn refresh_pinned(pinned: &[domain::types::PinnedCalc], list: &gtk::Box) {
+    for child in list.children() {
+        list.remove(&child);
+    }
+    if pinned.is_empty() {
+        let empty = gtk::Label::new(Some("No pinned results\n\nPress Ctrl+S to pin"));
+        empty.style_context().add_class("panel-empty");
+        list.pack_start(&empty, false, false, 0);
+    } else {
+        for pin in pinned {
+            let item = gtk::Box::new(gtk::Orientation::Vertical, 2);
+            item.style_context().add_class("panel-item");
+            item.set_margin_bottom(2);
+
+            let lbl = gtk::Label::new(Some(&pin.label));
+            lbl.style_context().add_class("panel-item-label");
+            lbl.set_xalign(0.0);
+
+            let expr = gtk::Label::new(Some(&pin.expression));
+            expr.style_context().add_class("panel-item-expr");
+            expr.set_xalign(1.0);
+            expr.set_ellipsize(gtk::pango::EllipsizeMode::End);
+
+            let val = gtk::Label::new(Some(&format!(
+                "= {}",
+                domain::types::format_number_default(pin.result)
+            )));
+            val.style_context().add_class("panel-item-result");
+            val.set_xalign(1.0);
+
+            item.pack_start(&lbl, false, false, 0);
+            item.pack_start(&expr, false, false, 0);
+            item.pack_start(&val, false, false, 0);
+            list.pack_start(&item, false, false, 0);
+        }
+    }
+    list.show_all();
+}
@@ -167,6 +167,7 @@ pub struct LayoutConfig {
     pub show_scientific: bool,
     pub show_memory_row: bool,
     pub button_size: String,
+    pub zoom: f64,
 }
 
 impl Default for LayoutConfig {
@@ -179,6 +180,7 @@ impl Default for LayoutConfig {
             show_scientific: false,
             show_memory_row: true,
             button_size: "auto".into(),
+            zoom: 1.0,
         }
     }
 }
@@ -210,6 +212,9 @@ pub struct BehaviorConfig {
     pub operator_precedence: bool,
     pub angle_mode: String,
     pub percentage_behavior: String,
+    pub watch_clipboard: bool,
+    pub interval_arithmetic: bool,
+    pub money_mode: bool,
 }
 
 impl Default for BehaviorConfig {
@@ -219,6 +224,9 @@ impl Default for BehaviorConfig {
             operator_precedence: true,
             angle_mode: "degrees".into(),
             percentage_behavior: "divide_100".into(),
+            watch_clipboard: false,
+            interval_arithmetic: false,
+            money_mode: false,
         }
     }
 }
@@ -264,6 +272,9 @@ impl Default for InputConfig {
 pub struct FeedbackConfig {
     pub animations: bool,
     pub button_press_style: String,
+    pub click_sound: bool,
+    pub key_highlight: bool,
+    pub speak_results: bool,
 }
 
 impl Default for FeedbackConfig {
@@ -271,6 +282,9 @@ impl Default for FeedbackConfig {
         Self {
             animations: true,
             button_press_style: "instant".into(),
+            click_sound: false,
+            key_highlight: true,
+            speak_results: false,
         }
     }
 }
@@ -284,6 +298,8 @@ pub struct WindowConfig {
     pub compact_mode: bool,
     pub default_width: i32,
     pub default_height: i32,
+    pub summon_shortcut: String,
+    pub close_to_tray: bool,
 }
 
 impl Default for WindowConfig {
@@ -295,6 +311,8 @@ impl Default for WindowConfig {
             compact_mode: false,
             default_width: 400,
             default_height: 580,
+            summon_shortcut: String::new(),
+            close_to_tray: true,
         }
     }
 }
@@ -391,6 +409,9 @@ show_scientific = false
 show_memory_row = true
 # auto, small, large
 button_size = "auto"
+# Starting zoom for keypad and display text (0.5 to 2.5); Ctrl+= / Ctrl+-
+# change it while running, Ctrl+0 goes back to this
+zoom = 1.0
 
 # -- Number Formatting ---------------------------------------------
 [format]
@@ -414,6 +435,12 @@ operator_precedence = true
 angle_mode = "degrees"
 # divide_100 or of_previous
 percentage_behavior = "divide_100"
+# Offer the result of calculations you copy elsewhere, e.g. "12*(3+4)"
+watch_clipboard = false
+# Compute with [lo, hi] bounds that are guaranteed to hold the exact result
+interval_arithmetic = false
+# Show results to the cent (half to even) and flag cents lost to rounding
+money_mode = false
 
 # -- History -------------------------------------------------------
 [history]
@@ -438,6 +465,12 @@ expression_mode = "inline"
 animations = true
 # instant or animated
 button_press_style = "instant"
+# Play the sound theme's click for every key (needs canberra-gtk-play)
+click_sound = false
+# Flash the on-screen button when its key is typed
+key_highlight = true
+# Read each result (or error) aloud after "=" (needs speech-dispatcher's spd-say)
+speak_results = false
 
 # -- Window --------------------------------------------------------
 [window]
@@ -450,6 +483,12 @@ remember_geometry = false
 compact_mode = false
 default_width = 400
 default_height = 580
+# Global shortcut that pops the calculator up near the pointer, e.g.
+# "Super+c" (empty = off). Escape sends it away again.
+summon_shortcut = ""
+# With the tray icon (built with the "tray" feature), closing the window
+# hides it to the tray instead of quitting.
+close_to_tray = true
 
 # -- Plugins -------------------------------------------------------
 # Custom functions: name = "expression using x"
@@ -1,9 +1,46 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::domain::types::HistoryEntry;
 use crate::services::config;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub const ALL: &'static [ExportFormat] = &[Self::Csv, Self::Json, Self::Markdown];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::Json => "JSON",
+            Self::Markdown => "Markdown",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Markdown => "md",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            "md" | "markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+
 pub fn history_path() -> PathBuf {
     config::dir().join("history.json")
 }
@@ -29,27 +66,110 @@ pub fn load_history(auto_save: bool) -> Vec<HistoryEntry> {
     }
 }
 
+pub fn render_export(history: &[HistoryEntry], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(history).unwrap_or_else(|_| "[]".into()),
+        ExportFormat::Csv => {
+            let mut s = String::from("expression,result,timestamp\n");
+            for entry in history {
+                s.push_str(&format!(
+                    "{},{},{}\n",
+                    csv_field(&entry.expression),
+                    csv_field(&entry.result_text),
+                    entry.timestamp
+                ));
+            }
+            s
+        }
+        ExportFormat::Markdown => {
+            let mut s = String::from("| Expression | Result |\n| --- | ---: |\n");
+            for entry in history {
+                s.push_str(&format!(
+                    "| {} | {} |\n",
+                    markdown_cell(&entry.expression),
+                    markdown_cell(&entry.result_text)
+                ));
+            }
+            s
+        }
+    }
+}
+
+pub fn export_history_to(history: &[HistoryEntry], path: &Path, format: ExportFormat) -> Result<(), String> {
+    fs::write(path, render_export(history, format)).map_err(|e| e.to_string())
+}
+
 pub fn export_history_json(history: &[HistoryEntry]) -> PathBuf {
     let _ = fs::create_dir_all(config::dir());
     let p = config::dir().join("history_export.json");
-    if let Ok(json) = serde_json::to_string_pretty(history) {
-        let _ = fs::write(&p, json);
-    }
+    let _ = export_history_to(history, &p, ExportFormat::Json);
     p
 }
 
 pub fn export_history_csv(history: &[HistoryEntry]) -> PathBuf {
     let _ = fs::create_dir_all(config::dir());
     let p = config::dir().join("history_export.csv");
-    let mut s = String::from("expression,result,timestamp\n");
-    for entry in history {
-        s.push_str(&format!(
-            "\"{}\",{},{}\n",
-            entry.expression.replace('"', "\"\""),
-            entry.result_text,
-            entry.timestamp
-        ));
-    }
-    let _ = fs::write(&p, s);
+    let _ = export_history_to(history, &p, ExportFormat::Csv);
     p
 }
+
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(expression: &str, result_text: &str) -> HistoryEntry {
+        HistoryEntry {
+            expression: expression.into(),
+            result_text: result_text.into(),
+            timestamp: 7,
+            session: 1,
+            ..HistoryEntry::default()
+        }
+    }
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(ExportFormat::from_path(Path::new("a.csv")), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::from_path(Path::new("a.JSON")), Some(ExportFormat::Json));
+        assert_eq!(ExportFormat::from_path(Path::new("a.md")), Some(ExportFormat::Markdown));
+        assert_eq!(ExportFormat::from_path(Path::new("a.txt")), None);
+        assert_eq!(ExportFormat::from_path(Path::new("noext")), None);
+    }
+
+    #[test]
+    fn csv_quotes_only_when_needed() {
+        let out = render_export(&[entry("1+1", "2"), entry("say \"x\", y", "1,000")], ExportFormat::Csv);
+        assert_eq!(
+            out,
+            "expression,result,timestamp\n1+1,2,7\n\"say \"\"x\"\", y\",\"1,000\",7\n"
+        );
+    }
+
+    #[test]
+    fn markdown_escapes_pipes() {
+        let out = render_export(&[entry("a|b", "3")], ExportFormat::Markdown);
+        assert!(out.starts_with("| Expression | Result |\n| --- | ---: |\n"));
+        assert!(out.contains("| a\\|b | 3 |"));
+    }
+
+    #[test]
+    fn json_roundtrips() {
+        let out = render_export(&[entry("2\u{00d7}3", "6")], ExportFormat::Json);
+        let back: Vec<HistoryEntry> = serde_json::from_str(&out).unwrap();
+        assert_eq!(back[0].expression, "2\u{00d7}3");
+    }
+}
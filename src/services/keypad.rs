@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::services::config;
+use crate::ui::layout::{ButtonAction, KeySpec, Keypad};
+
+#[derive(Serialize, Deserialize)]
+struct KeypadFile {
+    #[serde(default)]
+    basic: Vec<KeyEntry>,
+    #[serde(default)]
+    scientific: Vec<KeyEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyEntry {
+    label: String,
+    action: String,
+    col: usize,
+    row: usize,
+    #[serde(default = "one")]
+    span: usize,
+}
+
+fn one() -> usize {
+    1
+}
+
+impl KeyEntry {
+    fn from_spec(spec: &KeySpec) -> Self {
+        Self {
+            label: spec.label.to_string(),
+            action: spec.action.code().to_string(),
+            col: spec.col,
+            row: spec.row,
+            span: spec.span,
+        }
+    }
+
+    fn into_spec(self) -> Result<KeySpec, String> {
+        let action = ButtonAction::from_code(&self.action)?;
+        Ok(KeySpec::custom(self.label, action, self.col, self.row, self.span))
+    }
+}
+
+pub fn path() -> PathBuf {
+    config::dir().join("keypad.toml")
+}
+
+pub fn to_toml(keypad: &Keypad) -> Result<String, String> {
+    let file = KeypadFile {
+        basic: keypad.basic.iter().map(KeyEntry::from_spec).collect(),
+        scientific: keypad.scientific.iter().map(KeyEntry::from_spec).collect(),
+    };
+    toml::to_string_pretty(&file).map_err(|e| e.to_string())
+}
+
+pub fn from_toml(contents: &str) -> Result<Keypad, String> {
+    let file: KeypadFile = toml::from_str(contents).map_err(|e| e.to_string())?;
+    let convert = |entries: Vec<KeyEntry>| entries.into_iter().map(KeyEntry::into_spec).collect::<Result<Vec<_>, _>>();
+    let keypad = Keypad { basic: convert(file.basic)?, scientific: convert(file.scientific)? };
+    keypad.validate()?;
+    Ok(keypad)
+}
+
+/// The user's layout, or the built-in one if there is none or it is broken.
+pub fn load() -> Keypad {
+    match fs::read_to_string(path()) {
+        Ok(contents) => from_toml(&contents).unwrap_or_else(|e| {
+            eprintln!("Ignoring {}: {}", path().display(), e);
+            Keypad::default()
+        }),
+        Err(_) => Keypad::default(),
+    }
+}
+
+pub fn save(keypad: &Keypad) -> Result<(), String> {
+    keypad.validate()?;
+    let contents = to_toml(keypad)?;
+    fs::create_dir_all(config::dir()).map_err(|e| e.to_string())?;
+    fs::write(path(), contents).map_err(|e| e.to_string())
+}
+
+/// Drops the saved layout so the built-in one is used again.
+pub fn reset() -> Result<(), String> {
+    match fs::remove_file(path()) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_keypad_round_trips() {
+        let keypad = Keypad::default();
+        let text = to_toml(&keypad).unwrap();
+        assert_eq!(from_toml(&text).unwrap(), keypad);
+    }
+
+    #[test]
+    fn parses_hand_written_layout() {
+        let text = r#"
+[[basic]]
+label = "τ"
+action = "pi"
+col = 0
+row = 0
+
+[[basic]]
+label = "="
+action = "equals"
+col = 1
+row = 0
+span = 2
+"#;
+        let keypad = from_toml(text).unwrap();
+        assert!(keypad.scientific.is_empty());
+        assert_eq!(keypad.basic[0].label, "τ");
+        assert_eq!(keypad.basic[0].class, "constant-button");
+        assert_eq!(keypad.basic[1].span, 2);
+    }
+
+    #[test]
+    fn rejects_unknown_actions_and_overlaps() {
+        let unknown = "[[basic]]\nlabel = \"?\"\naction = \"launch\"\ncol = 0\nrow = 0\n";
+        assert!(from_toml(unknown).unwrap_err().contains("launch"));
+
+        let overlap = "[[scientific]]\nlabel = \"a\"\naction = \"sin\"\ncol = 0\nrow = 0\nspan = 2\n\
+                       [[scientific]]\nlabel = \"b\"\naction = \"cos\"\ncol = 1\nrow = 0\n";
+        assert!(from_toml(overlap).unwrap_err().starts_with("scientific:"));
+
+        let no_basic = "[[scientific]]\nlabel = \"sin\"\naction = \"sin\"\ncol = 0\nrow = 0\n";
+        assert!(from_toml(no_basic).unwrap_err().starts_with("basic:"));
+    }
+}
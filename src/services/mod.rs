@@ -1,4 +1,23 @@
+// Parts of these are only reachable from the GTK frontend; the allows keep
+// headless builds quiet about them without hiding dead code elsewhere.
 pub mod config;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 pub mod history;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub mod keypad;
+#[cfg(feature = "gui")]
+pub mod portal;
+#[cfg(feature = "tray")]
+pub mod tray;
+#[cfg(feature = "gui")]
+pub mod resources;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 pub mod session;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub mod snippets;
+#[cfg(feature = "gui")]
+pub mod sound;
+pub mod speech;
+#[cfg(feature = "gui")]
 pub mod theme;
+pub mod worker;
@@ -0,0 +1,175 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use gtk::gio;
+use gtk::glib;
+
+const BUS_NAME: &str = "org.freedesktop.portal.Desktop";
+const OBJECT_PATH: &str = "/org/freedesktop/portal/desktop";
+const SHORTCUTS: &str = "org.freedesktop.portal.GlobalShortcuts";
+const REQUEST: &str = "org.freedesktop.portal.Request";
+
+/// Converts a keymap-style combo (`Ctrl+Alt+c`) to the trigger format the
+/// portal expects (`CTRL+ALT+c`).
+pub fn portal_trigger(combo: &str) -> Result<String, String> {
+    let parts: Vec<&str> = combo.split('+').map(str::trim).collect();
+    let (key, modifiers) = match parts.split_last() {
+        Some((key, modifiers)) if !key.is_empty() => (key, modifiers),
+        _ => return Err(format!("No key in shortcut '{}'", combo)),
+    };
+    if !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("Unsupported key '{}' in shortcut '{}'", key, combo));
+    }
+    let mut out = Vec::new();
+    for modifier in modifiers {
+        let name = match modifier.to_lowercase().as_str() {
+            "ctrl" | "control" => "CTRL",
+            "alt" => "ALT",
+            "shift" => "SHIFT",
+            "super" | "logo" | "meta" => "LOGO",
+            _ => return Err(format!("Unknown modifier '{}' in shortcut '{}'", modifier, combo)),
+        };
+        out.push(name.to_string());
+    }
+    if out.is_empty() {
+        return Err(format!("Shortcut '{}' needs a modifier", combo));
+    }
+    out.push(key.to_string());
+    Ok(out.join("+"))
+}
+
+/// Where the portal reports the outcome of a call made with `token`.
+fn request_path(unique_name: &str, token: &str) -> String {
+    let sender = unique_name.trim_start_matches(':').replace('.', "_");
+    format!("{}/request/{}/{}", OBJECT_PATH, sender, token)
+}
+
+// Calls a portal method and hands the request's results to `done` once the
+// portal answers. Responses other than 0 mean the user cancelled or the call
+// failed.
+fn portal_request(
+    bus: &gio::DBusConnection,
+    method: &str,
+    token: &str,
+    params: glib::Variant,
+    done: impl FnOnce(HashMap<String, glib::Variant>) + 'static,
+) {
+    let Some(unique_name) = bus.unique_name() else { return };
+    let path = request_path(&unique_name, token);
+    let done = Rc::new(RefCell::new(Some(done)));
+    let subscription = Rc::new(Cell::new(None));
+    let id = {
+        let subscription = subscription.clone();
+        let method = method.to_string();
+        bus.signal_subscribe(
+            Some(BUS_NAME),
+            Some(REQUEST),
+            Some("Response"),
+            Some(&path),
+            None,
+            gio::DBusSignalFlags::NONE,
+            move |bus, _, _, _, _, params| {
+                if let Some(id) = subscription.take() {
+                    bus.signal_unsubscribe(id);
+                }
+                match params.get::<(u32, HashMap<String, glib::Variant>)>() {
+                    Some((0, results)) => {
+                        if let Some(done) = done.borrow_mut().take() {
+                            done(results);
+                        }
+                    }
+                    _ => eprintln!("fredulator: global shortcut {} was not granted", method),
+                }
+            },
+        )
+    };
+    subscription.set(Some(id));
+    let method_name = method.to_string();
+    bus.call(
+        Some(BUS_NAME),
+        OBJECT_PATH,
+        SHORTCUTS,
+        method,
+        Some(&params),
+        None,
+        gio::DBusCallFlags::NONE,
+        -1,
+        None::<&gio::Cancellable>,
+        move |reply| {
+            if let Err(e) = reply {
+                eprintln!("fredulator: {} failed: {}", method_name, e);
+            }
+        },
+    );
+}
+
+/// Registers `combo` through the desktop portal's GlobalShortcuts interface,
+/// the only way to get a system-wide key on Wayland, and calls `on_activate`
+/// with the event timestamp each time it is pressed. The portal asks the user
+/// to confirm the binding the first time and may settle on a different key.
+pub fn bind_global_shortcut(
+    id: &'static str,
+    description: &'static str,
+    combo: &str,
+    on_activate: impl Fn(u32) + 'static,
+) -> Result<(), String> {
+    let trigger = portal_trigger(combo)?;
+    let bus = gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>).map_err(|e| e.to_string())?;
+
+    let params = glib::Variant::parse(
+        None,
+        "({'handle_token': <'fredulator_session'>, 'session_handle_token': <'fredulator'>},)",
+    )
+    .map_err(|e| e.to_string())?;
+    let bus_c = bus.clone();
+    portal_request(&bus, "CreateSession", "fredulator_session", params, move |results| {
+        let Some(session) = results.get("session_handle").and_then(|v| v.str().map(str::to_string)) else { return };
+        bus_c.signal_subscribe(
+            Some(BUS_NAME),
+            Some(SHORTCUTS),
+            Some("Activated"),
+            Some(OBJECT_PATH),
+            Some(&session),
+            gio::DBusSignalFlags::NONE,
+            move |_, _, _, _, _, params| {
+                if params.try_child_get::<String>(1).ok().flatten().as_deref() == Some(id) {
+                    let timestamp = params.try_child_get::<u64>(2).ok().flatten().unwrap_or(0);
+                    on_activate(timestamp as u32);
+                }
+            },
+        );
+        let text = format!(
+            "(objectpath '{}', [('{}', {{'description': <'{}'>, 'preferred_trigger': <'{}'>}})], '', {{'handle_token': <'fredulator_bind'>}})",
+            session, id, description, trigger
+        );
+        match glib::Variant::parse(None, &text) {
+            Ok(params) => portal_request(&bus_c, "BindShortcuts", "fredulator_bind", params, |_| {}),
+            Err(e) => eprintln!("fredulator: cannot bind global shortcut: {}", e),
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triggers_use_portal_modifier_names() {
+        assert_eq!(portal_trigger("Ctrl+Alt+c").as_deref(), Ok("CTRL+ALT+c"));
+        assert_eq!(portal_trigger("super + space").as_deref(), Ok("LOGO+space"));
+        assert!(portal_trigger("c").is_err());
+        assert!(portal_trigger("Ctrl+").is_err());
+        assert!(portal_trigger("Hyper+c").is_err());
+        assert!(portal_trigger("Ctrl+'").is_err());
+    }
+
+    #[test]
+    fn request_path_from_sender() {
+        assert_eq!(
+            request_path(":1.42", "fredulator_bind"),
+            "/org/freedesktop/portal/desktop/request/1_42/fredulator_bind"
+        );
+    }
+}
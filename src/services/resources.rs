@@ -0,0 +1,80 @@
+use gtk::prelude::*;
+use gtk::gio;
+use gtk::glib;
+
+/// Path prefix every bundled asset lives under, matching
+/// `data/fredulator.gresource.xml`.
+pub const PREFIX: &str = "/io/github/fredrir/fredulator";
+
+pub const ICON_NAME: &str = "fredulator";
+
+/// Registers the resource bundle compiled by `build.rs`. Must run before
+/// anything looks up a resource path.
+pub fn register() -> Result<(), glib::Error> {
+    gio::resources_register_include!("fredulator.gresource")
+}
+
+pub fn lookup_text(path: &str) -> Option<String> {
+    let full = format!("{}/{}", PREFIX, path);
+    match gio::resources_lookup_data(&full, gio::ResourceLookupFlags::NONE) {
+        Ok(bytes) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+        Err(e) => {
+            eprintln!("Missing resource {}: {}", full, e);
+            None
+        }
+    }
+}
+
+/// Makes the bundled icon resolvable by name, so the window has an icon
+/// even when nothing is installed under /usr/share/icons.
+pub fn install_icons() {
+    if let Some(icon_theme) = gtk::IconTheme::default() {
+        icon_theme.add_resource_path(&format!("{}/icons", PREFIX));
+    }
+    gtk::Window::set_default_icon_name(ICON_NAME);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::theme::Theme;
+    use std::path::Path;
+
+    const MANIFEST: &str = include_str!("../../data/fredulator.gresource.xml");
+
+    fn bundled_files() -> Vec<&'static str> {
+        MANIFEST
+            .split("<file>")
+            .skip(1)
+            .filter_map(|s| s.split_once("</file>").map(|(name, _)| name))
+            .collect()
+    }
+
+    #[test]
+    fn manifest_files_exist() {
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("data");
+        let files = bundled_files();
+        assert!(!files.is_empty());
+        for file in files {
+            assert!(data.join(file).is_file(), "{} is listed but missing", file);
+        }
+    }
+
+    #[test]
+    fn manifest_prefix_matches() {
+        assert!(MANIFEST.contains(&format!("prefix=\"{}\"", PREFIX)));
+    }
+
+    #[test]
+    fn every_theme_stylesheet_is_bundled() {
+        let files = bundled_files();
+        assert!(files.contains(&"styles/base.css"));
+        for theme in Theme::ALL {
+            if let Some(sheet) = theme.stylesheet() {
+                assert!(files.contains(&sheet), "{} not bundled", sheet);
+            }
+        }
+        let icon = format!("icons/scalable/apps/{}.svg", ICON_NAME);
+        assert!(files.contains(&icon.as_str()));
+    }
+}
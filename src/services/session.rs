@@ -10,6 +10,8 @@ pub struct SessionState {
     pub tabs: Vec<TabState>,
     pub active_tab: usize,
     pub scientific_mode: bool,
+    #[serde(default)]
+    pub worksheet: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize)]
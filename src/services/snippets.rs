@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::domain::snippet::{self, Snippet};
+use crate::services::config;
+
+#[derive(Serialize, Deserialize)]
+struct SnippetsFile {
+    #[serde(default)]
+    snippet: Vec<Snippet>,
+}
+
+pub fn path() -> PathBuf {
+    config::dir().join("snippets.toml")
+}
+
+pub fn to_toml(snippets: &[Snippet]) -> Result<String, String> {
+    toml::to_string_pretty(&SnippetsFile { snippet: snippets.to_vec() }).map_err(|e| e.to_string())
+}
+
+pub fn from_toml(contents: &str) -> Result<Vec<Snippet>, String> {
+    let file: SnippetsFile = toml::from_str(contents).map_err(|e| e.to_string())?;
+    Ok(file.snippet)
+}
+
+/// The saved snippets, or the examples if nothing has been saved yet.
+pub fn load() -> Vec<Snippet> {
+    match fs::read_to_string(path()) {
+        Ok(contents) => from_toml(&contents).unwrap_or_else(|e| {
+            eprintln!("Ignoring {}: {}", path().display(), e);
+            Vec::new()
+        }),
+        Err(_) => snippet::defaults(),
+    }
+}
+
+pub fn save(snippets: &[Snippet]) -> Result<(), String> {
+    let contents = to_toml(snippets)?;
+    fs::create_dir_all(config::dir()).map_err(|e| e.to_string())?;
+    fs::write(path(), contents).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippets_round_trip() {
+        let snippets = snippet::defaults();
+        assert_eq!(from_toml(&to_toml(&snippets).unwrap()).unwrap(), snippets);
+        assert_eq!(to_toml(&[]).map(|t| from_toml(&t)), Ok(Ok(Vec::new())));
+    }
+
+    #[test]
+    fn parses_hand_written_snippets() {
+        let text = r#"
+[[snippet]]
+name = "Tip"
+expression = "bill * 0.18"
+"#;
+        assert_eq!(from_toml(text).unwrap(), [Snippet::new("Tip", "bill * 0.18")]);
+    }
+}
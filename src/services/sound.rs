@@ -0,0 +1,24 @@
+use std::process::{Command, Stdio};
+use std::sync::mpsc::Sender;
+use std::sync::OnceLock;
+
+use super::worker;
+
+static QUEUE: OnceLock<Sender<()>> = OnceLock::new();
+
+/// Plays the sound theme's "button-pressed" event through libcanberra's
+/// player, off the UI thread. One worker plays at a time; presses that come
+/// in while it plays collapse into a single click. Nothing happens when the
+/// player is missing.
+pub fn click() {
+    let queue = QUEUE.get_or_init(|| {
+        worker::spawn_latest(|()| {
+            let _ = Command::new("canberra-gtk-play")
+                .args(["--id", "button-pressed", "--description", "Fredulator key"])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        })
+    });
+    let _ = queue.send(());
+}
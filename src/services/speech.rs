@@ -0,0 +1,23 @@
+use std::process::{Command, Stdio};
+use std::sync::mpsc::Sender;
+use std::sync::OnceLock;
+
+use super::worker;
+
+static QUEUE: OnceLock<Sender<String>> = OnceLock::new();
+
+/// Speaks `text` through speech-dispatcher's `spd-say`, off the UI thread.
+/// One worker speaks at a time and skips to the newest text when several
+/// results come in while it talks. Nothing happens when `spd-say` is missing.
+pub fn say(text: &str) {
+    let queue = QUEUE.get_or_init(|| {
+        worker::spawn_latest(|text: String| {
+            let _ = Command::new("spd-say")
+                .args(["--wait", "--", &text])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        })
+    });
+    let _ = queue.send(text.to_string());
+}
@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use gtk::gio;
+use gtk::glib::{self, ToVariant};
+
+use super::resources::ICON_NAME;
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+const ITEM_IFACE: &str = "org.kde.StatusNotifierItem";
+const MENU_IFACE: &str = "com.canonical.dbusmenu";
+
+/// How many recent results the menu lists.
+pub const MAX_RESULTS: usize = 5;
+
+const EMPTY_ID: i32 = 90;
+const SEPARATOR_ID: i32 = 99;
+const SHOW_ID: i32 = 100;
+const QUIT_ID: i32 = 101;
+
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="org.kde.StatusNotifierItem">
+    <property name="Category" type="s" access="read"/>
+    <property name="Id" type="s" access="read"/>
+    <property name="Title" type="s" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="IconName" type="s" access="read"/>
+    <property name="ToolTip" type="(sa(iiay)ss)" access="read"/>
+    <property name="ItemIsMenu" type="b" access="read"/>
+    <property name="Menu" type="o" access="read"/>
+    <method name="Activate"><arg type="i" direction="in"/><arg type="i" direction="in"/></method>
+    <method name="SecondaryActivate"><arg type="i" direction="in"/><arg type="i" direction="in"/></method>
+    <method name="ContextMenu"><arg type="i" direction="in"/><arg type="i" direction="in"/></method>
+    <method name="Scroll"><arg type="i" direction="in"/><arg type="s" direction="in"/></method>
+    <signal name="NewToolTip"/>
+  </interface>
+  <interface name="com.canonical.dbusmenu">
+    <property name="Version" type="u" access="read"/>
+    <property name="TextDirection" type="s" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="IconThemePath" type="as" access="read"/>
+    <method name="GetLayout">
+      <arg type="i" direction="in"/><arg type="i" direction="in"/><arg type="as" direction="in"/>
+      <arg type="u" direction="out"/><arg type="(ia{sv}av)" direction="out"/>
+    </method>
+    <method name="GetGroupProperties">
+      <arg type="ai" direction="in"/><arg type="as" direction="in"/>
+      <arg type="a(ia{sv})" direction="out"/>
+    </method>
+    <method name="GetProperty">
+      <arg type="i" direction="in"/><arg type="s" direction="in"/><arg type="v" direction="out"/>
+    </method>
+    <method name="Event">
+      <arg type="i" direction="in"/><arg type="s" direction="in"/><arg type="v" direction="in"/><arg type="u" direction="in"/>
+    </method>
+    <method name="EventGroup">
+      <arg type="a(isvu)" direction="in"/><arg type="ai" direction="out"/>
+    </method>
+    <method name="AboutToShow">
+      <arg type="i" direction="in"/><arg type="b" direction="out"/>
+    </method>
+    <method name="AboutToShowGroup">
+      <arg type="ai" direction="in"/><arg type="ai" direction="out"/><arg type="ai" direction="out"/>
+    </method>
+    <signal name="LayoutUpdated"><arg type="u"/><arg type="i"/></signal>
+    <signal name="ItemsPropertiesUpdated"><arg type="a(ia{sv})"/><arg type="a(ias)"/></signal>
+  </interface>
+</node>
+"#;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrayEvent {
+    Show,
+    Copy(String),
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct MenuItem {
+    id: i32,
+    /// `None` for a separator.
+    label: Option<String>,
+    enabled: bool,
+}
+
+impl MenuItem {
+    fn new(id: i32, label: String) -> Self {
+        Self { id, label: Some(label), enabled: true }
+    }
+}
+
+/// The tray menu: recent results (newest first), then the app items.
+fn menu_items(results: &[String]) -> Vec<MenuItem> {
+    let mut items: Vec<MenuItem> = results
+        .iter()
+        .take(MAX_RESULTS)
+        .enumerate()
+        .map(|(i, result)| MenuItem::new(i as i32 + 1, result.clone()))
+        .collect();
+    if items.is_empty() {
+        items.push(MenuItem { enabled: false, ..MenuItem::new(EMPTY_ID, "No results yet".into()) });
+    }
+    items.push(MenuItem { id: SEPARATOR_ID, label: None, enabled: true });
+    items.push(MenuItem::new(SHOW_ID, "Show calculator".into()));
+    items.push(MenuItem::new(QUIT_ID, "Quit".into()));
+    items
+}
+
+fn event_for(id: i32, results: &[String]) -> Option<TrayEvent> {
+    match id {
+        SHOW_ID => Some(TrayEvent::Show),
+        QUIT_ID => Some(TrayEvent::Quit),
+        _ if (1..=MAX_RESULTS as i32).contains(&id) => results.get(id as usize - 1).cloned().map(TrayEvent::Copy),
+        _ => None,
+    }
+}
+
+fn properties(item: &MenuItem) -> HashMap<String, glib::Variant> {
+    let mut props = HashMap::new();
+    match &item.label {
+        // Underscores mark mnemonics in dbusmenu labels.
+        Some(label) => props.insert("label".to_string(), label.replace('_', "__").to_variant()),
+        None => props.insert("type".to_string(), "separator".to_variant()),
+    };
+    if !item.enabled {
+        props.insert("enabled".to_string(), false.to_variant());
+    }
+    props
+}
+
+fn layout(items: &[MenuItem]) -> glib::Variant {
+    let children: Vec<glib::Variant> = items
+        .iter()
+        .map(|item| {
+            glib::Variant::tuple_from_iter([
+                item.id.to_variant(),
+                properties(item).to_variant(),
+                Vec::<glib::Variant>::new().to_variant(),
+            ])
+        })
+        .collect();
+    let mut root = HashMap::new();
+    root.insert("children-display".to_string(), "submenu".to_variant());
+    glib::Variant::tuple_from_iter([0i32.to_variant(), root.to_variant(), children.to_variant()])
+}
+
+struct Shared {
+    results: Mutex<Vec<String>>,
+    events: Mutex<glib::Sender<TrayEvent>>,
+    revision: AtomicU32,
+    hosted: AtomicBool,
+}
+
+impl Shared {
+    fn results(&self) -> Vec<String> {
+        self.results.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    fn send(&self, event: TrayEvent) {
+        if let Ok(events) = self.events.lock() {
+            let _ = events.send(event);
+        }
+    }
+
+    fn tooltip(&self) -> glib::Variant {
+        let text = self.results().first().cloned().unwrap_or_default();
+        glib::Variant::tuple_from_iter([
+            ICON_NAME.to_variant(),
+            Vec::<(i32, i32, Vec<u8>)>::new().to_variant(),
+            "Fredulator".to_variant(),
+            text.to_variant(),
+        ])
+    }
+
+    fn item_property(&self, name: &str) -> glib::Variant {
+        match name {
+            "Category" => "ApplicationStatus".to_variant(),
+            "Id" => "fredulator".to_variant(),
+            "Title" => "Fredulator".to_variant(),
+            "Status" => "Active".to_variant(),
+            "IconName" => ICON_NAME.to_variant(),
+            "ToolTip" => self.tooltip(),
+            "ItemIsMenu" => false.to_variant(),
+            "Menu" => glib::Variant::parse(None, &format!("objectpath '{}'", MENU_PATH)).unwrap_or_else(|_| "".to_variant()),
+            _ => "".to_variant(),
+        }
+    }
+
+    fn menu_property(&self, name: &str) -> glib::Variant {
+        match name {
+            "Version" => 3u32.to_variant(),
+            "TextDirection" => "ltr".to_variant(),
+            "Status" => "normal".to_variant(),
+            _ => Vec::<String>::new().to_variant(),
+        }
+    }
+
+    fn item_call(&self, method: &str, invocation: gio::DBusMethodInvocation) {
+        if matches!(method, "Activate" | "SecondaryActivate") {
+            self.send(TrayEvent::Show);
+        }
+        invocation.return_value(None);
+    }
+
+    fn menu_call(&self, method: &str, params: &glib::Variant, invocation: gio::DBusMethodInvocation) {
+        let items = menu_items(&self.results());
+        let reply = match method {
+            "GetLayout" => glib::Variant::tuple_from_iter([self.revision.load(Ordering::SeqCst).to_variant(), layout(&items)]),
+            "GetGroupProperties" => {
+                let ids = params.try_child_get::<Vec<i32>>(0).ok().flatten().unwrap_or_default();
+                let found: Vec<(i32, HashMap<String, glib::Variant>)> = items
+                    .iter()
+                    .filter(|item| ids.is_empty() || ids.contains(&item.id))
+                    .map(|item| (item.id, properties(item)))
+                    .collect();
+                (found,).to_variant()
+            }
+            "GetProperty" => {
+                let id = params.try_child_get::<i32>(0).ok().flatten();
+                let name = params.try_child_get::<String>(1).ok().flatten().unwrap_or_default();
+                let value = items
+                    .iter()
+                    .find(|item| Some(item.id) == id)
+                    .and_then(|item| properties(item).remove(&name));
+                match value {
+                    Some(value) => glib::Variant::tuple_from_iter([glib::Variant::from_variant(&value)]),
+                    None => {
+                        invocation.return_dbus_error("com.canonical.dbusmenu.Error", "No such property");
+                        return;
+                    }
+                }
+            }
+            "Event" => {
+                let id = params.try_child_get::<i32>(0).ok().flatten();
+                let kind = params.try_child_get::<String>(1).ok().flatten();
+                if let (Some(id), Some("clicked")) = (id, kind.as_deref()) {
+                    if let Some(event) = event_for(id, &self.results()) {
+                        self.send(event);
+                    }
+                }
+                invocation.return_value(None);
+                return;
+            }
+            "EventGroup" => {
+                let events = params.try_child_get::<Vec<(i32, String, glib::Variant, u32)>>(0).ok().flatten().unwrap_or_default();
+                for (id, kind, _, _) in events {
+                    if kind == "clicked" {
+                        if let Some(event) = event_for(id, &self.results()) {
+                            self.send(event);
+                        }
+                    }
+                }
+                (Vec::<i32>::new(),).to_variant()
+            }
+            "AboutToShow" => (false,).to_variant(),
+            "AboutToShowGroup" => (Vec::<i32>::new(), Vec::<i32>::new()).to_variant(),
+            _ => {
+                invocation.return_dbus_error("org.freedesktop.DBus.Error.UnknownMethod", method);
+                return;
+            }
+        };
+        invocation.return_value(Some(&reply));
+    }
+}
+
+struct Tray {
+    bus: gio::DBusConnection,
+    shared: Arc<Shared>,
+}
+
+static TRAY: OnceLock<Tray> = OnceLock::new();
+
+fn register(
+    bus: &gio::DBusConnection,
+    node: &gio::DBusNodeInfo,
+    path: &str,
+    iface: &str,
+    shared: &Arc<Shared>,
+) -> Result<(), String> {
+    let info = node.lookup_interface(iface).ok_or_else(|| format!("No interface {}", iface))?;
+    let call = shared.clone();
+    let get = shared.clone();
+    let is_menu = iface == MENU_IFACE;
+    bus.register_object(
+        path,
+        &info,
+        move |_, _, _, _, method, params, invocation| {
+            if is_menu {
+                call.menu_call(method, &params, invocation);
+            } else {
+                call.item_call(method, invocation);
+            }
+        },
+        move |_, _, _, _, name| if is_menu { get.menu_property(name) } else { get.item_property(name) },
+        |_, _, _, _, _, _| false,
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+/// Puts a StatusNotifierItem icon in the panel. Its menu lists the latest
+/// results and app actions; choosing one sends a `TrayEvent` to `events`.
+/// Desktops without a tray host simply never show it, see `is_hosted`.
+pub fn start(events: glib::Sender<TrayEvent>) -> Result<(), String> {
+    let bus = gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>).map_err(|e| e.to_string())?;
+    let node = gio::DBusNodeInfo::for_xml(INTROSPECTION).map_err(|e| e.to_string())?;
+    let shared = Arc::new(Shared {
+        results: Mutex::new(Vec::new()),
+        events: Mutex::new(events),
+        revision: AtomicU32::new(1),
+        hosted: AtomicBool::new(false),
+    });
+    register(&bus, &node, ITEM_PATH, ITEM_IFACE, &shared)?;
+    register(&bus, &node, MENU_PATH, MENU_IFACE, &shared)?;
+
+    let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+    let hosted = shared.clone();
+    gio::bus_own_name_on_connection(
+        &bus,
+        &name,
+        gio::BusNameOwnerFlags::NONE,
+        move |bus, name| {
+            let hosted = hosted.clone();
+            bus.call(
+                Some("org.kde.StatusNotifierWatcher"),
+                "/StatusNotifierWatcher",
+                "org.kde.StatusNotifierWatcher",
+                "RegisterStatusNotifierItem",
+                Some(&(name,).to_variant()),
+                None,
+                gio::DBusCallFlags::NONE,
+                -1,
+                None::<&gio::Cancellable>,
+                move |reply| match reply {
+                    Ok(_) => hosted.hosted.store(true, Ordering::SeqCst),
+                    Err(e) => eprintln!("fredulator: no system tray available: {}", e),
+                },
+            );
+        },
+        |_, _| {},
+    );
+    TRAY.set(Tray { bus, shared }).map_err(|_| "Tray already started".to_string())
+}
+
+/// Whether a panel has picked up the icon, so hiding the window to it is safe.
+pub fn is_hosted() -> bool {
+    TRAY.get().is_some_and(|t| t.shared.hosted.load(Ordering::SeqCst))
+}
+
+/// Replaces the results listed in the menu, newest first.
+pub fn set_results(results: Vec<String>) {
+    let Some(tray) = TRAY.get() else { return };
+    match tray.shared.results.lock() {
+        Ok(mut current) if *current != results => *current = results,
+        _ => return,
+    }
+    let revision = tray.shared.revision.fetch_add(1, Ordering::SeqCst) + 1;
+    let _ = tray.bus.emit_signal(None, MENU_PATH, MENU_IFACE, "LayoutUpdated", Some(&(revision, 0i32).to_variant()));
+    let _ = tray.bus.emit_signal(None, ITEM_PATH, ITEM_IFACE, "NewToolTip", None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(n: usize) -> Vec<String> {
+        (1..=n).map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn menu_lists_recent_results_then_actions() {
+        let items = menu_items(&results(7));
+        let labels: Vec<Option<&str>> = items.iter().map(|i| i.label.as_deref()).collect();
+        assert_eq!(
+            labels,
+            [Some("1"), Some("2"), Some("3"), Some("4"), Some("5"), None, Some("Show calculator"), Some("Quit")]
+        );
+        let empty = menu_items(&[]);
+        assert_eq!(empty[0].label.as_deref(), Some("No results yet"));
+        assert!(!empty[0].enabled);
+    }
+
+    #[test]
+    fn clicks_map_to_events() {
+        let r = results(3);
+        assert_eq!(event_for(2, &r), Some(TrayEvent::Copy("2".into())));
+        assert_eq!(event_for(SHOW_ID, &r), Some(TrayEvent::Show));
+        assert_eq!(event_for(QUIT_ID, &r), Some(TrayEvent::Quit));
+        assert_eq!(event_for(4, &r), None);
+        assert_eq!(event_for(EMPTY_ID, &r), None);
+        assert_eq!(event_for(0, &r), None);
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::mpsc::{self, Sender};
+
+/// Starts a thread that runs `job` on what is sent to it, one request at a
+/// time. Requests that pile up while a job runs are dropped in favour of the
+/// newest, so a burst ends in a single run instead of a backlog.
+pub fn spawn_latest<T: Send + 'static>(job: impl Fn(T) + Send + 'static) -> Sender<T> {
+    let (tx, rx) = mpsc::channel::<T>();
+    std::thread::spawn(move || {
+        while let Ok(mut request) = rx.recv() {
+            while let Ok(newer) = rx.try_recv() {
+                request = newer;
+            }
+            job(request);
+        }
+    });
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_requests_on_the_worker() {
+        let (done_tx, done_rx) = mpsc::channel();
+        let queue = spawn_latest(move |n: u32| done_tx.send(n * 2).unwrap());
+        queue.send(21).unwrap();
+        assert_eq!(done_rx.recv(), Ok(42));
+    }
+}
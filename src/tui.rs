@@ -0,0 +1,275 @@
+use std::io;
+
+use crossterm::event::{self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::app::display::{self, DisplayUpdate};
+use crate::app::message::Message;
+use crate::app::state::AppState;
+use crate::app::update::{self, SideEffect};
+use crate::services::config::Config;
+use crate::services::{keypad, speech};
+use crate::ui::keyboard::{self, Direction};
+use crate::ui::layout::{self, ButtonAction, KeySpec};
+
+struct TuiApp {
+    state: AppState,
+    keys: Vec<Vec<KeySpec>>,
+    row: usize,
+    col: usize,
+    quit: bool,
+}
+
+impl TuiApp {
+    /// Starts with the cursor on equals, or the first key if the layout has
+    /// none. Empty grid rows are dropped so the cursor never lands on one.
+    fn new(state: AppState, layout: &[KeySpec]) -> Self {
+        let keys: Vec<Vec<KeySpec>> = layout::rows(layout).into_iter().filter(|r| !r.is_empty()).collect();
+        let (row, col) = keys
+            .iter()
+            .enumerate()
+            .find_map(|(r, row)| row.iter().position(|k| k.action == ButtonAction::Equals).map(|c| (r, c)))
+            .unwrap_or((0, 0));
+        Self { state, keys, row, col, quit: false }
+    }
+
+    fn dispatch(&mut self, msg: Message) {
+        for eff in display::reduce(&mut self.state, msg).effects {
+            match eff {
+                SideEffect::Navigate(dir) => self.navigate(dir),
+                SideEffect::ActivateButton => {
+                    let msg = self.keys[self.row][self.col].action.message();
+                    self.dispatch(msg);
+                }
+                SideEffect::Quit => self.quit = true,
+                SideEffect::Speak(text) => speech::say(&text),
+                _ => {}
+            }
+        }
+    }
+
+    fn navigate(&mut self, dir: Direction) {
+        // Work in grid columns so moving vertically through the wide "0" key
+        // lands on the visually closest neighbour.
+        let grid_col = self.grid_col();
+        match dir {
+            Direction::Left => self.col = self.col.saturating_sub(1),
+            Direction::Right => self.col = (self.col + 1).min(self.keys[self.row].len() - 1),
+            Direction::Up => {
+                self.row = self.row.saturating_sub(1);
+                self.col = self.index_at(grid_col);
+            }
+            Direction::Down => {
+                self.row = (self.row + 1).min(self.keys.len() - 1);
+                self.col = self.index_at(grid_col);
+            }
+        }
+    }
+
+    fn grid_col(&self) -> usize {
+        self.keys[self.row][..self.col].iter().map(|k| k.span).sum()
+    }
+
+    fn index_at(&self, grid_col: usize) -> usize {
+        let mut start = 0;
+        for (i, k) in self.keys[self.row].iter().enumerate() {
+            if grid_col < start + k.span {
+                return i;
+            }
+            start += k.span;
+        }
+        self.keys[self.row].len() - 1
+    }
+}
+
+pub fn run(config: Config) -> io::Result<()> {
+    keyboard::init_keymap(&config.keybindings);
+
+    let session_id = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut state = AppState::new(config, session_id);
+    update::restore_session(&mut state);
+
+    let mut app = TuiApp::new(state, &keypad::load().basic);
+
+    let mut terminal = ratatui::init();
+    // Pasted text arrives as one event and is validated as a whole.
+    let _ = execute!(io::stdout(), EnableBracketedPaste);
+    let result = event_loop(&mut terminal, &mut app);
+    let _ = execute!(io::stdout(), DisableBracketedPaste);
+    ratatui::restore();
+
+    if !app.quit {
+        update::save_on_exit(&app.state);
+    }
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut TuiApp) -> io::Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| draw(frame, app))?;
+        match event::read()? {
+            Event::Key(key) => {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                    break;
+                }
+                let msg = keyboard::lookup(&key_combo(&key));
+                app.dispatch(msg);
+            }
+            Event::Paste(text) => app.dispatch(Message::Paste(text)),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn key_combo(key: &KeyEvent) -> String {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+    let name = match key.code {
+        KeyCode::Enter => "Return",
+        KeyCode::Esc => "Escape",
+        KeyCode::Backspace => "BackSpace",
+        KeyCode::Delete => "Delete",
+        KeyCode::Tab => "Tab",
+        KeyCode::BackTab => return keyboard::build_combo(ctrl, alt, true, "Tab"),
+        KeyCode::Left => "Left",
+        KeyCode::Right => "Right",
+        KeyCode::Up => "Up",
+        KeyCode::Down => "Down",
+        KeyCode::F(1) => "F1",
+        KeyCode::Char(' ') => "space",
+        KeyCode::Char(c) => return keyboard::build_combo(ctrl, alt, false, &c.to_string()),
+        _ => return String::new(),
+    };
+    keyboard::build_combo(ctrl, alt, shift, name)
+}
+
+fn draw(frame: &mut Frame, app: &TuiApp) {
+    let [display_area, keypad_area, help_area] = Layout::vertical([
+        Constraint::Length(5),
+        Constraint::Min(10),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    draw_display(frame, app, display_area);
+    draw_keypad(frame, app, keypad_area);
+
+    let help = Paragraph::new("arrows/hjkl move \u{00b7} space press \u{00b7} type to calculate \u{00b7} Ctrl+q quit")
+        .style(Style::default().add_modifier(Modifier::DIM))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, help_area);
+}
+
+fn draw_display(frame: &mut Frame, app: &TuiApp, area: Rect) {
+    let update = DisplayUpdate::from_state(&app.state);
+    // A rounded result is marked, and the line the preview uses before "="
+    // shows its exact value.
+    let (main, preview) = match update.exact {
+        Some(exact) => {
+            let value = format!("= {}", exact.fraction.unwrap_or(exact.digits));
+            let preview = match exact.lost {
+                Some(lost) => format!("{} \u{00b7} {}", value, lost),
+                None => value,
+            };
+            (format!("\u{2248} {}", update.main), preview)
+        }
+        None => (update.main, update.preview.unwrap_or_default()),
+    };
+    let lines = vec![
+        Line::from(update.secondary.unwrap_or_default()).style(Style::default().add_modifier(Modifier::DIM)),
+        Line::from(main).style(Style::default().add_modifier(Modifier::BOLD)),
+        Line::from(preview).style(Style::default().add_modifier(Modifier::DIM)),
+    ];
+    let title = format!(" {} \u{00b7} {} ", app.state.tabs[app.state.active_tab].name, update.angle);
+    let display = Paragraph::new(lines)
+        .alignment(Alignment::Right)
+        .block(Block::bordered().title(title));
+    frame.render_widget(display, area);
+}
+
+fn draw_keypad(frame: &mut Frame, app: &TuiApp, area: Rect) {
+    let columns = layout::columns(&app.keys.concat());
+    let rows = Layout::vertical(vec![Constraint::Ratio(1, app.keys.len() as u32); app.keys.len()]).split(area);
+    for (r, (row, row_area)) in app.keys.iter().zip(rows.iter()).enumerate() {
+        let widths: Vec<Constraint> = row
+            .iter()
+            .map(|k| Constraint::Ratio(k.span as u32, columns as u32))
+            .collect();
+        let cells = Layout::horizontal(widths).split(*row_area);
+        for (c, (k, cell)) in row.iter().zip(cells.iter()).enumerate() {
+            let mut style = Style::default();
+            if r == app.row && c == app.col {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            let button = Paragraph::new(k.label.as_ref())
+                .alignment(Alignment::Center)
+                .style(style)
+                .block(Block::bordered());
+            frame.render_widget(button, *cell);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> TuiApp {
+        TuiApp::new(AppState::new(Config::default(), 0), layout::BASIC)
+    }
+
+    #[test]
+    fn starts_on_equals() {
+        let a = app();
+        assert_eq!((a.row, a.col), (4, 2));
+        let custom = [
+            KeySpec::custom("1".into(), ButtonAction::Digit('1'), 0, 2, 1),
+            KeySpec::custom("=".into(), ButtonAction::Equals, 1, 2, 1),
+        ];
+        let b = TuiApp::new(AppState::new(Config::default(), 0), &custom);
+        assert_eq!((b.keys.len(), b.row, b.col), (1, 0, 1));
+    }
+
+    #[test]
+    fn navigation_through_wide_zero() {
+        let mut a = app();
+        a.row = 3;
+        a.col = 1;
+        a.navigate(Direction::Down);
+        assert_eq!((a.row, a.col), (4, 0));
+        a.navigate(Direction::Right);
+        a.navigate(Direction::Up);
+        assert_eq!((a.row, a.col), (3, 2));
+    }
+
+    #[test]
+    fn activate_presses_selected_key() {
+        let mut a = app();
+        a.row = 1;
+        a.col = 0;
+        a.dispatch(Message::Activate);
+        assert_eq!(a.state.engine().main_display_text(), "7");
+    }
+
+    #[test]
+    fn key_combo_matches_gtk_names() {
+        let ev = |code, mods| KeyEvent::new(code, mods);
+        assert_eq!(key_combo(&ev(KeyCode::Enter, KeyModifiers::NONE)), "Return");
+        assert_eq!(key_combo(&ev(KeyCode::Char('z'), KeyModifiers::CONTROL)), "Ctrl+z");
+        assert_eq!(key_combo(&ev(KeyCode::Char('S'), KeyModifiers::SHIFT)), "S");
+        assert_eq!(key_combo(&ev(KeyCode::BackTab, KeyModifiers::SHIFT)), "Shift+Tab");
+    }
+}
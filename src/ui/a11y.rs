@@ -0,0 +1,138 @@
+use crate::domain::types::*;
+use crate::ui::layout::ButtonAction;
+
+/// What a screen reader should say for a keypad button. The visible labels
+/// ("x²", "÷", "sin⁻¹") are read out poorly or not at all.
+pub fn key_name(action: ButtonAction) -> String {
+    let name = match action {
+        ButtonAction::Digit(d) => return d.to_string(),
+        ButtonAction::Decimal => "point",
+        ButtonAction::BinaryOp(op) => match op {
+            BinaryOp::Add => "plus",
+            BinaryOp::Subtract => "minus",
+            BinaryOp::Multiply => "times",
+            BinaryOp::Divide => "divided by",
+            BinaryOp::Power => "to the power of",
+            BinaryOp::Modulo => "modulo",
+            BinaryOp::PlusMinus => "plus or minus",
+        },
+        ButtonAction::UnaryFunc(f) => match f {
+            UnaryFunc::Sin => "sine",
+            UnaryFunc::Cos => "cosine",
+            UnaryFunc::Tan => "tangent",
+            UnaryFunc::Asin => "inverse sine",
+            UnaryFunc::Acos => "inverse cosine",
+            UnaryFunc::Atan => "inverse tangent",
+            UnaryFunc::Sinh => "hyperbolic sine",
+            UnaryFunc::Cosh => "hyperbolic cosine",
+            UnaryFunc::Tanh => "hyperbolic tangent",
+            UnaryFunc::Ln => "natural logarithm",
+            UnaryFunc::Log10 => "logarithm base 10",
+            UnaryFunc::Sqrt => "square root",
+            UnaryFunc::Cbrt => "cube root",
+            UnaryFunc::Abs => "absolute value",
+            UnaryFunc::Exp => "e to the power of",
+        },
+        ButtonAction::PostfixOp(op) => match op {
+            PostfixOp::Square => "squared",
+            PostfixOp::Cube => "cubed",
+            PostfixOp::Reciprocal => "reciprocal",
+            PostfixOp::Factorial => "factorial",
+            PostfixOp::Percent => "percent",
+        },
+        ButtonAction::Constant(_, "\u{03c0}") => "pi",
+        ButtonAction::Constant(_, name) => return name.to_string(),
+        ButtonAction::LeftParen => "open parenthesis",
+        ButtonAction::RightParen => "close parenthesis",
+        ButtonAction::Equals => "equals",
+        ButtonAction::Clear => "all clear",
+        ButtonAction::ToggleSign => "change sign",
+        ButtonAction::EE => "times ten to the power of",
+        ButtonAction::MemoryClear => "memory clear",
+        ButtonAction::MemoryRecall => "memory recall",
+        ButtonAction::MemoryAdd => "memory add",
+        ButtonAction::MemorySubtract => "memory subtract",
+        ButtonAction::ToggleAngleMode => "angle mode",
+    };
+    name.to_string()
+}
+
+/// Short group description read after the name, keyed on the button's
+/// style class.
+pub fn key_description(class: &str) -> &'static str {
+    match class {
+        "digit-button" => "Digit",
+        "op-button" => "Operator",
+        "function-button" => "Function",
+        "power-button" => "Power or root",
+        "constant-button" => "Constant",
+        "memory-button" => "Memory",
+        "paren-button" => "Grouping",
+        "toggle-button" => "Switches between degrees and radians",
+        "equals-button" => "Evaluate",
+        _ => "Edit",
+    }
+}
+
+/// Turns display text into something that reads naturally, e.g. "−2.5e-3"
+/// becomes "minus 2.5 times ten to the power minus 3". Error messages pass
+/// through unchanged.
+pub fn spoken_result(text: &str) -> String {
+    let text = text.replace('\u{2212}', "-");
+    match text.split_once('e') {
+        Some((mantissa, exp)) if exp.parse::<i32>().is_ok() => format!(
+            "{} times ten to the power {}",
+            spoken_sign(mantissa),
+            spoken_sign(exp.trim_start_matches('+'))
+        ),
+        _ => spoken_sign(&text),
+    }
+}
+
+fn spoken_sign(s: &str) -> String {
+    match s.strip_prefix('-') {
+        Some(rest) => format!("minus {}", rest),
+        None => s.to_string(),
+    }
+}
+
+#[cfg(feature = "gui")]
+pub fn describe(widget: &impl gtk::prelude::IsA<gtk::Widget>, name: &str, description: &str) {
+    use gtk::prelude::*;
+    if let Some(obj) = widget.accessible() {
+        obj.set_name(name);
+        obj.set_description(description);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::layout;
+
+    #[test]
+    fn every_key_has_a_spoken_name() {
+        for spec in layout::BASIC.iter().chain(layout::SCIENTIFIC) {
+            let name = key_name(spec.action);
+            assert!(name.is_ascii(), "{:?} reads as {:?}", spec.label, name);
+            assert!(!name.is_empty());
+        }
+    }
+
+    #[test]
+    fn key_names() {
+        assert_eq!(key_name(ButtonAction::Digit('7')), "7");
+        assert_eq!(key_name(ButtonAction::BinaryOp(BinaryOp::Divide)), "divided by");
+        assert_eq!(key_name(ButtonAction::Constant(std::f64::consts::PI, "\u{03c0}")), "pi");
+        assert_eq!(key_name(ButtonAction::Constant(std::f64::consts::E, "e")), "e");
+    }
+
+    #[test]
+    fn spoken_results() {
+        assert_eq!(spoken_result("14"), "14");
+        assert_eq!(spoken_result("\u{2212}2.5"), "minus 2.5");
+        assert_eq!(spoken_result("-2.5e-3"), "minus 2.5 times ten to the power minus 3");
+        assert_eq!(spoken_result("1e+20"), "1 times ten to the power 20");
+        assert_eq!(spoken_result("Division by zero"), "Division by zero");
+    }
+}
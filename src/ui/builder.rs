@@ -8,46 +8,48 @@ use gtk::{
 use crate::domain::types::*;
 use crate::services::config::Config;
 use crate::services::theme::Theme;
+use crate::ui::a11y;
+use crate::ui::layout::{ButtonAction, KeySpec, Keypad};
 use crate::ui::navigation::NavButton;
 
-#[derive(Clone, Copy)]
-pub enum ButtonAction {
-    Digit(char),
-    Decimal,
-    BinaryOp(BinaryOp),
-    UnaryFunc(UnaryFunc),
-    PostfixOp(PostfixOp),
-    Constant(f64, &'static str),
-    LeftParen,
-    RightParen,
-    Equals,
-    Clear,
-    ToggleSign,
-    EE,
-    MemoryClear,
-    MemoryRecall,
-    MemoryAdd,
-    MemorySubtract,
-    ToggleAngleMode,
-}
-
 pub struct CalculatorUI {
     pub window: Window,
     pub expr_label: Label,
     pub result_label: Label,
+    /// "≈" beside a rounded result; clicking it shows the exact value.
+    pub exact_btn: Button,
+    pub display_event_box: gtk::EventBox,
+    pub display_long_press: gtk::GestureLongPress,
     pub preview_label: Label,
     pub sci_grid: Grid,
-    pub nav_buttons: Vec<NavButton>,
-    pub action_buttons: Vec<(Button, ButtonAction)>,
+    pub main_grid: Grid,
+    /// The display and both keypads; see `place_panes`.
+    pub calc_view: Grid,
+    /// Side panels and the calculator, left to right.
+    pub content_box: gtk::Box,
+    pub keys: Keys,
     pub tab_bar: gtk::Box,
+    /// Row under the tab bar offering the result of a copied calculation.
+    pub clipboard_revealer: Revealer,
+    pub clipboard_offer_btn: Button,
+    pub clipboard_dismiss_btn: Button,
     pub tab_add_btn: Button,
     pub menu_popover: gtk::Popover,
     pub menu_basic_btn: Button,
     pub menu_sci_btn: Button,
     pub menu_help_btn: Button,
+    pub menu_about_btn: Button,
     pub menu_notes_btn: Button,
+    pub menu_worksheet_btn: Button,
     pub menu_converter_btn: Button,
     pub menu_tools_btn: Button,
+    pub menu_export_btn: Button,
+    pub menu_keypad_btn: Button,
+    pub menu_snippets_btn: Button,
+    pub menu_steps_btn: Button,
+    pub menu_palette_btn: Button,
+    pub menu_copy_latex_btn: Button,
+    pub menu_copy_plain_btn: Button,
     pub menu_theme_btns: Vec<(Button, usize)>,
     pub panel_revealer: Revealer,
     pub panel_history_btn: Button,
@@ -80,14 +82,36 @@ pub struct CalculatorUI {
     pub tax_amount_entry: Entry,
     pub tax_rate_entry: Entry,
     pub tax_result_label: Label,
+    pub time_watch_label: Label,
+    pub time_start_btn: Button,
+    pub time_reset_btn: Button,
+    pub time_log_btn: Button,
+    pub time_entry: Entry,
+    pub time_rate_entry: Entry,
+    pub time_result_label: Label,
+    pub time_use_btn: Button,
+    pub ratio_entries: Vec<Entry>,
+    pub ratio_result_label: Label,
+    pub ratio_use_btn: Button,
     pub tools_back_btn: Button,
     pub notes_textview: TextView,
     pub notes_result_label: Label,
     pub notes_back_btn: Button,
+    pub worksheet_list: gtk::Box,
+    pub worksheet_back_btn: Button,
+}
+
+/// The keypad buttons, made by `fill_keypads` from a `Keypad`.
+#[derive(Default)]
+pub struct Keys {
+    pub nav_buttons: Vec<NavButton>,
+    pub action_buttons: Vec<(Button, ButtonAction)>,
+    /// Every `%` key, with the long press that opens the percent questions.
+    pub percent_long_press: Vec<(Button, gtk::GestureLongPress)>,
     pub angle_btn: Option<Button>,
 }
 
-pub fn build(config: &Config) -> CalculatorUI {
+pub fn build(config: &Config, keypad: &Keypad) -> CalculatorUI {
     let wcfg = &config.window;
     let window = Window::new(WindowType::Toplevel);
     window.set_title("Fredulator");
@@ -95,43 +119,18 @@ pub fn build(config: &Config) -> CalculatorUI {
     window.set_resizable(true);
     window.style_context().add_class("main-window");
 
-    let mut action_buttons: Vec<(Button, ButtonAction)> = Vec::new();
-    let mut nav_buttons: Vec<NavButton> = Vec::new();
-
-    let mk = |label: &str,
-              class: &str,
-              action: ButtonAction,
-              col: usize,
-              row: usize,
-              sci: bool,
-              actions: &mut Vec<(Button, ButtonAction)>,
-              navs: &mut Vec<NavButton>|
-     -> Button {
-        let b = Button::with_label(label);
-        b.style_context().add_class(class);
-        b.set_hexpand(true);
-        b.set_vexpand(true);
-        b.set_can_focus(true);
-        actions.push((b.clone(), action));
-        navs.push(NavButton {
-            button: b.clone(),
-            col,
-            row,
-            scientific: sci,
-        });
-        b
-    };
-
     let outer_tab_bar = gtk::Box::new(Orientation::Horizontal, 4);
     outer_tab_bar.style_context().add_class("tab-bar");
 
     let tab_add_btn = Button::with_label("+");
     tab_add_btn.style_context().add_class("tab-add");
     tab_add_btn.set_can_focus(false);
+    a11y::describe(&tab_add_btn, "New tab", "Open another calculator tab");
 
     let menu_btn = Button::with_label("\u{2261}");
     menu_btn.style_context().add_class("menu-button");
     menu_btn.set_can_focus(false);
+    a11y::describe(&menu_btn, "Menu", "Modes, themes, export and help");
 
     let tab_scroll = ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
     tab_scroll.set_policy(PolicyType::Automatic, PolicyType::Never);
@@ -154,10 +153,10 @@ pub fn build(config: &Config) -> CalculatorUI {
 
     let mode_selector = gtk::Box::new(Orientation::Horizontal, 2);
     mode_selector.style_context().add_class("mode-selector");
-    let menu_basic_btn = Button::with_label("Basic");
+    let menu_basic_btn = Button::with_mnemonic("_Basic");
     menu_basic_btn.style_context().add_class("active");
     menu_basic_btn.set_hexpand(true);
-    let menu_sci_btn = Button::with_label("Scientific");
+    let menu_sci_btn = Button::with_mnemonic("_Scientific");
     menu_sci_btn.set_hexpand(true);
     mode_selector.pack_start(&menu_basic_btn, true, true, 0);
     mode_selector.pack_start(&menu_sci_btn, true, true, 0);
@@ -166,23 +165,69 @@ pub fn build(config: &Config) -> CalculatorUI {
     let sep0 = gtk::Separator::new(Orientation::Horizontal);
     menu_box.pack_start(&sep0, false, false, 4);
 
-    let menu_notes_btn = Button::with_label("\u{270e} Math Notes     [Ctrl+n]");
+    let menu_notes_btn = Button::with_mnemonic("\u{270e} Math _Notes     [Ctrl+n]");
     menu_notes_btn.style_context().add_class("menu-item");
     menu_notes_btn.set_halign(gtk::Align::Fill);
-    let menu_converter_btn = Button::with_label("\u{21c4} Converter      [Ctrl+e]");
+    let menu_worksheet_btn = Button::with_mnemonic("\u{2630} _Worksheet      [Ctrl+j]");
+    menu_worksheet_btn.style_context().add_class("menu-item");
+    menu_worksheet_btn.set_halign(gtk::Align::Fill);
+    let menu_converter_btn = Button::with_mnemonic("\u{21c4} _Converter      [Ctrl+e]");
     menu_converter_btn.style_context().add_class("menu-item");
     menu_converter_btn.set_halign(gtk::Align::Fill);
-    let menu_tools_btn = Button::with_label("% Quick Tools   [Ctrl+r]");
+    let menu_tools_btn = Button::with_mnemonic("% Quick _Tools   [Ctrl+r]");
     menu_tools_btn.style_context().add_class("menu-item");
     menu_tools_btn.set_halign(gtk::Align::Fill);
 
     menu_box.pack_start(&menu_notes_btn, false, false, 0);
+    menu_box.pack_start(&menu_worksheet_btn, false, false, 0);
     menu_box.pack_start(&menu_converter_btn, false, false, 0);
     menu_box.pack_start(&menu_tools_btn, false, false, 0);
 
+    let menu_steps_btn = Button::with_mnemonic("\u{2261} Show St_eps     [Ctrl+i]");
+    menu_steps_btn.style_context().add_class("menu-item");
+    menu_steps_btn.set_halign(gtk::Align::Fill);
+    menu_box.pack_start(&menu_steps_btn, false, false, 0);
+
+    let menu_palette_btn = Button::with_mnemonic("\u{2318} Co_mmands\u{2026}    [Ctrl+k]");
+    menu_palette_btn.style_context().add_class("menu-item");
+    menu_palette_btn.set_halign(gtk::Align::Fill);
+    menu_box.pack_start(&menu_palette_btn, false, false, 0);
+
+    let menu_snippets_btn = Button::with_mnemonic("\u{2702} Sn_ippets\u{2026}");
+    menu_snippets_btn.style_context().add_class("menu-item");
+    menu_snippets_btn.set_halign(gtk::Align::Fill);
+    menu_box.pack_start(&menu_snippets_btn, false, false, 0);
+
+    let menu_export_btn = Button::with_mnemonic("\u{2913} E_xport History\u{2026}");
+    menu_export_btn.style_context().add_class("menu-item");
+    menu_export_btn.set_halign(gtk::Align::Fill);
+    menu_box.pack_start(&menu_export_btn, false, false, 0);
+
+    let menu_keypad_btn = Button::with_mnemonic("\u{2328} Edit _Keypad\u{2026}");
+    menu_keypad_btn.style_context().add_class("menu-item");
+    menu_keypad_btn.set_halign(gtk::Align::Fill);
+    menu_box.pack_start(&menu_keypad_btn, false, false, 0);
+
     let sep = gtk::Separator::new(Orientation::Horizontal);
     menu_box.pack_start(&sep, false, false, 4);
 
+    let copy_header = Label::new(Some("COPY AS\u{2026}"));
+    copy_header.style_context().add_class("menu-header");
+    copy_header.set_xalign(0.0);
+    menu_box.pack_start(&copy_header, false, false, 0);
+
+    let menu_copy_latex_btn = Button::with_mnemonic("_LaTeX");
+    menu_copy_latex_btn.style_context().add_class("menu-item");
+    menu_copy_latex_btn.set_halign(gtk::Align::Fill);
+    let menu_copy_plain_btn = Button::with_mnemonic("_Plain text  (expr = result)");
+    menu_copy_plain_btn.style_context().add_class("menu-item");
+    menu_copy_plain_btn.set_halign(gtk::Align::Fill);
+    menu_box.pack_start(&menu_copy_latex_btn, false, false, 0);
+    menu_box.pack_start(&menu_copy_plain_btn, false, false, 0);
+
+    let sep_copy = gtk::Separator::new(Orientation::Horizontal);
+    menu_box.pack_start(&sep_copy, false, false, 4);
+
     let theme_header = Label::new(Some("THEMES"));
     theme_header.style_context().add_class("menu-header");
     theme_header.set_xalign(0.0);
@@ -247,11 +292,16 @@ pub fn build(config: &Config) -> CalculatorUI {
     let sep3 = gtk::Separator::new(Orientation::Horizontal);
     menu_box.pack_start(&sep3, false, false, 4);
 
-    let menu_help_btn = Button::with_label("? Shortcuts");
+    let menu_help_btn = Button::with_mnemonic("? S_hortcuts");
     menu_help_btn.style_context().add_class("menu-item");
     menu_help_btn.set_halign(gtk::Align::Fill);
     menu_box.pack_start(&menu_help_btn, false, false, 0);
 
+    let menu_about_btn = Button::with_mnemonic("_About Fredulator");
+    menu_about_btn.style_context().add_class("menu-item");
+    menu_about_btn.set_halign(gtk::Align::Fill);
+    menu_box.pack_start(&menu_about_btn, false, false, 0);
+
     menu_box.show_all();
     menu_popover.add(&menu_box);
 
@@ -277,6 +327,7 @@ pub fn build(config: &Config) -> CalculatorUI {
     result_label.set_hexpand(true);
     result_label.set_selectable(false);
     result_label.set_ellipsize(gtk::pango::EllipsizeMode::Start);
+    result_label.set_line_wrap_mode(gtk::pango::WrapMode::WordChar);
     result_label.set_max_width_chars(1);
 
     let preview_label = Label::new(Some(" "));
@@ -288,15 +339,41 @@ pub fn build(config: &Config) -> CalculatorUI {
     preview_label.set_max_width_chars(1);
     preview_label.set_opacity(0.0);
 
+    let exact_btn = Button::with_label("\u{2248}");
+    exact_btn.style_context().add_class("exact-indicator");
+    exact_btn.set_relief(gtk::ReliefStyle::None);
+    exact_btn.set_valign(gtk::Align::Center);
+    exact_btn.set_can_focus(false);
+    exact_btn.set_tooltip_text(Some("Rounded \u{2014} click for the exact value"));
+    exact_btn.set_no_show_all(true);
+
+    let result_row = gtk::Box::new(Orientation::Horizontal, 4);
+    result_row.pack_start(&exact_btn, false, false, 0);
+    result_row.pack_start(&result_label, true, true, 0);
+
+    a11y::describe(&expr_label, "", "Expression");
+    a11y::describe(&exact_btn, "approximately", "Show the exact value");
+    a11y::describe(&result_label, "0", "Result");
+    a11y::describe(&preview_label, "", "Live preview");
+
     let display_box = gtk::Box::new(Orientation::Vertical, 0);
     display_box.style_context().add_class("display-area");
     display_box.set_size_request(-1, 250);
     display_box.set_vexpand(false);
     display_box.set_vexpand_set(true);
     display_box.pack_start(&expr_label, false, false, 0);
-    display_box.pack_start(&result_label, true, true, 0);
+    display_box.pack_start(&result_row, true, true, 0);
     display_box.pack_start(&preview_label, false, false, 0);
 
+    let display_event_box = gtk::EventBox::new();
+    display_event_box.add(&display_box);
+    a11y::describe(
+        &display_event_box,
+        "Calculator display",
+        "Drag the result out, drop an expression in, or right-click to convert units",
+    );
+    let display_long_press = gtk::GestureLongPress::new(&display_event_box);
+
     let layout_cfg = &config.layout;
     let spacing = layout_cfg.button_spacing as i32;
 
@@ -307,45 +384,6 @@ pub fn build(config: &Config) -> CalculatorUI {
     sci_grid.set_column_homogeneous(true);
     sci_grid.set_row_homogeneous(true);
 
-    let sci_btns: Vec<(&str, &str, ButtonAction, usize, usize)> = vec![
-        ("MC", "memory-button", ButtonAction::MemoryClear, 0, 0),
-        ("MR", "memory-button", ButtonAction::MemoryRecall, 1, 0),
-        ("M+", "memory-button", ButtonAction::MemoryAdd, 2, 0),
-        ("M\u{2212}", "memory-button", ButtonAction::MemorySubtract, 0, 1),
-        ("(", "paren-button", ButtonAction::LeftParen, 1, 1),
-        (")", "paren-button", ButtonAction::RightParen, 2, 1),
-        ("Deg", "toggle-button", ButtonAction::ToggleAngleMode, 0, 2),
-        ("x\u{00b2}", "power-button", ButtonAction::PostfixOp(PostfixOp::Square), 1, 2),
-        ("x\u{00b3}", "power-button", ButtonAction::PostfixOp(PostfixOp::Cube), 2, 2),
-        ("x\u{02b8}", "power-button", ButtonAction::BinaryOp(BinaryOp::Power), 0, 3),
-        ("\u{215f}x", "power-button", ButtonAction::PostfixOp(PostfixOp::Reciprocal), 1, 3),
-        ("\u{221a}", "power-button", ButtonAction::UnaryFunc(UnaryFunc::Sqrt), 2, 3),
-        ("\u{00b3}\u{221a}", "power-button", ButtonAction::UnaryFunc(UnaryFunc::Cbrt), 0, 4),
-        ("sin", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Sin), 1, 4),
-        ("cos", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Cos), 2, 4),
-        ("tan", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Tan), 0, 5),
-        ("ln", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Ln), 1, 5),
-        ("log", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Log10), 2, 5),
-        ("n!", "function-button", ButtonAction::PostfixOp(PostfixOp::Factorial), 0, 6),
-        ("\u{03c0}", "constant-button", ButtonAction::Constant(std::f64::consts::PI, "\u{03c0}"), 1, 6),
-        ("e", "constant-button", ButtonAction::Constant(std::f64::consts::E, "e"), 2, 6),
-        ("EE", "function-button", ButtonAction::EE, 0, 7),
-        ("sin\u{207b}\u{00b9}", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Asin), 1, 7),
-        ("cos\u{207b}\u{00b9}", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Acos), 2, 7),
-    ];
-
-    let mut angle_btn_ref = None;
-    for (label, class, action, col, row) in sci_btns {
-        let b = mk(
-            label, class, action, col, row, true,
-            &mut action_buttons, &mut nav_buttons,
-        );
-        sci_grid.attach(&b, col as i32, row as i32, 1, 1);
-        if matches!(action, ButtonAction::ToggleAngleMode) {
-            angle_btn_ref = Some(b);
-        }
-    }
-
     let main_grid = Grid::new();
     main_grid.style_context().add_class("calc-grid");
     main_grid.set_row_spacing(spacing as u32);
@@ -353,44 +391,7 @@ pub fn build(config: &Config) -> CalculatorUI {
     main_grid.set_column_homogeneous(true);
     main_grid.set_row_homogeneous(true);
 
-    let b = mk("AC", "clear-button", ButtonAction::Clear, 0, 0, false, &mut action_buttons, &mut nav_buttons);
-    main_grid.attach(&b, 0, 0, 1, 1);
-    let b = mk("+/\u{2212}", "util-button", ButtonAction::ToggleSign, 1, 0, false, &mut action_buttons, &mut nav_buttons);
-    main_grid.attach(&b, 1, 0, 1, 1);
-    let b = mk("%", "util-button", ButtonAction::PostfixOp(PostfixOp::Percent), 2, 0, false, &mut action_buttons, &mut nav_buttons);
-    main_grid.attach(&b, 2, 0, 1, 1);
-    let b = mk("\u{00f7}", "op-button", ButtonAction::BinaryOp(BinaryOp::Divide), 3, 0, false, &mut action_buttons, &mut nav_buttons);
-    main_grid.attach(&b, 3, 0, 1, 1);
-
-    for (i, d) in ['7', '8', '9'].iter().enumerate() {
-        let b = mk(&d.to_string(), "digit-button", ButtonAction::Digit(*d), i, 1, false, &mut action_buttons, &mut nav_buttons);
-        main_grid.attach(&b, i as i32, 1, 1, 1);
-    }
-    let b = mk("\u{00d7}", "op-button", ButtonAction::BinaryOp(BinaryOp::Multiply), 3, 1, false, &mut action_buttons, &mut nav_buttons);
-    main_grid.attach(&b, 3, 1, 1, 1);
-
-    for (i, d) in ['4', '5', '6'].iter().enumerate() {
-        let b = mk(&d.to_string(), "digit-button", ButtonAction::Digit(*d), i, 2, false, &mut action_buttons, &mut nav_buttons);
-        main_grid.attach(&b, i as i32, 2, 1, 1);
-    }
-    let b = mk("\u{2212}", "op-button", ButtonAction::BinaryOp(BinaryOp::Subtract), 3, 2, false, &mut action_buttons, &mut nav_buttons);
-    main_grid.attach(&b, 3, 2, 1, 1);
-
-    for (i, d) in ['1', '2', '3'].iter().enumerate() {
-        let b = mk(&d.to_string(), "digit-button", ButtonAction::Digit(*d), i, 3, false, &mut action_buttons, &mut nav_buttons);
-        main_grid.attach(&b, i as i32, 3, 1, 1);
-    }
-    let b = mk("+", "op-button", ButtonAction::BinaryOp(BinaryOp::Add), 3, 3, false, &mut action_buttons, &mut nav_buttons);
-    main_grid.attach(&b, 3, 3, 1, 1);
-
-    let d0 = mk("0", "digit-button", ButtonAction::Digit('0'), 1, 4, false, &mut action_buttons, &mut nav_buttons);
-    main_grid.attach(&d0, 0, 4, 2, 1);
-    // Extra nav entry at col=0 so 'j' from the '1' button (col=0) reaches '0'
-    nav_buttons.push(NavButton { button: d0.clone(), col: 0, row: 4, scientific: false });
-    let b = mk(".", "digit-button", ButtonAction::Decimal, 2, 4, false, &mut action_buttons, &mut nav_buttons);
-    main_grid.attach(&b, 2, 4, 1, 1);
-    let b = mk("=", "equals-button", ButtonAction::Equals, 3, 4, false, &mut action_buttons, &mut nav_buttons);
-    main_grid.attach(&b, 3, 4, 1, 1);
+    let keys = fill_keypads(&sci_grid, &main_grid, keypad);
 
     let panel_revealer = Revealer::new();
     panel_revealer.set_transition_type(RevealerTransitionType::SlideRight);
@@ -405,18 +406,18 @@ pub fn build(config: &Config) -> CalculatorUI {
     panel_tabs.set_margin_start(4);
     panel_tabs.set_margin_end(4);
 
-    let panel_history_btn = Button::with_label("History");
+    let panel_history_btn = Button::with_mnemonic("_History");
     panel_history_btn.style_context().add_class("panel-tab");
     panel_history_btn.style_context().add_class("active");
     panel_history_btn.set_hexpand(true);
     panel_history_btn.set_can_focus(false);
 
-    let panel_memory_btn = Button::with_label("Memory");
+    let panel_memory_btn = Button::with_mnemonic("_Memory");
     panel_memory_btn.style_context().add_class("panel-tab");
     panel_memory_btn.set_hexpand(true);
     panel_memory_btn.set_can_focus(false);
 
-    let panel_pinned_btn = Button::with_label("Pinned");
+    let panel_pinned_btn = Button::with_mnemonic("P_inned");
     panel_pinned_btn.style_context().add_class("panel-tab");
     panel_pinned_btn.set_hexpand(true);
     panel_pinned_btn.set_can_focus(false);
@@ -432,7 +433,7 @@ pub fn build(config: &Config) -> CalculatorUI {
 
     let history_panel = gtk::Box::new(Orientation::Vertical, 2);
     let history_search_entry = Entry::new();
-    history_search_entry.set_placeholder_text(Some("Search history..."));
+    history_search_entry.set_placeholder_text(Some("Search history (fuzzy, or a weekday)..."));
     history_search_entry.style_context().add_class("panel-search");
     history_search_entry.set_margin_start(4);
     history_search_entry.set_margin_end(4);
@@ -496,13 +497,15 @@ pub fn build(config: &Config) -> CalculatorUI {
     mode_stack.set_transition_type(StackTransitionType::SlideLeftRight);
     mode_stack.set_transition_duration(200);
 
-    let calc_view = gtk::Box::new(Orientation::Vertical, 0);
-    calc_view.pack_start(&display_box, false, false, 0);
-
-    let grid_box = gtk::Box::new(Orientation::Horizontal, 6);
-    grid_box.pack_start(&sci_grid, true, true, 0);
-    grid_box.pack_start(&main_grid, true, true, 0);
-    calc_view.pack_start(&grid_box, true, true, 0);
+    // Children are placed by `place_panes`, portrait or landscape.
+    let calc_view = Grid::new();
+    calc_view.set_column_spacing(6);
+    display_event_box.set_hexpand(true);
+    sci_grid.set_hexpand(true);
+    sci_grid.set_vexpand(true);
+    main_grid.set_hexpand(true);
+    main_grid.set_vexpand(true);
+    place_panes(&calc_view, &display_event_box, &sci_grid, &main_grid, false);
     mode_stack.add_named(&calc_view, "calculator");
 
     let conv_view = gtk::Box::new(Orientation::Vertical, 8);
@@ -676,6 +679,75 @@ pub fn build(config: &Config) -> CalculatorUI {
 
     tools_notebook.append_page(&tax_page, Some(&Label::new(Some("Tax"))));
 
+    let time_page = gtk::Box::new(Orientation::Vertical, 8);
+    time_page.set_margin_top(12);
+    time_page.set_margin_start(8);
+    time_page.set_margin_end(8);
+    let time_watch_label = Label::new(Some("0:00:00"));
+    time_watch_label.style_context().add_class("tools-result");
+    time_page.pack_start(&time_watch_label, false, false, 0);
+    let watch_box = gtk::Box::new(Orientation::Horizontal, 4);
+    let time_start_btn = Button::with_label("Start");
+    let time_reset_btn = Button::with_label("Reset");
+    let time_log_btn = Button::with_label("Log");
+    time_log_btn.set_tooltip_text(Some("Add the stopwatch time to the durations"));
+    for btn in [&time_start_btn, &time_reset_btn, &time_log_btn] {
+        btn.style_context().add_class("tools-pct-btn");
+        btn.set_hexpand(true);
+        watch_box.pack_start(btn, true, true, 0);
+    }
+    time_page.pack_start(&watch_box, false, false, 0);
+    let time_lbl1 = Label::new(Some("Durations:"));
+    time_lbl1.set_xalign(0.0);
+    time_page.pack_start(&time_lbl1, false, false, 0);
+    let time_entry = Entry::new();
+    time_entry.set_placeholder_text(Some("1:30 + 45m"));
+    time_page.pack_start(&time_entry, false, false, 0);
+    let time_lbl2 = Label::new(Some("Hourly rate:"));
+    time_lbl2.set_xalign(0.0);
+    time_page.pack_start(&time_lbl2, false, false, 0);
+    let time_rate_entry = Entry::new();
+    time_rate_entry.set_placeholder_text(Some("0.00"));
+    time_page.pack_start(&time_rate_entry, false, false, 0);
+    let time_result_label = Label::new(Some("Time: 0:00:00  |  Billable: 0.00"));
+    time_result_label.style_context().add_class("tools-result");
+    time_page.pack_start(&time_result_label, false, false, 8);
+    let time_use_btn = Button::with_label("Use billable amount");
+    time_use_btn.style_context().add_class("tools-pct-btn");
+    time_page.pack_start(&time_use_btn, false, false, 0);
+
+    tools_notebook.append_page(&time_page, Some(&Label::new(Some("Time"))));
+
+    let ratio_page = gtk::Box::new(Orientation::Vertical, 8);
+    ratio_page.set_margin_top(12);
+    ratio_page.set_margin_start(8);
+    ratio_page.set_margin_end(8);
+    let ratio_lbl = Label::new(Some("Fill in three, leave one blank:"));
+    ratio_lbl.set_xalign(0.0);
+    ratio_page.pack_start(&ratio_lbl, false, false, 0);
+    let ratio_box = gtk::Box::new(Orientation::Horizontal, 4);
+    let mut ratio_entries = Vec::new();
+    for (i, hint) in ["a", "b", "c", "x"].iter().enumerate() {
+        if i > 0 {
+            ratio_box.pack_start(&Label::new(Some(if i == 2 { "=" } else { ":" })), false, false, 0);
+        }
+        let entry = Entry::new();
+        entry.set_placeholder_text(Some(hint));
+        entry.set_width_chars(4);
+        entry.set_hexpand(true);
+        ratio_box.pack_start(&entry, true, true, 0);
+        ratio_entries.push(entry);
+    }
+    ratio_page.pack_start(&ratio_box, false, false, 0);
+    let ratio_result_label = Label::new(Some("a : b = c : x"));
+    ratio_result_label.style_context().add_class("tools-result");
+    ratio_page.pack_start(&ratio_result_label, false, false, 8);
+    let ratio_use_btn = Button::with_label("Use answer");
+    ratio_use_btn.style_context().add_class("tools-pct-btn");
+    ratio_page.pack_start(&ratio_use_btn, false, false, 0);
+
+    tools_notebook.append_page(&ratio_page, Some(&Label::new(Some("Ratio"))));
+
     tools_view.pack_start(&tools_notebook, true, true, 0);
 
     let notes_view = gtk::Box::new(Orientation::Vertical, 8);
@@ -719,12 +791,42 @@ pub fn build(config: &Config) -> CalculatorUI {
     notes_result_scroll.add(&notes_result_label);
     notes_view.pack_start(&notes_result_scroll, true, true, 0);
 
+    let worksheet_view = gtk::Box::new(Orientation::Vertical, 8);
+    worksheet_view.style_context().add_class("notes-panel");
+    worksheet_view.set_margin_top(8);
+    worksheet_view.set_margin_start(12);
+    worksheet_view.set_margin_end(12);
+
+    let worksheet_header_box = gtk::Box::new(Orientation::Horizontal, 8);
+    let worksheet_back_btn = Button::with_label("\u{2190} Back");
+    worksheet_back_btn.style_context().add_class("back-button");
+    worksheet_back_btn.set_can_focus(false);
+    let worksheet_header = Label::new(Some("Worksheet"));
+    worksheet_header.style_context().add_class("mode-header");
+    worksheet_header_box.pack_start(&worksheet_back_btn, false, false, 0);
+    worksheet_header_box.pack_start(&worksheet_header, false, false, 8);
+    worksheet_view.pack_start(&worksheet_header_box, false, false, 0);
+    let worksheet_hint = Label::new(Some(
+        "Enter adds a line. Use names (rate = 1.25), ans, or #2 for line 2.",
+    ));
+    worksheet_hint.style_context().add_class("panel-item-label");
+    worksheet_hint.set_xalign(0.0);
+    worksheet_hint.set_line_wrap(true);
+    worksheet_view.pack_start(&worksheet_hint, false, false, 0);
+
+    let worksheet_scroll = ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    worksheet_scroll.set_vexpand(true);
+    let worksheet_list = gtk::Box::new(Orientation::Vertical, 2);
+    worksheet_scroll.add(&worksheet_list);
+    worksheet_view.pack_start(&worksheet_scroll, true, true, 0);
+
     let mode_panel_stack = Stack::new();
     mode_panel_stack.set_transition_type(StackTransitionType::Crossfade);
     mode_panel_stack.set_transition_duration(150);
     mode_panel_stack.add_named(&conv_view, "converter");
     mode_panel_stack.add_named(&tools_view, "tools");
     mode_panel_stack.add_named(&notes_view, "notes");
+    mode_panel_stack.add_named(&worksheet_view, "worksheet");
 
     let mode_panel_container = gtk::Box::new(Orientation::Vertical, 0);
     mode_panel_container.style_context().add_class("mode-panel-container");
@@ -741,8 +843,33 @@ pub fn build(config: &Config) -> CalculatorUI {
     content_box.pack_start(&mode_stack, true, true, 0);
     content_box.pack_start(&mode_panel_revealer, false, false, 0);
 
+    let clipboard_offer_btn = Button::with_label("");
+    clipboard_offer_btn.set_relief(gtk::ReliefStyle::None);
+    clipboard_offer_btn.set_tooltip_text(Some("Copy the result"));
+    clipboard_offer_btn.set_can_focus(false);
+    if let Some(label) = clipboard_offer_btn.child().and_then(|c| c.downcast::<Label>().ok()) {
+        label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+        label.set_xalign(0.0);
+    }
+    let clipboard_dismiss_btn = Button::with_label("\u{00d7}");
+    clipboard_dismiss_btn.set_relief(gtk::ReliefStyle::None);
+    clipboard_dismiss_btn.set_tooltip_text(Some("Dismiss"));
+    clipboard_dismiss_btn.set_can_focus(false);
+    a11y::describe(&clipboard_dismiss_btn, "dismiss", "Dismiss the clipboard result");
+
+    let clipboard_row = gtk::Box::new(Orientation::Horizontal, 4);
+    clipboard_row.style_context().add_class("clipboard-offer");
+    clipboard_row.pack_start(&clipboard_offer_btn, true, true, 0);
+    clipboard_row.pack_end(&clipboard_dismiss_btn, false, false, 0);
+
+    let clipboard_revealer = Revealer::new();
+    clipboard_revealer.set_transition_type(RevealerTransitionType::SlideDown);
+    clipboard_revealer.add(&clipboard_row);
+    clipboard_revealer.set_reveal_child(false);
+
     let vbox = gtk::Box::new(Orientation::Vertical, 0);
     vbox.pack_start(&outer_tab_bar, false, false, 0);
+    vbox.pack_start(&clipboard_revealer, false, false, 0);
     vbox.pack_start(&content_box, true, true, 0);
 
     window.add(&vbox);
@@ -751,19 +878,36 @@ pub fn build(config: &Config) -> CalculatorUI {
         window,
         expr_label,
         result_label,
+        exact_btn,
+        display_event_box,
+        display_long_press,
         preview_label,
         sci_grid,
-        nav_buttons,
-        action_buttons,
+        main_grid,
+        calc_view,
+        content_box,
+        keys,
         tab_bar,
+        clipboard_revealer,
+        clipboard_offer_btn,
+        clipboard_dismiss_btn,
         tab_add_btn,
         menu_popover,
         menu_basic_btn,
         menu_sci_btn,
         menu_help_btn,
+        menu_about_btn,
         menu_notes_btn,
+        menu_worksheet_btn,
         menu_converter_btn,
         menu_tools_btn,
+        menu_export_btn,
+        menu_keypad_btn,
+        menu_snippets_btn,
+        menu_steps_btn,
+        menu_palette_btn,
+        menu_copy_latex_btn,
+        menu_copy_plain_btn,
         menu_theme_btns,
         panel_revealer,
         panel_history_btn,
@@ -796,10 +940,116 @@ pub fn build(config: &Config) -> CalculatorUI {
         tax_amount_entry,
         tax_rate_entry,
         tax_result_label,
+        time_watch_label,
+        time_start_btn,
+        time_reset_btn,
+        time_log_btn,
+        time_entry,
+        time_rate_entry,
+        time_result_label,
+        time_use_btn,
+        ratio_entries,
+        ratio_result_label,
+        ratio_use_btn,
         tools_back_btn,
         notes_textview,
         notes_result_label,
         notes_back_btn,
-        angle_btn: angle_btn_ref,
+        worksheet_list,
+        worksheet_back_btn,
+    }
+}
+
+/// Replaces the buttons of both keypads with the ones `keypad` describes.
+/// Runs at startup and again whenever the keypad editor saves.
+pub fn fill_keypads(sci_grid: &Grid, main_grid: &Grid, keypad: &Keypad) -> Keys {
+    let mut keys = Keys::default();
+    for (grid, layout, sci) in [(sci_grid, &keypad.scientific, true), (main_grid, &keypad.basic, false)] {
+        for child in grid.children() {
+            grid.remove(&child);
+        }
+        let mut focus = Vec::new();
+        for spec in layout {
+            let b = key_button(spec, sci, &mut keys);
+            grid.attach(&b, spec.col as i32, spec.row as i32, spec.span as i32, 1);
+            if matches!(spec.action, ButtonAction::ToggleAngleMode) {
+                keys.angle_btn = Some(b.clone());
+            }
+            focus.push((spec.row, spec.col, b));
+        }
+        set_row_major_focus(grid, focus);
     }
+
+    keys.percent_long_press = keys
+        .action_buttons
+        .iter()
+        .filter(|(_, action)| *action == ButtonAction::PostfixOp(PostfixOp::Percent))
+        .map(|(b, _)| {
+            b.set_tooltip_text(Some("Hold for percent of, change and share"));
+            (b.clone(), gtk::GestureLongPress::new(b))
+        })
+        .collect();
+    keys
+}
+
+fn key_button(spec: &KeySpec, sci: bool, keys: &mut Keys) -> Button {
+    let b = Button::with_label(&spec.label);
+    b.style_context().add_class(spec.class);
+    b.set_hexpand(true);
+    b.set_vexpand(true);
+    b.set_can_focus(true);
+    // Focus, and its ring, only moves with the arrow keys, so a click
+    // doesn't leave Enter pressing that key instead of "=".
+    b.set_focus_on_click(false);
+    a11y::describe(&b, &a11y::key_name(spec.action), a11y::key_description(spec.class));
+    keys.action_buttons.push((b.clone(), spec.action));
+    // Wide keys get a nav entry per covered column; the rightmost one is
+    // registered first so it is the one matched when the key has focus.
+    for col in (spec.col..spec.col + spec.span).rev() {
+        keys.nav_buttons.push(NavButton {
+            button: b.clone(),
+            col,
+            row: spec.row,
+            scientific: sci,
+        });
+    }
+    b
+}
+
+/// Portrait stacks the display over the keypads, scientific on the left.
+/// Landscape gives the display and scientific keys the left column and the
+/// basic keypad the full height on the right.
+pub fn place_panes(view: &Grid, display: &impl IsA<gtk::Widget>, sci: &Grid, main: &Grid, landscape: bool) {
+    for child in view.children() {
+        view.remove(&child);
+    }
+    if landscape {
+        view.attach(display, 0, 0, 1, 1);
+        view.attach(sci, 0, 1, 1, 1);
+        view.attach(main, 1, 0, 1, 2);
+    } else {
+        view.attach(display, 0, 0, 2, 1);
+        view.attach(sci, 0, 1, 1, 1);
+        view.attach(main, 1, 1, 1, 1);
+    }
+}
+
+/// In landscape the converter and other mode panels open on the left, next
+/// to the scientific keys, rather than past the keypad on the right.
+pub fn place_mode_panel(content: &gtk::Box, mode_panel: &Revealer, landscape: bool) {
+    if landscape {
+        content.reorder_child(mode_panel, 0);
+        mode_panel.set_transition_type(RevealerTransitionType::SlideRight);
+    } else {
+        content.reorder_child(mode_panel, -1);
+        mode_panel.set_transition_type(RevealerTransitionType::SlideLeft);
+    }
+}
+
+/// Tab walks the keypad reading order (row by row, left to right) instead of
+/// GTK's allocation-based guess, which skips around the wide keys.
+fn set_row_major_focus(grid: &Grid, mut keys: Vec<(usize, usize, Button)>) {
+    keys.sort_by_key(|(row, col, _)| (*row, *col));
+    let chain: Vec<gtk::Widget> = keys.into_iter().map(|(_, _, b)| b.upcast()).collect();
+    grid.set_focus_chain(&chain);
 }
@@ -0,0 +1,167 @@
+use crate::app::message::Message;
+use crate::domain::convert::UnitPair;
+use crate::domain::search::fuzzy_score;
+use crate::domain::types::BinaryOp;
+use crate::ui::a11y;
+use crate::ui::keyboard;
+use crate::ui::layout::{ButtonAction, ACTION_CODES};
+
+/// Keymap actions offered by the command palette, in the order listed when
+/// nothing has been typed.
+const ACTIONS: &[(&str, &str)] = &[
+    ("toggle_scientific", "Toggle scientific mode"),
+    ("toggle_angle", "Toggle angle unit (degrees / radians)"),
+    ("toggle_interval", "Toggle interval arithmetic"),
+    ("toggle_money", "Toggle money mode (two decimals)"),
+    ("open_converter", "Open unit converter"),
+    ("open_tools", "Open quick tools"),
+    ("open_notes", "Open math notes"),
+    ("open_worksheet", "Open worksheet"),
+    ("back_to_calc", "Back to calculator"),
+    ("toggle_history", "Show or hide history"),
+    ("toggle_memory", "Show or hide memory"),
+    ("toggle_pinned", "Show or hide pinned results"),
+    ("show_steps", "Show steps"),
+    ("pin_result", "Pin result"),
+    ("memory_store", "Store result in memory"),
+    ("copy_latex", "Copy as LaTeX"),
+    ("copy_plain", "Copy as plain text"),
+    ("paste", "Paste"),
+    ("export_history", "Export history as JSON"),
+    ("export_history_csv", "Export history as CSV"),
+    ("undo", "Undo"),
+    ("clear", "Clear"),
+    ("new_tab", "New tab"),
+    ("close_tab", "Close tab"),
+    ("next_tab", "Next tab"),
+    ("prev_tab", "Previous tab"),
+    ("toggle_theme", "Cycle theme"),
+    ("zoom_in", "Zoom in"),
+    ("zoom_out", "Zoom out"),
+    ("zoom_reset", "Reset zoom"),
+    ("show_help", "Keyboard shortcuts"),
+    ("quit", "Quit"),
+];
+
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub title: String,
+    /// Extra words the command can be found by.
+    pub keywords: String,
+    /// Shown beside the title: the bound key, or the name to type for
+    /// functions and constants.
+    pub hint: Option<String>,
+    pub message: Message,
+}
+
+impl Command {
+    fn new(title: String, keywords: String, hint: Option<String>, message: Message) -> Self {
+        Self { title, keywords, hint, message }
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Everything the palette can do: app actions, inserting functions and
+/// constants, memory keys, and converting the result with each of `pairs`.
+pub fn all(pairs: &[UnitPair]) -> Vec<Command> {
+    let mut commands: Vec<Command> = ACTIONS
+        .iter()
+        .filter_map(|(action, title)| {
+            let message = keyboard::parse_action(action)?;
+            Some(Command::new(title.to_string(), action.replace('_', " "), keyboard::shortcut(action), message))
+        })
+        .collect();
+    for (code, action) in ACTION_CODES {
+        let name = a11y::key_name(*action);
+        let (title, keywords) = match action {
+            ButtonAction::UnaryFunc(_) | ButtonAction::Constant(..) => (format!("Insert {}", name), "function constant"),
+            ButtonAction::PostfixOp(_) => (format!("Apply {}", name), "operator"),
+            ButtonAction::BinaryOp(BinaryOp::PlusMinus) => (format!("Insert {}", name), "uncertainty error tolerance"),
+            ButtonAction::MemoryClear
+            | ButtonAction::MemoryRecall
+            | ButtonAction::MemoryAdd
+            | ButtonAction::MemorySubtract => (capitalize(&name), "memory"),
+            _ => continue,
+        };
+        commands.push(Command::new(title, keywords.to_string(), Some(code.to_string()), action.message()));
+    }
+    for pair in pairs {
+        commands.push(Command::new(
+            format!("Convert {}", pair.label()),
+            format!("{:?} {} {} unit", pair.category, pair.from, pair.to),
+            None,
+            Message::ConvertResult(*pair),
+        ));
+    }
+    commands
+}
+
+fn term_score(term: &str, command: &Command) -> Option<i32> {
+    let title = command.title.to_lowercase();
+    let hint = command.hint.as_deref().unwrap_or("").to_lowercase();
+    let keywords = command.keywords.to_lowercase();
+    if title.split_whitespace().any(|w| w.starts_with(term)) || hint == term {
+        return Some(2000 + term.len() as i32);
+    }
+    if title.contains(term) || keywords.contains(term) || hint.contains(term) {
+        return Some(1000 + term.len() as i32);
+    }
+    fuzzy_score(term, &title)
+}
+
+/// Commands matching every whitespace-separated term of `query`, best first.
+/// Word starts rank above substrings, which rank above fuzzy matches.
+pub fn filter<'a>(commands: &'a [Command], query: &str) -> Vec<&'a Command> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let mut scored: Vec<(i32, usize, &Command)> = commands
+        .iter()
+        .enumerate()
+        .filter_map(|(i, command)| {
+            let mut total = 0;
+            for term in &terms {
+                total += term_score(term, command)?;
+            }
+            Some((total, i, command))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored.into_iter().map(|(_, _, c)| c).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::convert::COMMON_PAIRS;
+
+    fn titles(found: Vec<&Command>) -> Vec<&str> {
+        found.iter().map(|c| c.title.as_str()).collect()
+    }
+
+    #[test]
+    fn every_action_is_listed() {
+        let commands = all(COMMON_PAIRS);
+        assert!(ACTIONS.iter().all(|(_, title)| commands.iter().any(|c| c.title == *title)));
+        assert!(commands.iter().any(|c| c.title == "Insert square root" && c.hint.as_deref() == Some("sqrt")));
+        assert!(commands.iter().any(|c| c.title == "Memory recall"));
+        assert!(commands.iter().any(|c| matches!(c.message, Message::ConvertResult(p) if p == COMMON_PAIRS[0])));
+    }
+
+    #[test]
+    fn filter_ranks_word_starts_first() {
+        let commands = all(COMMON_PAIRS);
+        assert_eq!(filter(&commands, "").len(), commands.len());
+        assert_eq!(titles(filter(&commands, "sci"))[0], "Toggle scientific mode");
+        assert_eq!(titles(filter(&commands, "sqrt"))[0], "Insert square root");
+        assert_eq!(titles(filter(&commands, "export csv")), ["Export history as CSV"]);
+        assert_eq!(titles(filter(&commands, "radians"))[0], "Toggle angle unit (degrees / radians)");
+        assert_eq!(titles(filter(&commands, "conv km"))[0], "Convert mi \u{2192} km");
+        assert!(filter(&commands, "zzzz").is_empty());
+    }
+}
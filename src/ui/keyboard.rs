@@ -1,9 +1,12 @@
+#[cfg(feature = "gui")]
 use gtk::gdk;
+#[cfg(feature = "gui")]
 use gtk::gdk::keys::constants as key;
 use std::collections::HashMap;
 use std::sync::OnceLock;
 
 use crate::app::message::Message;
+use crate::domain::ast::CopyStyle;
 use crate::domain::types::*;
 use crate::services::config::KeybindingsConfig;
 
@@ -16,44 +19,73 @@ pub enum Direction {
 }
 
 static KEYMAP: OnceLock<HashMap<String, Message>> = OnceLock::new();
+static SHORTCUTS: OnceLock<HashMap<String, String>> = OnceLock::new();
 
 pub fn init_keymap(config: &KeybindingsConfig) {
     let _ = KEYMAP.set(build_keymap(config));
+    let _ = SHORTCUTS.set(build_shortcuts(config));
 }
 
+/// The key an action is bound to, for showing next to it. When several keys
+/// run the same action the shortest is picked.
+#[cfg(feature = "gui")]
+pub fn shortcut(action: &str) -> Option<String> {
+    SHORTCUTS.get().and_then(|m| m.get(action).cloned())
+}
+
+#[cfg(feature = "gui")]
 pub fn map_key(event: &gdk::EventKey) -> Message {
-    let combo = event_to_combo(event);
+    lookup(&event_to_combo(event))
+}
+
+/// Resolves a combo string such as `Ctrl+z` or `Return` against the active keymap.
+pub fn lookup(combo: &str) -> Message {
     if combo.is_empty() {
         return Message::Noop;
     }
     KEYMAP
         .get()
-        .and_then(|m| m.get(&combo).cloned())
+        .and_then(|m| m.get(combo).cloned())
         .unwrap_or(Message::Noop)
 }
 
-fn build_keymap(config: &KeybindingsConfig) -> HashMap<String, Message> {
-    let scheme = match config.scheme.as_str() {
+// Combo to action name, after custom bindings are applied.
+fn bindings(config: &KeybindingsConfig) -> HashMap<String, String> {
+    let mut map = match config.scheme.as_str() {
         "emacs" => emacs_scheme(),
         _ => default_scheme(),
     };
-
-    let mut map = HashMap::new();
-    for (combo, action_name) in &scheme {
-        if let Some(msg) = parse_action(action_name) {
-            map.insert(combo.clone(), msg);
-        }
-    }
     for (combo, action_name) in &config.custom {
         if action_name == "none" || action_name == "unbound" {
             map.remove(combo);
-        } else if let Some(msg) = parse_action(action_name) {
-            map.insert(combo.clone(), msg);
+        } else if parse_action(action_name).is_some() {
+            map.insert(combo.clone(), action_name.clone());
+        }
+    }
+    map
+}
+
+fn build_keymap(config: &KeybindingsConfig) -> HashMap<String, Message> {
+    bindings(config)
+        .into_iter()
+        .filter_map(|(combo, action_name)| parse_action(&action_name).map(|msg| (combo, msg)))
+        .collect()
+}
+
+fn build_shortcuts(config: &KeybindingsConfig) -> HashMap<String, String> {
+    let mut map: HashMap<String, String> = HashMap::new();
+    for (combo, action_name) in bindings(config) {
+        match map.get(&action_name) {
+            Some(current) if (current.len(), current.as_str()) <= (combo.len(), combo.as_str()) => {}
+            _ => {
+                map.insert(action_name, combo);
+            }
         }
     }
     map
 }
 
+#[cfg(feature = "gui")]
 fn event_to_combo(event: &gdk::EventKey) -> String {
     let keyval = event.keyval();
     let state = event.state();
@@ -87,7 +119,7 @@ fn event_to_combo(event: &gdk::EventKey) -> String {
     String::new()
 }
 
-fn build_combo(ctrl: bool, alt: bool, shift: bool, key_name: &str) -> String {
+pub fn build_combo(ctrl: bool, alt: bool, shift: bool, key_name: &str) -> String {
     let mut s = String::new();
     if ctrl { s.push_str("Ctrl+"); }
     if alt { s.push_str("Alt+"); }
@@ -96,7 +128,7 @@ fn build_combo(ctrl: bool, alt: bool, shift: bool, key_name: &str) -> String {
     s
 }
 
-fn parse_action(name: &str) -> Option<Message> {
+pub fn parse_action(name: &str) -> Option<Message> {
     match name {
         "digit_0" => Some(Message::Digit('0')),
         "digit_1" => Some(Message::Digit('1')),
@@ -114,6 +146,7 @@ fn parse_action(name: &str) -> Option<Message> {
         "multiply" => Some(Message::BinaryOp(BinaryOp::Multiply)),
         "divide" => Some(Message::BinaryOp(BinaryOp::Divide)),
         "power" => Some(Message::BinaryOp(BinaryOp::Power)),
+        "plus_minus" => Some(Message::BinaryOp(BinaryOp::PlusMinus)),
         "percent" => Some(Message::PostfixOp(PostfixOp::Percent)),
         "factorial" => Some(Message::PostfixOp(PostfixOp::Factorial)),
         "equals" => Some(Message::Equals),
@@ -143,10 +176,26 @@ fn parse_action(name: &str) -> Option<Message> {
         "open_converter" => Some(Message::OpenConverter),
         "open_tools" => Some(Message::OpenTools),
         "open_notes" => Some(Message::OpenNotes),
+        "open_worksheet" => Some(Message::OpenWorksheet),
         "open_menu" => Some(Message::OpenMenu),
         "back_to_calc" => Some(Message::CloseMode),
         "export_history" => Some(Message::ExportHistoryJson),
         "show_help" => Some(Message::ShowHelp),
+        "show_steps" => Some(Message::ShowSteps),
+        "paste" => Some(Message::RequestPaste),
+        "toggle_angle" => Some(Message::ToggleAngleMode),
+        "toggle_interval" => Some(Message::ToggleIntervalArithmetic),
+        "toggle_money" => Some(Message::ToggleMoneyMode),
+        "open_interval" => Some(Message::Type('[')),
+        "interval_separator" => Some(Message::Type(',')),
+        "close_interval" => Some(Message::Type(']')),
+        "export_history_csv" => Some(Message::ExportHistoryCsv),
+        "copy_latex" => Some(Message::CopyAs(CopyStyle::Latex)),
+        "copy_plain" => Some(Message::CopyAs(CopyStyle::Plain)),
+        "command_palette" => Some(Message::OpenPalette),
+        "zoom_in" => Some(Message::ZoomIn),
+        "zoom_out" => Some(Message::ZoomOut),
+        "zoom_reset" => Some(Message::ZoomReset),
         _ => None,
     }
 }
@@ -159,6 +208,7 @@ fn default_scheme() -> HashMap<String, String> {
     m.insert("*".into(), "multiply".into());
     m.insert("/".into(), "divide".into());
     m.insert("^".into(), "power".into());
+    m.insert("\u{00b1}".into(), "plus_minus".into());
     m.insert("%".into(), "percent".into());
     m.insert("!".into(), "factorial".into());
     m.insert(".".into(), "decimal".into());
@@ -171,6 +221,9 @@ fn default_scheme() -> HashMap<String, String> {
     m.insert("Ctrl+Delete".into(), "clear".into());
     m.insert("(".into(), "left_paren".into());
     m.insert(")".into(), "right_paren".into());
+    m.insert("[".into(), "open_interval".into());
+    m.insert(",".into(), "interval_separator".into());
+    m.insert("]".into(), "close_interval".into());
     m.insert("n".into(), "toggle_sign".into());
     m.insert("h".into(), "navigate_left".into());
     m.insert("j".into(), "navigate_down".into());
@@ -194,9 +247,17 @@ fn default_scheme() -> HashMap<String, String> {
     m.insert("Ctrl+e".into(), "open_converter".into());
     m.insert("Ctrl+r".into(), "open_tools".into());
     m.insert("Ctrl+n".into(), "open_notes".into());
+    m.insert("Ctrl+j".into(), "open_worksheet".into());
     m.insert("Ctrl+m".into(), "toggle_memory".into());
     m.insert("Ctrl+p".into(), "toggle_pinned".into());
     m.insert("Ctrl+h".into(), "toggle_history".into());
+    m.insert("Ctrl+i".into(), "show_steps".into());
+    m.insert("Ctrl+v".into(), "paste".into());
+    m.insert("Ctrl+k".into(), "command_palette".into());
+    m.insert("Ctrl+=".into(), "zoom_in".into());
+    m.insert("Ctrl++".into(), "zoom_in".into());
+    m.insert("Ctrl+-".into(), "zoom_out".into());
+    m.insert("Ctrl+0".into(), "zoom_reset".into());
     m.insert("Tab".into(), "next_tab".into());
     m.insert("Shift+Tab".into(), "prev_tab".into());
     m.insert("Ctrl+Shift+e".into(), "export_history".into());
@@ -219,6 +280,7 @@ fn emacs_scheme() -> HashMap<String, String> {
     m.insert("Ctrl+/".into(), "undo".into());
     m.insert("Alt+p".into(), "toggle_pinned".into());
     m.insert("Alt+n".into(), "open_notes".into());
+    m.insert("Ctrl+y".into(), "paste".into());
     m
 }
 
@@ -269,6 +331,24 @@ mod tests {
         assert!(!map.contains_key("h"));
     }
 
+    #[test]
+    fn shortcuts_prefer_the_shortest_key() {
+        let config = KeybindingsConfig {
+            scheme: "default".into(),
+            custom: {
+                let mut m = HashMap::new();
+                m.insert("Ctrl+Shift+u".into(), "toggle_angle".into());
+                m.insert("Ctrl+e".into(), "unbound".into());
+                m
+            },
+        };
+        let shortcuts = build_shortcuts(&config);
+        assert_eq!(shortcuts.get("undo").map(String::as_str), Some("u"));
+        assert_eq!(shortcuts.get("clear").map(String::as_str), Some("Ctrl+Delete"));
+        assert_eq!(shortcuts.get("toggle_angle").map(String::as_str), Some("Ctrl+Shift+u"));
+        assert_eq!(shortcuts.get("open_converter"), None);
+    }
+
     #[test]
     fn parse_all_actions() {
         let actions = [
@@ -278,8 +358,10 @@ mod tests {
             "activate", "toggle_theme", "toggle_scientific", "quit", "undo",
             "new_tab", "close_tab", "next_tab", "prev_tab", "toggle_history",
             "toggle_memory", "toggle_pinned", "pin_result", "memory_store",
-            "open_converter", "open_tools", "open_notes", "open_menu",
-            "back_to_calc", "export_history", "show_help",
+            "open_converter", "open_tools", "open_notes", "open_worksheet", "open_menu",
+            "back_to_calc", "export_history", "show_help", "show_steps",
+            "paste", "toggle_angle", "export_history_csv", "copy_latex",
+            "copy_plain", "command_palette", "zoom_in", "zoom_out", "zoom_reset",
         ];
         for a in actions {
             assert!(parse_action(a).is_some(), "Failed to parse: {}", a);
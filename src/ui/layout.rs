@@ -0,0 +1,410 @@
+use std::borrow::Cow;
+
+use crate::app::message::Message;
+use crate::domain::types::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ButtonAction {
+    Digit(char),
+    Decimal,
+    BinaryOp(BinaryOp),
+    UnaryFunc(UnaryFunc),
+    PostfixOp(PostfixOp),
+    Constant(f64, &'static str),
+    LeftParen,
+    RightParen,
+    Equals,
+    Clear,
+    ToggleSign,
+    EE,
+    MemoryClear,
+    MemoryRecall,
+    MemoryAdd,
+    MemorySubtract,
+    ToggleAngleMode,
+}
+
+impl ButtonAction {
+    pub fn message(self) -> Message {
+        match self {
+            Self::Digit(d) => Message::Digit(d),
+            Self::Decimal => Message::Decimal,
+            Self::BinaryOp(op) => Message::BinaryOp(op),
+            Self::UnaryFunc(f) => Message::UnaryFunc(f),
+            Self::PostfixOp(op) => Message::PostfixOp(op),
+            Self::Constant(val, name) => Message::Constant(val, name),
+            Self::LeftParen => Message::LeftParen,
+            Self::RightParen => Message::RightParen,
+            Self::Equals => Message::Equals,
+            Self::Clear => Message::Clear,
+            Self::ToggleSign => Message::ToggleSign,
+            Self::EE => Message::EE,
+            Self::MemoryClear => Message::MemoryClear,
+            Self::MemoryRecall => Message::MemoryRecall,
+            Self::MemoryAdd => Message::MemoryAdd,
+            Self::MemorySubtract => Message::MemorySubtract,
+            Self::ToggleAngleMode => Message::ToggleAngleMode,
+        }
+    }
+
+    /// The keypad action that sends `msg`, if any; the inverse of `message`.
+    pub fn for_message(msg: &Message) -> Option<Self> {
+        Some(match *msg {
+            Message::Digit(d) => Self::Digit(d),
+            Message::Decimal => Self::Decimal,
+            Message::BinaryOp(op) => Self::BinaryOp(op),
+            Message::UnaryFunc(f) => Self::UnaryFunc(f),
+            Message::PostfixOp(op) => Self::PostfixOp(op),
+            Message::Constant(val, name) => Self::Constant(val, name),
+            Message::LeftParen => Self::LeftParen,
+            Message::RightParen => Self::RightParen,
+            Message::Equals => Self::Equals,
+            Message::Clear => Self::Clear,
+            Message::ToggleSign => Self::ToggleSign,
+            Message::EE => Self::EE,
+            Message::MemoryClear => Self::MemoryClear,
+            Message::MemoryRecall => Self::MemoryRecall,
+            Message::MemoryAdd => Self::MemoryAdd,
+            Message::MemorySubtract => Self::MemorySubtract,
+            Message::ToggleAngleMode => Self::ToggleAngleMode,
+            _ => return None,
+        })
+    }
+}
+
+/// One keypad button: what it shows, where it sits and what it does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySpec {
+    pub label: Cow<'static, str>,
+    pub class: &'static str,
+    pub action: ButtonAction,
+    pub col: usize,
+    pub row: usize,
+    pub span: usize,
+}
+
+impl KeySpec {
+    /// A user-defined key; the style class follows from the action.
+    pub fn custom(label: String, action: ButtonAction, col: usize, row: usize, span: usize) -> Self {
+        Self { label: Cow::Owned(label), class: default_class(action), action, col, row, span }
+    }
+}
+
+const fn key(label: &'static str, class: &'static str, action: ButtonAction, col: usize, row: usize) -> KeySpec {
+    KeySpec { label: Cow::Borrowed(label), class, action, col, row, span: 1 }
+}
+
+const fn wide(label: &'static str, class: &'static str, action: ButtonAction, col: usize, row: usize, span: usize) -> KeySpec {
+    KeySpec { label: Cow::Borrowed(label), class, action, col, row, span }
+}
+
+pub const BASIC: &[KeySpec] = &[
+    key("AC", "clear-button", ButtonAction::Clear, 0, 0),
+    key("+/\u{2212}", "util-button", ButtonAction::ToggleSign, 1, 0),
+    key("%", "util-button", ButtonAction::PostfixOp(PostfixOp::Percent), 2, 0),
+    key("\u{00f7}", "op-button", ButtonAction::BinaryOp(BinaryOp::Divide), 3, 0),
+    key("7", "digit-button", ButtonAction::Digit('7'), 0, 1),
+    key("8", "digit-button", ButtonAction::Digit('8'), 1, 1),
+    key("9", "digit-button", ButtonAction::Digit('9'), 2, 1),
+    key("\u{00d7}", "op-button", ButtonAction::BinaryOp(BinaryOp::Multiply), 3, 1),
+    key("4", "digit-button", ButtonAction::Digit('4'), 0, 2),
+    key("5", "digit-button", ButtonAction::Digit('5'), 1, 2),
+    key("6", "digit-button", ButtonAction::Digit('6'), 2, 2),
+    key("\u{2212}", "op-button", ButtonAction::BinaryOp(BinaryOp::Subtract), 3, 2),
+    key("1", "digit-button", ButtonAction::Digit('1'), 0, 3),
+    key("2", "digit-button", ButtonAction::Digit('2'), 1, 3),
+    key("3", "digit-button", ButtonAction::Digit('3'), 2, 3),
+    key("+", "op-button", ButtonAction::BinaryOp(BinaryOp::Add), 3, 3),
+    wide("0", "digit-button", ButtonAction::Digit('0'), 0, 4, 2),
+    key(".", "digit-button", ButtonAction::Decimal, 2, 4),
+    key("=", "equals-button", ButtonAction::Equals, 3, 4),
+];
+
+pub const SCIENTIFIC: &[KeySpec] = &[
+    key("MC", "memory-button", ButtonAction::MemoryClear, 0, 0),
+    key("MR", "memory-button", ButtonAction::MemoryRecall, 1, 0),
+    key("M+", "memory-button", ButtonAction::MemoryAdd, 2, 0),
+    key("M\u{2212}", "memory-button", ButtonAction::MemorySubtract, 0, 1),
+    key("(", "paren-button", ButtonAction::LeftParen, 1, 1),
+    key(")", "paren-button", ButtonAction::RightParen, 2, 1),
+    key("Deg", "toggle-button", ButtonAction::ToggleAngleMode, 0, 2),
+    key("x\u{00b2}", "power-button", ButtonAction::PostfixOp(PostfixOp::Square), 1, 2),
+    key("x\u{00b3}", "power-button", ButtonAction::PostfixOp(PostfixOp::Cube), 2, 2),
+    key("x\u{02b8}", "power-button", ButtonAction::BinaryOp(BinaryOp::Power), 0, 3),
+    key("\u{215f}x", "power-button", ButtonAction::PostfixOp(PostfixOp::Reciprocal), 1, 3),
+    key("\u{221a}", "power-button", ButtonAction::UnaryFunc(UnaryFunc::Sqrt), 2, 3),
+    key("\u{00b3}\u{221a}", "power-button", ButtonAction::UnaryFunc(UnaryFunc::Cbrt), 0, 4),
+    key("sin", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Sin), 1, 4),
+    key("cos", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Cos), 2, 4),
+    key("tan", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Tan), 0, 5),
+    key("ln", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Ln), 1, 5),
+    key("log", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Log10), 2, 5),
+    key("n!", "function-button", ButtonAction::PostfixOp(PostfixOp::Factorial), 0, 6),
+    key("\u{03c0}", "constant-button", ButtonAction::Constant(std::f64::consts::PI, "\u{03c0}"), 1, 6),
+    key("e", "constant-button", ButtonAction::Constant(std::f64::consts::E, "e"), 2, 6),
+    key("EE", "function-button", ButtonAction::EE, 0, 7),
+    key("sin\u{207b}\u{00b9}", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Asin), 1, 7),
+    key("cos\u{207b}\u{00b9}", "function-button", ButtonAction::UnaryFunc(UnaryFunc::Acos), 2, 7),
+];
+
+/// Stable names for every action, used by saved keypad layouts.
+pub const ACTION_CODES: &[(&str, ButtonAction)] = &[
+    ("0", ButtonAction::Digit('0')),
+    ("1", ButtonAction::Digit('1')),
+    ("2", ButtonAction::Digit('2')),
+    ("3", ButtonAction::Digit('3')),
+    ("4", ButtonAction::Digit('4')),
+    ("5", ButtonAction::Digit('5')),
+    ("6", ButtonAction::Digit('6')),
+    ("7", ButtonAction::Digit('7')),
+    ("8", ButtonAction::Digit('8')),
+    ("9", ButtonAction::Digit('9')),
+    ("decimal", ButtonAction::Decimal),
+    ("add", ButtonAction::BinaryOp(BinaryOp::Add)),
+    ("subtract", ButtonAction::BinaryOp(BinaryOp::Subtract)),
+    ("multiply", ButtonAction::BinaryOp(BinaryOp::Multiply)),
+    ("divide", ButtonAction::BinaryOp(BinaryOp::Divide)),
+    ("power", ButtonAction::BinaryOp(BinaryOp::Power)),
+    ("mod", ButtonAction::BinaryOp(BinaryOp::Modulo)),
+    ("plus_minus", ButtonAction::BinaryOp(BinaryOp::PlusMinus)),
+    ("sin", ButtonAction::UnaryFunc(UnaryFunc::Sin)),
+    ("cos", ButtonAction::UnaryFunc(UnaryFunc::Cos)),
+    ("tan", ButtonAction::UnaryFunc(UnaryFunc::Tan)),
+    ("asin", ButtonAction::UnaryFunc(UnaryFunc::Asin)),
+    ("acos", ButtonAction::UnaryFunc(UnaryFunc::Acos)),
+    ("atan", ButtonAction::UnaryFunc(UnaryFunc::Atan)),
+    ("sinh", ButtonAction::UnaryFunc(UnaryFunc::Sinh)),
+    ("cosh", ButtonAction::UnaryFunc(UnaryFunc::Cosh)),
+    ("tanh", ButtonAction::UnaryFunc(UnaryFunc::Tanh)),
+    ("ln", ButtonAction::UnaryFunc(UnaryFunc::Ln)),
+    ("log", ButtonAction::UnaryFunc(UnaryFunc::Log10)),
+    ("sqrt", ButtonAction::UnaryFunc(UnaryFunc::Sqrt)),
+    ("cbrt", ButtonAction::UnaryFunc(UnaryFunc::Cbrt)),
+    ("abs", ButtonAction::UnaryFunc(UnaryFunc::Abs)),
+    ("exp", ButtonAction::UnaryFunc(UnaryFunc::Exp)),
+    ("square", ButtonAction::PostfixOp(PostfixOp::Square)),
+    ("cube", ButtonAction::PostfixOp(PostfixOp::Cube)),
+    ("reciprocal", ButtonAction::PostfixOp(PostfixOp::Reciprocal)),
+    ("factorial", ButtonAction::PostfixOp(PostfixOp::Factorial)),
+    ("percent", ButtonAction::PostfixOp(PostfixOp::Percent)),
+    ("pi", ButtonAction::Constant(std::f64::consts::PI, "\u{03c0}")),
+    ("e", ButtonAction::Constant(std::f64::consts::E, "e")),
+    ("lparen", ButtonAction::LeftParen),
+    ("rparen", ButtonAction::RightParen),
+    ("equals", ButtonAction::Equals),
+    ("clear", ButtonAction::Clear),
+    ("negate", ButtonAction::ToggleSign),
+    ("ee", ButtonAction::EE),
+    ("mc", ButtonAction::MemoryClear),
+    ("mr", ButtonAction::MemoryRecall),
+    ("m+", ButtonAction::MemoryAdd),
+    ("m-", ButtonAction::MemorySubtract),
+    ("angle", ButtonAction::ToggleAngleMode),
+];
+
+impl ButtonAction {
+    pub fn code(self) -> &'static str {
+        ACTION_CODES
+            .iter()
+            .find(|(_, a)| *a == self)
+            .map(|(code, _)| *code)
+            .unwrap_or("")
+    }
+
+    pub fn from_code(code: &str) -> Result<Self, String> {
+        ACTION_CODES
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, a)| *a)
+            .ok_or_else(|| format!("Unknown key action '{}'", code))
+    }
+}
+
+/// Style class a key gets when the layout doesn't say otherwise.
+pub fn default_class(action: ButtonAction) -> &'static str {
+    match action {
+        ButtonAction::Digit(_) | ButtonAction::Decimal => "digit-button",
+        ButtonAction::BinaryOp(BinaryOp::Power) => "power-button",
+        ButtonAction::BinaryOp(_) => "op-button",
+        ButtonAction::UnaryFunc(UnaryFunc::Sqrt | UnaryFunc::Cbrt) => "power-button",
+        ButtonAction::UnaryFunc(_) | ButtonAction::EE => "function-button",
+        ButtonAction::PostfixOp(PostfixOp::Percent) | ButtonAction::ToggleSign => "util-button",
+        ButtonAction::PostfixOp(PostfixOp::Factorial) => "function-button",
+        ButtonAction::PostfixOp(_) => "power-button",
+        ButtonAction::Constant(..) => "constant-button",
+        ButtonAction::LeftParen | ButtonAction::RightParen => "paren-button",
+        ButtonAction::Equals => "equals-button",
+        ButtonAction::Clear => "clear-button",
+        ButtonAction::MemoryClear
+        | ButtonAction::MemoryRecall
+        | ButtonAction::MemoryAdd
+        | ButtonAction::MemorySubtract => "memory-button",
+        ButtonAction::ToggleAngleMode => "toggle-button",
+    }
+}
+
+/// Largest grid a custom layout may use in either direction.
+pub const MAX_GRID: usize = 12;
+
+/// The two keypad grids the frontends render.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keypad {
+    pub basic: Vec<KeySpec>,
+    pub scientific: Vec<KeySpec>,
+}
+
+impl Default for Keypad {
+    fn default() -> Self {
+        Self { basic: BASIC.to_vec(), scientific: SCIENTIFIC.to_vec() }
+    }
+}
+
+impl Keypad {
+    /// Checks both layouts. The scientific keys may all be removed, but a
+    /// basic keypad without any keys would leave nothing to press.
+    pub fn validate(&self) -> Result<(), String> {
+        validate(&self.basic).map_err(|e| format!("basic: {}", e))?;
+        validate(&self.scientific).map_err(|e| format!("scientific: {}", e))?;
+        if self.basic.is_empty() {
+            return Err("basic: the keypad needs at least one key".into());
+        }
+        Ok(())
+    }
+}
+
+/// Rejects layouts the grid can't render: empty labels, zero-width keys,
+/// keys past `MAX_GRID` or two keys sharing a cell.
+pub fn validate(layout: &[KeySpec]) -> Result<(), String> {
+    let mut taken = [[false; MAX_GRID]; MAX_GRID];
+    for spec in layout {
+        if spec.label.trim().is_empty() {
+            return Err(format!("Key at row {}, column {} has no label", spec.row + 1, spec.col + 1));
+        }
+        if spec.span == 0 || spec.col + spec.span > MAX_GRID || spec.row >= MAX_GRID {
+            return Err(format!("Key '{}' does not fit in a {}\u{00d7}{} grid", spec.label, MAX_GRID, MAX_GRID));
+        }
+        for cell in &mut taken[spec.row][spec.col..spec.col + spec.span] {
+            if *cell {
+                return Err(format!("Key '{}' overlaps another key in row {}", spec.label, spec.row + 1));
+            }
+            *cell = true;
+        }
+    }
+    Ok(())
+}
+
+/// Width of the layout in grid columns.
+#[cfg(any(feature = "tui", test))]
+pub fn columns(layout: &[KeySpec]) -> usize {
+    layout.iter().map(|k| k.col + k.span).max().unwrap_or(0)
+}
+
+/// Keys grouped by row and ordered left to right.
+#[cfg(any(feature = "tui", test))]
+pub fn rows(layout: &[KeySpec]) -> Vec<Vec<KeySpec>> {
+    let count = layout.iter().map(|k| k.row + 1).max().unwrap_or(0);
+    let mut rows = vec![Vec::new(); count];
+    for spec in layout {
+        rows[spec.row].push(spec.clone());
+    }
+    for row in &mut rows {
+        row.sort_by_key(|k| k.col);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_tiles_grid(layout: &[KeySpec]) {
+        let cols = columns(layout);
+        let rows = rows(layout);
+        let mut seen = vec![vec![false; cols]; rows.len()];
+        for spec in layout {
+            for cell in &mut seen[spec.row][spec.col..spec.col + spec.span] {
+                assert!(!*cell, "{:?} overlaps in row {}", spec.label, spec.row);
+                *cell = true;
+            }
+        }
+        assert!(seen.iter().flatten().all(|&s| s), "layout leaves holes");
+    }
+
+    #[test]
+    fn layouts_tile_their_grids() {
+        assert_tiles_grid(BASIC);
+        assert_tiles_grid(SCIENTIFIC);
+        assert_eq!(columns(BASIC), 4);
+        assert_eq!(columns(SCIENTIFIC), 3);
+    }
+
+    #[test]
+    fn messages_map_back_to_keys() {
+        for spec in BASIC.iter().chain(SCIENTIFIC) {
+            assert_eq!(ButtonAction::for_message(&spec.action.message()), Some(spec.action));
+        }
+        assert_eq!(ButtonAction::for_message(&Message::Backspace), None);
+    }
+
+    #[test]
+    fn basic_has_every_digit() {
+        for d in '0'..='9' {
+            assert!(BASIC.iter().any(|k| k.action == ButtonAction::Digit(d)), "missing {}", d);
+        }
+    }
+
+    #[test]
+    fn rows_are_ordered() {
+        let r = rows(BASIC);
+        assert_eq!(r.len(), 5);
+        let labels: Vec<&str> = r[4].iter().map(|k| k.label.as_ref()).collect();
+        assert_eq!(labels, ["0", ".", "="]);
+    }
+
+    #[test]
+    fn default_classes_match_builtin_layouts() {
+        for spec in BASIC.iter().chain(SCIENTIFIC) {
+            assert_eq!(default_class(spec.action), spec.class, "{}", spec.label);
+        }
+    }
+
+    #[test]
+    fn action_codes_round_trip() {
+        for (code, action) in ACTION_CODES {
+            assert_eq!(action.code(), *code);
+            assert_eq!(ButtonAction::from_code(code), Ok(*action));
+        }
+        for spec in BASIC.iter().chain(SCIENTIFIC) {
+            assert!(!spec.action.code().is_empty(), "{} has no code", spec.label);
+        }
+        assert!(ButtonAction::from_code("frobnicate").is_err());
+    }
+
+    #[test]
+    fn validate_rejects_bad_layouts() {
+        assert!(validate(BASIC).is_ok());
+        assert!(validate(SCIENTIFIC).is_ok());
+
+        let mut overlap = BASIC.to_vec();
+        overlap.push(KeySpec::custom("x".into(), ButtonAction::Equals, 1, 4, 1));
+        assert!(validate(&overlap).unwrap_err().contains("overlaps"));
+
+        let blank = [KeySpec::custom(" ".into(), ButtonAction::Equals, 0, 0, 1)];
+        assert!(validate(&blank).is_err());
+
+        let too_wide = [KeySpec::custom("=".into(), ButtonAction::Equals, MAX_GRID - 1, 0, 2)];
+        assert!(validate(&too_wide).is_err());
+
+        // Holes are fine: removing a key just leaves a gap.
+        assert!(validate(&BASIC[1..]).is_ok());
+    }
+
+    #[test]
+    fn keypad_needs_basic_keys() {
+        assert!(Keypad::default().validate().is_ok());
+        let no_scientific = Keypad { basic: BASIC.to_vec(), scientific: Vec::new() };
+        assert!(no_scientific.validate().is_ok());
+        let no_basic = Keypad { basic: Vec::new(), scientific: SCIENTIFIC.to_vec() };
+        assert!(no_basic.validate().unwrap_err().starts_with("basic:"));
+    }
+}
@@ -1,3 +1,13 @@
+// Parts of these are only reachable from the GTK frontend; the allows keep
+// headless builds quiet about them without hiding dead code elsewhere.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub mod a11y;
+#[cfg(feature = "gui")]
 pub mod builder;
+#[cfg(feature = "gui")]
+pub mod commands;
 pub mod keyboard;
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub mod layout;
+#[cfg(feature = "gui")]
 pub mod navigation;
@@ -12,25 +12,35 @@ pub struct NavButton {
 pub fn navigate(nav: &[NavButton], dir: Direction, scientific: bool) {
     let visible: Vec<&NavButton> = nav.iter().filter(|b| !b.scientific || scientific).collect();
     let current = visible.iter().find(|b| b.button.has_focus());
+    // The basic grid sits to the right of the scientific one, whose width
+    // depends on the user's keypad layout.
+    let sci_width = nav.iter().filter(|b| b.scientific).map(|b| b.col + 1).max().unwrap_or(0);
+    let eff_pos = |b: &NavButton| {
+        if !b.scientific && scientific {
+            (b.col + sci_width, b.row)
+        } else {
+            (b.col, b.row)
+        }
+    };
     if let Some(cur) = current {
-        let (cc, cr) = eff_pos(cur, scientific);
+        let (cc, cr) = eff_pos(cur);
         let target = match dir {
             Direction::Left => visible
                 .iter()
-                .filter(|b| eff_pos(b, scientific).1 == cr && eff_pos(b, scientific).0 < cc)
-                .max_by_key(|b| eff_pos(b, scientific).0),
+                .filter(|b| eff_pos(b).1 == cr && eff_pos(b).0 < cc)
+                .max_by_key(|b| eff_pos(b).0),
             Direction::Right => visible
                 .iter()
-                .filter(|b| eff_pos(b, scientific).1 == cr && eff_pos(b, scientific).0 > cc)
-                .min_by_key(|b| eff_pos(b, scientific).0),
+                .filter(|b| eff_pos(b).1 == cr && eff_pos(b).0 > cc)
+                .min_by_key(|b| eff_pos(b).0),
             Direction::Up => visible
                 .iter()
-                .filter(|b| eff_pos(b, scientific).0 == cc && eff_pos(b, scientific).1 < cr)
-                .max_by_key(|b| eff_pos(b, scientific).1),
+                .filter(|b| eff_pos(b).0 == cc && eff_pos(b).1 < cr)
+                .max_by_key(|b| eff_pos(b).1),
             Direction::Down => visible
                 .iter()
-                .filter(|b| eff_pos(b, scientific).0 == cc && eff_pos(b, scientific).1 > cr)
-                .min_by_key(|b| eff_pos(b, scientific).1),
+                .filter(|b| eff_pos(b).0 == cc && eff_pos(b).1 > cr)
+                .min_by_key(|b| eff_pos(b).1),
         };
         if let Some(t) = target {
             t.button.grab_focus();
@@ -46,13 +56,3 @@ pub fn activate_focused(nav: &[NavButton], scientific: bool) {
         b.button.clicked();
     }
 }
-
-fn eff_pos(b: &NavButton, scientific: bool) -> (usize, usize) {
-    if b.scientific {
-        (b.col, b.row)
-    } else if scientific {
-        (b.col + 3, b.row)
-    } else {
-        (b.col, b.row)
-    }
-}